@@ -0,0 +1,316 @@
+/*!
+# parlance-derive
+
+`#[derive(Parse)]`, re-exported by `parlance` with its `derive` feature.
+
+*/
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, Fields, LitStr,
+    Type,
+};
+
+/// Implement `Parser<I, Self, E, F>` for any `I: Input` & `E: ExpectError<I>`.
+/// Types which hold some of their input, such as a `Span`, fix `I` with
+/// `#[parse(input = ...)]`.
+///
+/// Struct fields are parsed in order, each by the inherent `parse` function of
+/// its type unless given a parser with `#[parse(with = ...)]`. Enum variants
+/// are tried in order, as alternatives.
+///
+/// | Attribute                   | On               | Meaning                                  |
+/// |-----------------------------|------------------|------------------------------------------|
+/// | `#[parse(sep = expr)]`      | struct, enum     | A parser matched between elements        |
+/// | `#[parse(input = Type)]`    | struct, enum     | The input type `I`, rather than any      |
+/// | `#[parse(failure = Type)]`  | struct, enum     | The failure type `F`, `Never` by default |
+/// | `#[parse(tag = "...")]`     | field, variant   | Text matched before the field/variant    |
+/// | `#[parse(with = expr)]`     | field            | The parser for the field                 |
+/// | `#[parse(with = expr)]`     | variant          | The parser for a variant's only field, or matched for a unit variant |
+#[proc_macro_derive(Parse, attributes(parse))]
+pub fn derive_parse(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    sep: Option<Expr>,
+    input: Option<Type>,
+    failure: Option<Type>,
+    tag: Option<LitStr>,
+    with: Option<Expr>,
+}
+impl Options {
+    fn from_attributes(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("parse")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("sep") {
+                    options.sep = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("input") {
+                    options.input = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("failure") {
+                    options.failure = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("tag") {
+                    options.tag = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("with") {
+                    options.with = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `sep`, `input`, `failure`, `tag` or `with`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+    fn container(attrs: &[Attribute]) -> syn::Result<Self> {
+        let options = Self::from_attributes(attrs)?;
+        if let Some(tag) = &options.tag {
+            return Err(Error::new(tag.span(), "`tag` applies to fields & variants"));
+        }
+        if let Some(with) = &options.with {
+            return Err(Error::new(
+                with.span(),
+                "`with` applies to fields & variants",
+            ));
+        }
+        Ok(options)
+    }
+    fn element(attrs: &[Attribute]) -> syn::Result<Self> {
+        let options = Self::from_attributes(attrs)?;
+        if let Some(sep) = &options.sep {
+            return Err(Error::new(sep.span(), "`sep` applies to structs & enums"));
+        }
+        if let Some(input) = &options.input {
+            return Err(Error::new(
+                input.span(),
+                "`input` applies to structs & enums",
+            ));
+        }
+        if let Some(failure) = &options.failure {
+            return Err(Error::new(
+                failure.span(),
+                "`failure` applies to structs & enums",
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// One step of parsing a struct or variant.
+enum Element {
+    Tag(LitStr),
+    /// Parse a field into the named variable, with an explicit parser or by
+    /// its type.
+    Field(syn::Ident, Result<Expr, Type>),
+    /// Parse & discard.
+    Skip(Expr),
+}
+
+fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let options = Options::container(&input.attrs)?;
+    let name = &input.ident;
+    let failure = match &options.failure {
+        Some(failure) => quote!(#failure),
+        None => quote!(::parlance::parse::Never),
+    };
+    let input_ty = match &options.input {
+        Some(input) => quote!(#input),
+        None => quote!(__I),
+    };
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let elements = field_elements(&data.fields, Vec::new())?;
+            sequence(&elements, &options, construct(quote!(Self), &data.fields))
+        }
+        Data::Enum(data) => {
+            if data.variants.is_empty() {
+                return Err(Error::new(
+                    name.span(),
+                    "an enum must have variants to be parsed",
+                ));
+            }
+            let alternatives = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_options = Options::element(&variant.attrs)?;
+                    let mut elements = Vec::new();
+                    if let Some(tag) = variant_options.tag {
+                        elements.push(Element::Tag(tag));
+                    }
+                    let elements = match (variant_options.with, &variant.fields) {
+                        (None, fields) => field_elements(fields, elements)?,
+                        (Some(with), Fields::Unit) => {
+                            elements.push(Element::Skip(with));
+                            elements
+                        }
+                        (Some(with), fields) if fields.len() == 1 => {
+                            elements.push(Element::Field(format_ident!("__field0"), Ok(with)));
+                            elements
+                        }
+                        (Some(with), _) => {
+                            return Err(Error::new(
+                                with.span(),
+                                "`with` on a variant needs at most one field",
+                            ))
+                        }
+                    };
+                    if elements.is_empty() {
+                        return Err(Error::new(
+                            variant.span(),
+                            "a unit variant needs `#[parse(tag = ...)]` or `#[parse(with = ...)]`",
+                        ));
+                    }
+                    let ident = &variant.ident;
+                    let body = sequence(
+                        &elements,
+                        &options,
+                        construct(quote!(Self::#ident), &variant.fields),
+                    );
+                    Ok(quote! {
+                        let __alternative = |input: &#input_ty| -> ::parlance::parse::ParserResult<#input_ty, Self, __E, #failure> {
+                            #body
+                        };
+                        match __alternative(input) {
+                            Ok(x) => return Ok(x),
+                            Err(::parlance::parse::ParserError::Error(e)) => {
+                                __error = Some(match __error {
+                                    Some(previous) => ::parlance::parse::ExpectError::merge(previous, e),
+                                    None => e,
+                                });
+                            }
+                            Err(::parlance::parse::ParserError::Failure(f)) => {
+                                return Err(::parlance::parse::ParserError::Failure(f));
+                            }
+                        }
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! {
+                let mut __error = None;
+                #(#alternatives)*
+                Err(::parlance::parse::ParserError::Error(
+                    __error.expect("Every alternative failed"),
+                ))
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "unions can't be parsed",
+            ))
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut parser_generics = input.generics.clone();
+    parser_generics.params.insert(
+        0,
+        syn::parse_quote!(__E: ::parlance::parse::ExpectError<#input_ty>),
+    );
+    if options.input.is_none() {
+        parser_generics
+            .params
+            .insert(0, syn::parse_quote!(__I: ::parlance::input::Input));
+    }
+    let (parser_impl_generics, _, _) = parser_generics.split_for_impl();
+    let fn_generics = match options.input {
+        Some(_) => quote!(<__E: ::parlance::parse::ExpectError<#input_ty>>),
+        None => quote! {
+            <__I: ::parlance::input::Input, __E: ::parlance::parse::ExpectError<__I>>
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub fn parse #fn_generics(
+                input: &#input_ty,
+            ) -> ::parlance::parse::ParserResult<#input_ty, Self, __E, #failure> {
+                #body
+            }
+        }
+        impl #parser_impl_generics ::parlance::parse::Parser<#input_ty, Self, __E, #failure>
+            for #name #ty_generics #where_clause
+        {
+            fn parse(&self, input: &#input_ty) -> ::parlance::parse::ParserResult<#input_ty, Self, __E, #failure> {
+                Self::parse(input)
+            }
+        }
+    })
+}
+
+fn field_elements(fields: &Fields, mut elements: Vec<Element>) -> syn::Result<Vec<Element>> {
+    for (i, field) in fields.iter().enumerate() {
+        let options = Options::element(&field.attrs)?;
+        if let Some(tag) = options.tag {
+            elements.push(Element::Tag(tag));
+        }
+        let parser = match options.with {
+            Some(with) => Ok(with),
+            None => Err(field.ty.clone()),
+        };
+        elements.push(Element::Field(format_ident!("__field{}", i), parser));
+    }
+    Ok(elements)
+}
+
+/// Parse each element from `input` in turn, then build the output.
+fn sequence(elements: &[Element], options: &Options, output: TokenStream) -> TokenStream {
+    let steps = elements.iter().enumerate().map(|(i, element)| {
+        let sep = match (&options.sep, i) {
+            (Some(sep), 1..) => quote! {
+                let (_, __remaining) = ::parlance::parse::Parser::parse(&(#sep), &__remaining)?;
+            },
+            _ => quote!(),
+        };
+        let step = match element {
+            Element::Tag(tag) => quote! {
+                let __remaining = match ::parlance::input::Input::pop(&__remaining, &#tag) {
+                    Some((_, remaining)) => remaining,
+                    None => {
+                        return Err(::parlance::parse::ParserError::Error(
+                            ::parlance::parse::ExpectError::expected(
+                                &__remaining,
+                                ::parlance::parse::Expectation::tag(#tag),
+                            ),
+                        ))
+                    }
+                };
+            },
+            Element::Field(var, Ok(with)) => quote! {
+                let (#var, __remaining) = ::parlance::parse::Parser::parse(&(#with), &__remaining)?;
+            },
+            Element::Field(var, Err(ty)) => quote! {
+                let (#var, __remaining) = <#ty>::parse(&__remaining)?;
+            },
+            Element::Skip(with) => quote! {
+                let (_, __remaining) = ::parlance::parse::Parser::parse(&(#with), &__remaining)?;
+            },
+        };
+        quote!(#sep #step)
+    });
+    quote! {
+        let __remaining = ::std::clone::Clone::clone(input);
+        #(#steps)*
+        Ok((#output, __remaining))
+    }
+}
+
+fn construct(path: TokenStream, fields: &Fields) -> TokenStream {
+    let vars = (0..fields.len()).map(|i| format_ident!("__field{}", i));
+    match fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|f| &f.ident);
+            quote!(#path { #(#names: #vars),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#vars),*)),
+        Fields::Unit => path,
+    }
+}
@@ -0,0 +1,483 @@
+/*!
+# Expressions
+
+- [`expression`] builds a precedence-climbing (Pratt) parser from an atom and
+  a set of prefix, infix & postfix operators, each with a binding power. Higher
+  powers bind more tightly.
+- Operators are folded into the output with callbacks, so any AST can be built.
+- [`Expression::grouping`], [`Expression::indexed`] & [`Expression::ternary`]
+  register operators which contain a nested expression, such as `(a)`, `a[b]`
+  & `a ? b : c`.
+- [`chainl1`] & [`chainr1`] handle a single level of binary operator.
+
+If the operand following an operator can't be parsed, the operator is not
+consumed, and parsing stops before it.
+
+*/
+
+use std::rc::Rc;
+
+use crate::parse::{Parser, ParserError, ParserResult};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`
+    Right,
+    /// `a == b == c` is not allowed, and parsing stops before the second `==`.
+    Neither,
+}
+
+type Boxed<'a, I, T, E, F> = Box<dyn Fn(&I) -> ParserResult<I, T, E, F> + 'a>;
+type Unary<'a, O> = Box<dyn FnOnce(O) -> O + 'a>;
+type Binary<'a, O> = Box<dyn FnOnce(O, O) -> O + 'a>;
+/// A parser for a delimiter, whose output is ignored.
+type Token<'a, I, E, F> = Boxed<'a, I, (), E, F>;
+type Prefix<'a, I, O, E, F> = Boxed<'a, I, Unary<'a, O>, E, F>;
+
+/// The tokens surrounding a nested expression.
+struct Delimiters<'a, I, E, F> {
+    open: Token<'a, I, E, F>,
+    close: Token<'a, I, E, F>,
+}
+impl<'a, I: 'a, E: 'a, F: 'a> Delimiters<'a, I, E, F> {
+    fn new<OpenOutput: 'a, CloseOutput: 'a>(
+        open: impl Parser<I, OpenOutput, E, F> + 'a,
+        close: impl Parser<I, CloseOutput, E, F> + 'a,
+    ) -> Self {
+        Self {
+            open: boxed(open.map(|_| ())),
+            close: boxed(close.map(|_| ())),
+        }
+    }
+}
+
+enum Postfix<'a, I, O, E, F> {
+    Operator(Boxed<'a, I, Unary<'a, O>, E, F>),
+    Indexed {
+        delimiters: Delimiters<'a, I, E, F>,
+        fold: Box<dyn Fn(O, O) -> O + 'a>,
+    },
+}
+
+enum Infix<'a, I, O, E, F> {
+    Operator(Associativity, Boxed<'a, I, Binary<'a, O>, E, F>),
+    Ternary {
+        delimiters: Delimiters<'a, I, E, F>,
+        fold: Box<dyn Fn(O, O, O) -> O + 'a>,
+    },
+}
+impl<I, O, E, F> Infix<'_, I, O, E, F> {
+    fn associativity(&self) -> Associativity {
+        match self {
+            Infix::Operator(associativity, _) => *associativity,
+            Infix::Ternary { .. } => Associativity::Right,
+        }
+    }
+}
+
+/// Erase the type of a parser.
+fn boxed<'a, I, O, E, F>(p: impl Parser<I, O, E, F> + 'a) -> Boxed<'a, I, O, E, F> {
+    Box::new(move |input: &I| p.parse(input))
+}
+
+/// Treat a recoverable error as `None`, so that the next option can be tried.
+fn attempt<I, O, E, F>(result: ParserResult<I, O, E, F>) -> Result<Option<(O, I)>, F> {
+    match result {
+        Ok(x) => Ok(Some(x)),
+        Err(ParserError::Error(_)) => Ok(None),
+        Err(ParserError::Failure(f)) => Err(f),
+    }
+}
+
+/// A precedence-climbing expression parser, created by [`expression`].
+pub struct Expression<'a, I, O, E, F> {
+    atom: Boxed<'a, I, O, E, F>,
+    groups: Vec<Delimiters<'a, I, E, F>>,
+    prefix: Vec<(u16, Prefix<'a, I, O, E, F>)>,
+    postfix: Vec<(u16, Postfix<'a, I, O, E, F>)>,
+    infix: Vec<(u16, Infix<'a, I, O, E, F>)>,
+}
+
+/// Start building an expression parser from the parser for its operands.
+pub fn expression<'a, I, O, E, F>(
+    atom: impl Parser<I, O, E, F> + 'a,
+) -> Expression<'a, I, O, E, F> {
+    Expression {
+        atom: boxed(atom),
+        groups: Vec::new(),
+        prefix: Vec::new(),
+        postfix: Vec::new(),
+        infix: Vec::new(),
+    }
+}
+
+impl<'a, I: 'a, O: 'a, E: 'a, F: 'a> Expression<'a, I, O, E, F> {
+    /// An operator before its operand, such as `-a`.
+    pub fn prefix<OpOutput: 'a>(
+        mut self,
+        power: u16,
+        op: impl Parser<I, OpOutput, E, F> + 'a,
+        fold: impl Fn(OpOutput, O) -> O + 'a,
+    ) -> Self {
+        let fold = Rc::new(fold);
+        let op = op.map(move |o| {
+            let fold = fold.clone();
+            Box::new(move |x| fold(o, x)) as Unary<'a, O>
+        });
+        self.prefix.push((power, boxed(op)));
+        self
+    }
+    /// An operator between its operands, such as `a + b`.
+    pub fn infix<OpOutput: 'a>(
+        mut self,
+        associativity: Associativity,
+        power: u16,
+        op: impl Parser<I, OpOutput, E, F> + 'a,
+        fold: impl Fn(O, OpOutput, O) -> O + 'a,
+    ) -> Self {
+        let fold = Rc::new(fold);
+        let op = op.map(move |o| {
+            let fold = fold.clone();
+            Box::new(move |l, r| fold(l, o, r)) as Binary<'a, O>
+        });
+        self.infix
+            .push((power, Infix::Operator(associativity, boxed(op))));
+        self
+    }
+    /// An operator after its operand, such as `a?`.
+    pub fn postfix<OpOutput: 'a>(
+        mut self,
+        power: u16,
+        op: impl Parser<I, OpOutput, E, F> + 'a,
+        fold: impl Fn(O, OpOutput) -> O + 'a,
+    ) -> Self {
+        let fold = Rc::new(fold);
+        let op = op.map(move |o| {
+            let fold = fold.clone();
+            Box::new(move |x| fold(x, o)) as Unary<'a, O>
+        });
+        self.postfix.push((power, Postfix::Operator(boxed(op))));
+        self
+    }
+    /// A nested expression used as an operand, such as `(a)`.
+    pub fn grouping<OpenOutput: 'a, CloseOutput: 'a>(
+        mut self,
+        open: impl Parser<I, OpenOutput, E, F> + 'a,
+        close: impl Parser<I, CloseOutput, E, F> + 'a,
+    ) -> Self {
+        self.groups.push(Delimiters::new(open, close));
+        self
+    }
+    /// A postfix operator enclosing a nested expression, such as `a[b]`.
+    pub fn indexed<OpenOutput: 'a, CloseOutput: 'a>(
+        mut self,
+        power: u16,
+        open: impl Parser<I, OpenOutput, E, F> + 'a,
+        close: impl Parser<I, CloseOutput, E, F> + 'a,
+        fold: impl Fn(O, O) -> O + 'a,
+    ) -> Self {
+        self.postfix.push((
+            power,
+            Postfix::Indexed {
+                delimiters: Delimiters::new(open, close),
+                fold: Box::new(fold),
+            },
+        ));
+        self
+    }
+    /// A right-associative operator of three operands, such as `a ? b : c`.
+    pub fn ternary<OpenOutput: 'a, CloseOutput: 'a>(
+        mut self,
+        power: u16,
+        open: impl Parser<I, OpenOutput, E, F> + 'a,
+        close: impl Parser<I, CloseOutput, E, F> + 'a,
+        fold: impl Fn(O, O, O) -> O + 'a,
+    ) -> Self {
+        self.infix.push((
+            power,
+            Infix::Ternary {
+                delimiters: Delimiters::new(open, close),
+                fold: Box::new(fold),
+            },
+        ));
+        self
+    }
+}
+
+/// The binding powers to the left & right of an infix operator.
+fn binding_power(power: u16, associativity: Associativity) -> (u32, u32) {
+    let power = 2 * power as u32;
+    match associativity {
+        Associativity::Left | Associativity::Neither => (power, power + 1),
+        Associativity::Right => (power + 1, power),
+    }
+}
+
+impl<I, O, E, F> Expression<'_, I, O, E, F> {
+    /// Parse `open`, a nested expression, then `close`.
+    fn enclosed(
+        &self,
+        delimiters: &Delimiters<'_, I, E, F>,
+        input: &I,
+    ) -> Result<Option<(O, I)>, F> {
+        let Some((_, remaining)) = attempt((delimiters.open)(input))? else {
+            return Ok(None);
+        };
+        let Some((inner, remaining)) = attempt(self.parse_bp(&remaining, 0))? else {
+            return Ok(None);
+        };
+        let Some((_, remaining)) = attempt((delimiters.close)(&remaining))? else {
+            return Ok(None);
+        };
+        Ok(Some((inner, remaining)))
+    }
+
+    /// An operand, with any prefix operators applied.
+    fn parse_operand(&self, input: &I) -> ParserResult<I, O, E, F> {
+        for (power, op) in self.prefix.iter() {
+            let Some((fold, remaining)) = attempt(op(input)).map_err(ParserError::Failure)? else {
+                continue;
+            };
+            let operand = self.parse_bp(&remaining, 2 * *power as u32 + 1);
+            if let Some((x, remaining)) = attempt(operand).map_err(ParserError::Failure)? {
+                return Ok((fold(x), remaining));
+            }
+        }
+        for delimiters in self.groups.iter() {
+            if let Some(x) = self
+                .enclosed(delimiters, input)
+                .map_err(ParserError::Failure)?
+            {
+                return Ok(x);
+            }
+        }
+
+        (self.atom)(input)
+    }
+
+    /// Parse an expression containing only operators which bind at least as
+    /// tightly as `min_bp`.
+    fn parse_bp(&self, input: &I, min_bp: u32) -> ParserResult<I, O, E, F> {
+        let (mut lhs, mut remaining) = self.parse_operand(input)?;
+        // The power of the last non-associative operator, which may not be
+        // followed by another of the same power.
+        let mut neither: Option<u16> = None;
+
+        'operators: loop {
+            for (power, postfix) in self.postfix.iter() {
+                if (2 * *power as u32) < min_bp {
+                    continue;
+                }
+                match postfix {
+                    Postfix::Operator(op) => {
+                        if let Some((fold, r)) =
+                            attempt(op(&remaining)).map_err(ParserError::Failure)?
+                        {
+                            lhs = fold(lhs);
+                            remaining = r;
+                            continue 'operators;
+                        }
+                    }
+                    Postfix::Indexed { delimiters, fold } => {
+                        if let Some((inner, r)) = self
+                            .enclosed(delimiters, &remaining)
+                            .map_err(ParserError::Failure)?
+                        {
+                            lhs = fold(lhs, inner);
+                            remaining = r;
+                            continue 'operators;
+                        }
+                    }
+                }
+            }
+
+            for (power, infix) in self.infix.iter() {
+                let associativity = infix.associativity();
+                let (left_bp, right_bp) = binding_power(*power, associativity);
+                if left_bp < min_bp
+                    || (associativity == Associativity::Neither && neither == Some(*power))
+                {
+                    continue;
+                }
+                match infix {
+                    Infix::Operator(_, op) => {
+                        let Some((fold, r)) =
+                            attempt(op(&remaining)).map_err(ParserError::Failure)?
+                        else {
+                            continue;
+                        };
+                        let Some((rhs, r)) =
+                            attempt(self.parse_bp(&r, right_bp)).map_err(ParserError::Failure)?
+                        else {
+                            continue;
+                        };
+                        lhs = fold(lhs, rhs);
+                        remaining = r;
+                    }
+                    Infix::Ternary { delimiters, fold } => {
+                        let Some((middle, r)) = self
+                            .enclosed(delimiters, &remaining)
+                            .map_err(ParserError::Failure)?
+                        else {
+                            continue;
+                        };
+                        let Some((rhs, r)) =
+                            attempt(self.parse_bp(&r, right_bp)).map_err(ParserError::Failure)?
+                        else {
+                            continue;
+                        };
+                        lhs = fold(lhs, middle, rhs);
+                        remaining = r;
+                    }
+                }
+                neither = (associativity == Associativity::Neither).then_some(*power);
+                continue 'operators;
+            }
+
+            break;
+        }
+
+        Ok((lhs, remaining))
+    }
+}
+impl<I, O, E, F> Parser<I, O, E, F> for Expression<'_, I, O, E, F> {
+    fn parse(&self, input: &I) -> ParserResult<I, O, E, F> {
+        self.parse_bp(input, 0)
+    }
+}
+
+/// One or more `p` separated by `op`, folded from the left.
+pub fn chainl1<I, O, OpOutput, E, F>(
+    p: impl Parser<I, O, E, F>,
+    op: impl Parser<I, OpOutput, E, F>,
+    fold: impl Fn(O, OpOutput, O) -> O,
+) -> impl Parser<I, O, E, F> {
+    move |input: &I| {
+        let (mut lhs, mut remaining) = p.parse(input)?;
+        loop {
+            let Some((o, r)) = attempt(op.parse(&remaining)).map_err(ParserError::Failure)? else {
+                break;
+            };
+            let Some((rhs, r)) = attempt(p.parse(&r)).map_err(ParserError::Failure)? else {
+                break;
+            };
+            lhs = fold(lhs, o, rhs);
+            remaining = r;
+        }
+
+        Ok((lhs, remaining))
+    }
+}
+
+/// One or more `p` separated by `op`, folded from the right.
+pub fn chainr1<I, O, OpOutput, E, F>(
+    p: impl Parser<I, O, E, F>,
+    op: impl Parser<I, OpOutput, E, F>,
+    fold: impl Fn(O, OpOutput, O) -> O,
+) -> impl Parser<I, O, E, F> {
+    move |input: &I| {
+        let (first, mut remaining) = p.parse(input)?;
+        let mut operands = vec![first];
+        let mut ops = Vec::new();
+        loop {
+            let Some((o, r)) = attempt(op.parse(&remaining)).map_err(ParserError::Failure)? else {
+                break;
+            };
+            let Some((rhs, r)) = attempt(p.parse(&r)).map_err(ParserError::Failure)? else {
+                break;
+            };
+            ops.push(o);
+            operands.push(rhs);
+            remaining = r;
+        }
+
+        let mut output = operands
+            .pop()
+            .expect("There should be at least one operand");
+        while let (Some(o), Some(lhs)) = (ops.pop(), operands.pop()) {
+            output = fold(lhs, o, output);
+        }
+
+        Ok((output, remaining))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parse::{Never, NotFound},
+        primitives::{numbers::digits, tag::tag},
+    };
+
+    type Result<'a> = ParserResult<&'a str, String, NotFound>;
+
+    fn number<'a>(s: &&'a str) -> Result<'a> {
+        digits.map(|n: &str| n.to_string()).parse(s)
+    }
+
+    fn binary(l: String, op: &str, r: String) -> String {
+        format!("({} {} {})", op, l, r)
+    }
+
+    fn calculator<'a>() -> Expression<'a, &'a str, String, NotFound, Never> {
+        expression(number)
+            .grouping("(", ")")
+            .ternary(0, "?", ":", |c, t, f| format!("(if {} {} {})", c, t, f))
+            .infix(Associativity::Neither, 1, "==", binary)
+            .infix(Associativity::Left, 2, "+", binary)
+            .infix(Associativity::Left, 2, "-", binary)
+            .infix(Associativity::Left, 3, "*", binary)
+            .prefix(4, "-", |_, x| format!("(neg {})", x))
+            .infix(Associativity::Right, 5, "^", binary)
+            .postfix(6, "!", |x, _| format!("(fact {})", x))
+            .indexed(7, "[", "]", |x, i| format!("(index {} {})", x, i))
+    }
+
+    fn parse(s: &str) -> (String, &str) {
+        calculator().parse(&s).unwrap()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(parse("1+2*3"), ("(+ 1 (* 2 3))".into(), ""));
+        assert_eq!(parse("1*2+3"), ("(+ (* 1 2) 3)".into(), ""));
+        assert_eq!(parse("(1+2)*3"), ("(* (+ 1 2) 3)".into(), ""));
+        assert_eq!(parse("1-2-3"), ("(- (- 1 2) 3)".into(), ""));
+        assert_eq!(parse("2^3^4"), ("(^ 2 (^ 3 4))".into(), ""));
+    }
+
+    #[test]
+    fn prefix_and_postfix() {
+        assert_eq!(parse("-1*2"), ("(* (neg 1) 2)".into(), ""));
+        assert_eq!(parse("-2^2"), ("(neg (^ 2 2))".into(), ""));
+        assert_eq!(parse("-3!"), ("(neg (fact 3))".into(), ""));
+        assert_eq!(parse("1[2+3]!"), ("(fact (index 1 (+ 2 3)))".into(), ""));
+    }
+
+    #[test]
+    fn mixfix() {
+        assert_eq!(parse("1?2:3?4:5"), ("(if 1 2 (if 3 4 5))".into(), ""));
+        assert_eq!(parse("1==2?3+4:5"), ("(if (== 1 2) (+ 3 4) 5)".into(), ""));
+    }
+
+    #[test]
+    fn stops_before_unusable_operators() {
+        assert_eq!(parse("1==2==3"), ("(== 1 2)".into(), "==3"));
+        assert_eq!(parse("1+"), ("1".into(), "+"));
+        assert_eq!(parse("1[2"), ("1".into(), "[2"));
+        assert!(calculator().parse(&"+1").is_err());
+    }
+
+    #[test]
+    fn chains() {
+        let left = chainl1(number, tag("-"), binary);
+        assert_eq!(left.parse(&"1-2-3"), Ok(("(- (- 1 2) 3)".into(), "")));
+
+        let right = chainr1(number, tag("^"), binary);
+        assert_eq!(right.parse(&"1^2^3^"), Ok(("(^ 1 (^ 2 3))".into(), "^")));
+        assert_eq!(right.parse(&"1"), Ok(("1".into(), "")));
+    }
+}
@@ -5,7 +5,9 @@ macro_rules! fuse (
             {
                 use $crate::parse::{Sequence, FuseSequence};
                 fn fuse_impl<I: $crate::input::Input>(s: &I) -> $crate::parse::ParserResult<I, I> {
-                    if let Ok((len, _)) = $x.output_len().parse(&s.as_str()) {
+                    let result: $crate::parse::ParserResult<&str, usize> =
+                        $x.output_len().parse(&s.as_str());
+                    if let Ok((len, _)) = result {
                         Ok(s.split_at(len))
                     } else {
                         Err($crate::parse::ParserError::Error($crate::parse::NotFound))
@@ -27,10 +29,9 @@ mod test {
     fn simple() {
         assert_eq!(fuse!(("a", "b")).parse(&"abc"), Ok(("ab", "c")));
 
-        fn foo<'a, 'b>(s: &'a &'b str) -> ParserResult<&'b str, &'b str> {
-            return Ok(("", *s));
+        fn foo<'b>(s: &&'b str) -> ParserResult<&'b str, &'b str> {
+            Ok(("", *s))
         }
         assert_eq!(fuse!(("a", "b", foo)).parse(&"abc"), Ok(("ab", "c")))
     }
-
 }
@@ -0,0 +1,298 @@
+/*!
+# PEG grammars
+
+[`grammar!`][crate::grammar] declares a struct of mutually recursive rules,
+written in PEG notation. Each rule is a [`Deferred`][super::Deferred] parser.
+
+```
+use parlance::{grammar, parse::{NotFound, Parser}, primitives::numbers::digits};
+
+grammar! {
+    pub struct Arith<&'static str, NotFound> {
+        expr: i64 = { l:term "+" r:expr => { l + r } / term }
+        term: i64 = { "(" e:expr ")" => { e } / n:{ digits } => { n.parse().unwrap() } }
+    }
+}
+
+let arith = Arith::new();
+assert_eq!(arith.expr.parse(&"1+(2+3)"), Ok((6, "")));
+```
+
+## Syntax
+
+`struct Name<Input, Error, Failure>` declares the grammar. `Failure` may be
+omitted, and defaults to [`Never`][crate::parse::Never]. `Error` must implement
+[`ExpectError`][crate::parse::ExpectError].
+
+Each rule is written `name: Output = { ... }`, and its body is made of:
+
+| Syntax            | Meaning                                                  |
+|-------------------|----------------------------------------------------------|
+| `"text"`          | [`tag`][crate::primitives::tag::tag]                     |
+| `rule`            | Another rule, or any `Clone` parser in scope             |
+| `{ expr }`        | Any Rust expression giving a parser                      |
+| `( ... )`         | Grouping                                                 |
+| `a b`             | Sequence, using [`Sequence::and`][crate::parse::Sequence] |
+| `a / b`           | Ordered choice, using [`Choice::or`][crate::parse::Choice] |
+| `a*`, `a+`, `a?`  | Zero or more, one or more ([`many`][fn@super::many]), optional |
+| `&a`, `!a`        | [`peek`][super::peek] & [`not`][super::not]              |
+| `label:a`         | Bind the output of `a` for use in an action              |
+| `... => { ... }`  | An action, computing the output of an alternative        |
+
+An alternative without an action outputs the output of its only item, or the
+input it consumed if it has several.
+
+Sequences & choices are built from nested pairs, so aren't limited in length.
+Each rule's body is expanded separately, so `recursion_limit` only needs
+raising for very long rules.
+
+Rules refer to each other with [`weak`][super::Deferred::weak] handles: the
+grammar struct must outlive its rules' use.
+
+*/
+
+#[macro_export]
+macro_rules! grammar {
+    // Split alternatives on `/`.
+    (@choice $t:tt [$($alts:tt)*] [$($current:tt)*] / $($rest:tt)*) => {
+        $crate::grammar!(@choice $t [$($alts)* [$($current)*]] [] $($rest)*)
+    };
+    (@choice $t:tt [$($alts:tt)*] [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::grammar!(@choice $t [$($alts)*] [$($current)* $next] $($rest)*)
+    };
+    (@choice $t:tt [$($alts:tt)*] [$($current:tt)*]) => {
+        $crate::grammar!(@or $t $($alts)* [$($current)*])
+    };
+    (@or $t:tt [$($alt:tt)*]) => {
+        $crate::grammar!(@seq $t [] $($alt)*)
+    };
+    (@or $t:tt [$($alt:tt)*] $($rest:tt)+) => {
+        $crate::parse::Choice::or((
+            $crate::grammar!(@seq $t [] $($alt)*),
+            $crate::grammar!(@or $t $($rest)+),
+        ))
+    };
+
+    // Collect the items of an alternative as `[label prefix suffix atom]`.
+    (@seq $t:tt [$($items:tt)+] => $action:block) => {
+        $crate::parse::Parser::map(
+            $crate::grammar!(@and $t $($items)+),
+            |$crate::grammar!(@pattern $($items)+)| $action,
+        )
+    };
+    (@seq $t:tt [$item:tt]) => {
+        $crate::grammar!(@item $t $item)
+    };
+    (@seq $t:tt [$($items:tt)+]) => {
+        $crate::combinators::lookahead::recognize($crate::grammar!(@and $t $($items)+))
+    };
+    (@seq $t:tt [$($items:tt)*] $label:ident : $($rest:tt)+) => {
+        $crate::grammar!(@prefix $t [$($items)*] $label $($rest)+)
+    };
+    (@seq $t:tt [$($items:tt)*] $($rest:tt)+) => {
+        $crate::grammar!(@prefix $t [$($items)*] _ $($rest)+)
+    };
+    (@prefix $t:tt $items:tt $label:tt & $($rest:tt)+) => {
+        $crate::grammar!(@suffix $t $items $label peek $($rest)+)
+    };
+    (@prefix $t:tt $items:tt $label:tt ! $($rest:tt)+) => {
+        $crate::grammar!(@suffix $t $items $label not $($rest)+)
+    };
+    (@prefix $t:tt $items:tt $label:tt $($rest:tt)+) => {
+        $crate::grammar!(@suffix $t $items $label _ $($rest)+)
+    };
+    (@suffix $t:tt [$($items:tt)*] $label:tt $prefix:tt $atom:tt * $($rest:tt)*) => {
+        $crate::grammar!(@seq $t [$($items)* [$label $prefix many0 $atom]] $($rest)*)
+    };
+    (@suffix $t:tt [$($items:tt)*] $label:tt $prefix:tt $atom:tt + $($rest:tt)*) => {
+        $crate::grammar!(@seq $t [$($items)* [$label $prefix many1 $atom]] $($rest)*)
+    };
+    (@suffix $t:tt [$($items:tt)*] $label:tt $prefix:tt $atom:tt ? $($rest:tt)*) => {
+        $crate::grammar!(@seq $t [$($items)* [$label $prefix opt $atom]] $($rest)*)
+    };
+    (@suffix $t:tt [$($items:tt)*] $label:tt $prefix:tt $atom:tt $($rest:tt)*) => {
+        $crate::grammar!(@seq $t [$($items)* [$label $prefix _ $atom]] $($rest)*)
+    };
+
+    // Sequences as nested pairs, with a matching pattern for actions.
+    (@and $t:tt $item:tt) => {
+        $crate::grammar!(@item $t $item)
+    };
+    (@and $t:tt $item:tt $($rest:tt)+) => {
+        $crate::parse::Sequence::and((
+            $crate::grammar!(@item $t $item),
+            $crate::grammar!(@and $t $($rest)+),
+        ))
+    };
+    (@pattern [$label:tt $($_:tt)*]) => {
+        $label
+    };
+    (@pattern [$label:tt $($_:tt)*] $($rest:tt)+) => {
+        ($label, $crate::grammar!(@pattern $($rest)+))
+    };
+
+    (@item $t:tt [$label:tt peek $suffix:tt $atom:tt]) => {
+        $crate::combinators::lookahead::peek($crate::grammar!(@repeat $t $suffix $atom))
+    };
+    (@item $t:tt [$label:tt not $suffix:tt $atom:tt]) => {
+        $crate::combinators::lookahead::not($crate::grammar!(@repeat $t $suffix $atom))
+    };
+    (@item $t:tt [$label:tt _ $suffix:tt $atom:tt]) => {
+        $crate::grammar!(@repeat $t $suffix $atom)
+    };
+    (@repeat $t:tt many0 $atom:tt) => {
+        $crate::parse::Parser::map(
+            $crate::parse::Parser::opt($crate::combinators::many::many($crate::grammar!(@atom $t $atom))),
+            ::std::option::Option::unwrap_or_default,
+        )
+    };
+    (@repeat $t:tt many1 $atom:tt) => {
+        $crate::combinators::many::many($crate::grammar!(@atom $t $atom))
+    };
+    (@repeat $t:tt opt $atom:tt) => {
+        $crate::parse::Parser::opt($crate::grammar!(@atom $t $atom))
+    };
+    (@repeat $t:tt _ $atom:tt) => {
+        $crate::grammar!(@atom $t $atom)
+    };
+
+    (@atom $t:tt $tag:literal) => {
+        $crate::grammar!(@typed $t $crate::parse::Parser::to_failure($crate::primitives::tag::tag($tag)))
+    };
+    (@atom $t:tt $parser:ident) => {
+        $crate::grammar!(@typed $t ::std::clone::Clone::clone(&$parser))
+    };
+    (@atom $t:tt ($($inner:tt)+)) => {
+        $crate::grammar!(@choice $t [] [] $($inner)+)
+    };
+    (@atom $t:tt {$($parser:tt)+}) => {
+        $crate::grammar!(@typed $t {$($parser)+})
+    };
+    // Fix the input & error types early, so that actions can use them.
+    (@typed [$input:ty, $error:ty, $failure:ty] $parser:expr) => {
+        $crate::combinators::grammar::typed::<$input, _, $error, $failure, _>($parser)
+    };
+
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident<$input:ty, $error:ty $(,)?> { $($rules:tt)* }
+    ) => {
+        $crate::grammar! {
+            $(#[$meta])*
+            $vis struct $name<$input, $error, $crate::parse::Never> { $($rules)* }
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident<$input:ty, $error:ty, $failure:ty $(,)?> {
+            $(
+                $(#[$rule_meta:meta])*
+                $rule:ident : $output:ty = { $($body:tt)+ }
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$rule_meta])*
+                pub $rule: $crate::combinators::recursive::Deferred<
+                    'static,
+                    $input,
+                    $output,
+                    $error,
+                    $failure,
+                >,
+            )*
+        }
+        impl $name {
+            pub fn new() -> Self {
+                let grammar = Self {
+                    $($rule: $crate::combinators::recursive::Deferred::new(),)*
+                };
+                {
+                    $(let $rule = grammar.$rule.weak();)*
+                    $(grammar.$rule.define(
+                        $crate::grammar!(@choice [$input, $error, $failure] [] [] $($body)+),
+                    );)*
+                }
+                grammar
+            }
+        }
+        impl ::std::default::Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    };
+}
+
+use crate::parse::Parser;
+
+#[doc(hidden)]
+pub fn typed<I, O, E, F, P: Parser<I, O, E, F>>(p: P) -> P {
+    p
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        input::{span::Span, Input, Slice},
+        parse::{Expectation, Expected, NotFound, Parser, ParserError},
+        primitives::numbers::digits,
+    };
+
+    grammar! {
+        /// Arithmetic, as written in a spec.
+        struct Arith<Span, Expected<Span>> {
+            expr: i64 = {
+                l:product "+" r:expr => { l + r }
+                / l:product "-" r:expr => { l - r }
+                / product
+            }
+            product: i64 = { l:atom "*" r:product => { l * r } / atom }
+            atom: i64 = {
+                "(" e:expr ")" => { e }
+                / neg:"-"? n:number => { if neg.is_some() { -n } else { n } }
+            }
+            number: i64 = { n:{ digits } => { n.as_str().parse().unwrap() } }
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let arith = Arith::new();
+        let (output, remaining) = arith.expr.parse(&Span::anonymous("2*(3+-4)*5")).unwrap();
+        assert_eq!(output, -10);
+        assert!(remaining.is_empty());
+
+        let Err(ParserError::Error(e)) = arith.expr.parse(&Span::anonymous("(3+x")) else {
+            panic!("Expected an error")
+        };
+        assert_eq!(e.input().position(), (1, 3));
+        assert!(e.expectations().any(|e| e == &Expectation::tag(")")));
+    }
+
+    grammar! {
+        struct Idents<&'static str, NotFound> {
+            keyword: &'static str = { ("if" / "else") !letter }
+            ident: &'static str = { !keyword letter+ }
+            letter: &'static str = { "a" / "e" / "f" / "i" / "l" / "s" }
+            idents: Vec<&'static str> = { first:ident rest:(" " i:ident => { i })* => {
+                let mut idents = vec![first];
+                idents.extend(rest);
+                idents
+            } }
+        }
+    }
+
+    #[test]
+    fn predicates() {
+        let g = Idents::new();
+        assert_eq!(g.keyword.parse(&"if "), Ok(("if", " ")));
+        assert_eq!(g.keyword.parse(&"ifs"), Err(ParserError::Error(NotFound)));
+        assert_eq!(
+            g.idents.parse(&"ifs fi else"),
+            Ok((vec!["ifs", "fi"], " else"))
+        );
+    }
+}
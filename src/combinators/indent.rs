@@ -0,0 +1,561 @@
+/*!
+# Indentation
+
+Parsers for layouts in which indentation groups lines into blocks, as in
+Python & YAML. They read [`Span`] input, whose source gives the indentation of
+the line around any position.
+
+- [`indentation`] reads the blanks at the start of a line.
+- [`at_column`] runs a parser only at the given column.
+- [`same_indent`] parses items on successive lines at the column of the first,
+  and stops at a line indented less.
+- [`indented_block`] parses the lines after a header which are indented more
+  than it, as [`same_indent`] items.
+- [`layout`] instead splits input into lines, with an [`Event::Indent`] or
+  [`Event::Dedent`] whenever a line opens or closes a block, tracked on a stack
+  of indentation levels. Grammars over these events need no layout rules of
+  their own.
+- Indentation is measured in bytes, so a tab is as wide as a space. Tabs &
+  spaces can't be mixed, within a line or between lines which are compared,
+  and fail with [`IndentError::Mixed`] at the first blank which differs.
+- Blank lines are skipped, and never open or close a block.
+
+```
+use parlance::{
+    combinators::indent::{layout, Event},
+    input::{span::Span, Input},
+};
+
+let input = Span::anonymous("if x:\n    y\nz\n");
+let events = layout(&input)
+    .map(|event| match event.unwrap() {
+        Event::Indent(_) => "indent".to_string(),
+        Event::Dedent(_) => "dedent".to_string(),
+        Event::Line(line) => line.as_str().to_string(),
+    })
+    .collect::<Vec<_>>();
+assert_eq!(events, ["if x:", "indent", "y", "dedent", "z"]);
+```
+
+*/
+
+use std::{cmp::Ordering, collections::VecDeque, fmt};
+
+use crate::{
+    diagnostics::Diagnostic,
+    input::{span::Span, Input, Slice},
+    parse::{ExpectError, Expectation, Never, Parser, ParserError, ParserResult},
+};
+
+#[derive(Clone, Debug)]
+pub enum IndentError {
+    /// Indentation which mixes tabs & spaces, within a line or with the lines
+    /// it is compared with, at the first blank which differs.
+    Mixed(Span),
+    /// A line indented more than its block, where no block is opened, at its
+    /// indentation.
+    Unexpected(Span),
+    /// A line indented less than a block it ends, but more than the block
+    /// around that, at its indentation.
+    Unmatched(Span),
+}
+impl IndentError {
+    pub fn span(&self) -> &Span {
+        match self {
+            IndentError::Mixed(span)
+            | IndentError::Unexpected(span)
+            | IndentError::Unmatched(span) => span,
+        }
+    }
+}
+impl fmt::Display for IndentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndentError::Mixed(_) => f.write_str("tabs & spaces mixed in indentation"),
+            IndentError::Unexpected(_) => f.write_str("unexpected indent"),
+            IndentError::Unmatched(_) => {
+                f.write_str("unindent does not match any outer indentation level")
+            }
+        }
+    }
+}
+impl std::error::Error for IndentError {}
+impl From<Never> for IndentError {
+    fn from(value: Never) -> Self {
+        unreachable!()
+    }
+}
+impl From<IndentError> for Diagnostic {
+    fn from(value: IndentError) -> Self {
+        Diagnostic::error(value.to_string(), value.span().clone())
+    }
+}
+
+fn is_blank(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn fail<E, F: From<IndentError>>(e: IndentError) -> ParserError<E, F> {
+    ParserError::Failure(e.into())
+}
+
+/// The blanks at the start of `s`, which must all be spaces or all tabs.
+fn split_indentation(s: &Span) -> Result<(Span, Span), IndentError> {
+    let (indent, remaining) = s
+        .take_while(is_blank)
+        .unwrap_or_else(|| (s.empty(), s.clone()));
+    let mut blanks = indent.as_str().char_indices();
+    if let Some((_, first)) = blanks.next() {
+        if let Some((idx, _)) = blanks.find(|(_, c)| *c != first) {
+            return Err(IndentError::Mixed(indent.slice(idx..idx + 1)));
+        }
+    }
+    Ok((indent, remaining))
+}
+
+/// The blanks at the start of a line, which must all be spaces or all tabs.
+/// Outputs an empty span where the line isn't indented.
+pub fn indentation<E, F: From<IndentError>>(s: &Span) -> ParserResult<Span, Span, E, F> {
+    split_indentation(s).map_err(fail)
+}
+
+/// Runs `p` only where the input is at `column`, counted from 1 as in
+/// [`Span::position`].
+pub fn at_column<O, E: ExpectError<Span>, F, P: Parser<Span, O, E, F>>(
+    column: usize,
+    p: P,
+) -> impl Parser<Span, O, E, F> {
+    move |input: &Span| {
+        if input.position().1 != column {
+            return Err(ParserError::Error(E::expected(
+                input,
+                Expectation::label("indentation"),
+            )));
+        }
+        p.parse(input)
+    }
+}
+
+/// The text of the line containing the start of `s`, without its line break.
+fn line_of(s: &Span) -> (usize, &str) {
+    s.line_text(s.line_index(s.offset()))
+}
+
+/// The text of the line before `s`.
+fn line_prefix(s: &Span) -> &str {
+    let (start, line) = line_of(s);
+    line.get(..s.offset() - start).unwrap_or(line)
+}
+
+/// The indentation of the line containing the start of `s`.
+fn line_indentation(s: &Span) -> &str {
+    let (_, line) = line_of(s);
+    &line[..line.len() - line.trim_start_matches(is_blank).len()]
+}
+
+/// The indentation & text of the next line which isn't blank, from the end of
+/// a line. `None` where `s` isn't at the end of a line, or no such line
+/// follows.
+fn next_line(s: &Span) -> Result<Option<(Span, Span)>, IndentError> {
+    let mut remaining = s.take_while(is_blank).map_or(s.clone(), |(_, r)| r);
+    loop {
+        let Some((_, rest)) = remaining.pop(&"\n").or_else(|| remaining.pop(&"\r\n")) else {
+            return Ok(None);
+        };
+        let after_blanks = rest.take_while(is_blank).map_or(rest.clone(), |(_, r)| r);
+        let text = after_blanks.as_str();
+        if text.is_empty() {
+            return Ok(None);
+        } else if text.starts_with('\n') || text.starts_with("\r\n") {
+            remaining = after_blanks;
+        } else {
+            return split_indentation(&rest).map(Some);
+        }
+    }
+}
+
+/// Compare the indentation of a line with the text before a block's column.
+/// Fails at the first blank which differs from a blank of the reference.
+fn compare(reference: &str, indent: &Span) -> Result<Ordering, IndentError> {
+    let mixed = reference
+        .chars()
+        .zip(indent.as_str().chars())
+        .position(|(r, c)| is_blank(r) && r != c);
+    match mixed {
+        Some(idx) => Err(IndentError::Mixed(indent.slice(idx..idx + 1))),
+        None => Ok(indent.len().cmp(&reference.len())),
+    }
+}
+
+/// Items on successive lines at the column where the first begins, such as
+/// the entries of a YAML mapping. Each item after the first is parsed from
+/// after its line's indentation, and blank lines between them are skipped.
+///
+/// Items end at a line indented less, or one which `p` doesn't match, leaving
+/// the input at the end of the last item. An item should leave the input at
+/// the end of its line, or of the last line of any block within it.
+///
+/// Fails where a line is indented more than the items, unless it is part of
+/// an item.
+pub fn same_indent<O, E: ExpectError<Span>, F: From<IndentError>, P: Parser<Span, O, E, F>>(
+    p: P,
+) -> impl Parser<Span, Vec<O>, E, F> {
+    move |input: &Span| {
+        let reference = line_prefix(input);
+        let (first, mut remaining) = p.parse(input)?;
+        let mut items = vec![first];
+        while let Some((indent, text)) = next_line(&remaining).map_err(fail)? {
+            match compare(reference, &indent).map_err(fail)? {
+                Ordering::Less => break,
+                Ordering::Equal => {}
+                Ordering::Greater if indent.len() < line_indentation(&remaining).len() => {
+                    return Err(fail(IndentError::Unmatched(indent)));
+                }
+                Ordering::Greater => return Err(fail(IndentError::Unexpected(indent))),
+            }
+            match p.parse(&text) {
+                Ok((item, rest)) => {
+                    items.push(item);
+                    remaining = rest;
+                }
+                Err(ParserError::Error(_)) => break,
+                Err(ParserError::Failure(f)) => return Err(ParserError::Failure(f)),
+            }
+        }
+        Ok((items, remaining))
+    }
+}
+
+/// The block after a header, from the end of the header's line: the lines
+/// after it which are indented more than the header's line, as
+/// [`same_indent`] items. The first line of the block sets its indentation.
+pub fn indented_block<O, E: ExpectError<Span>, F: From<IndentError>, P: Parser<Span, O, E, F>>(
+    p: P,
+) -> impl Parser<Span, Vec<O>, E, F> {
+    let items = same_indent(p);
+    move |input: &Span| {
+        let header = line_indentation(input);
+        match next_line(input).map_err(fail)? {
+            Some((indent, text))
+                if compare(header, &indent).map_err(fail)? == Ordering::Greater =>
+            {
+                items.parse(&text)
+            }
+            Some((_, text)) => Err(ParserError::Error(E::expected(
+                &text,
+                Expectation::label("indented block"),
+            ))),
+            None => Err(ParserError::Error(E::expected(
+                input,
+                Expectation::label("indented block"),
+            ))),
+        }
+    }
+}
+
+/// A line of input, or a change of indentation before one.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// A block opened by a line indented more than the last, at its
+    /// indentation.
+    Indent(Span),
+    /// The end of a block, before a line indented less or at the end of the
+    /// input. Its span is empty.
+    Dedent(Span),
+    /// The text of a line which isn't blank, without its indentation or line
+    /// break.
+    Line(Span),
+}
+
+/// An iterator over the [`Event`]s of some input, from [`layout`]. Iteration
+/// ends after the first error.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    remaining: Span,
+    /// The widths of the open blocks, starting with the unindented lines.
+    levels: Vec<usize>,
+    /// The blank which the input is indented with, once one has been seen.
+    blank: Option<char>,
+    events: VecDeque<Event>,
+    done: bool,
+}
+impl Layout {
+    /// The number of blocks open, not counting the unindented lines.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Queue the events for the next line, or the end of the input.
+    fn advance(&mut self) -> Result<(), IndentError> {
+        let (indent, text) = loop {
+            let s = &self.remaining;
+            let (indent, text) = s
+                .take_while(is_blank)
+                .unwrap_or_else(|| (s.empty(), s.clone()));
+            if let Some((_, rest)) = text.pop(&"\n").or_else(|| text.pop(&"\r\n")) {
+                self.remaining = rest;
+            } else if text.is_empty() {
+                self.done = true;
+                for _ in 1..self.levels.len() {
+                    self.events.push_back(Event::Dedent(text.empty()));
+                }
+                self.levels.truncate(1);
+                return Ok(());
+            } else {
+                break (indent, text);
+            }
+        };
+
+        let end = text.as_str().find('\n').unwrap_or(text.len());
+        self.remaining = text.skip(end).pop(&"\n").map_or(text.skip(end), |(_, r)| r);
+        let line = text.take(end);
+        let line = match line.as_str().strip_suffix('\r') {
+            Some(s) => line.take(s.len()),
+            None => line,
+        };
+
+        if let Some(first) = indent.as_str().chars().next() {
+            let blank = *self.blank.get_or_insert(first);
+            if let Some(idx) = indent.as_str().find(|c| c != blank) {
+                return Err(IndentError::Mixed(indent.slice(idx..idx + 1)));
+            }
+        }
+        let top = *self
+            .levels
+            .last()
+            .expect("The unindented level is never closed");
+        match indent.len().cmp(&top) {
+            Ordering::Greater => {
+                self.levels.push(indent.len());
+                self.events.push_back(Event::Indent(indent));
+            }
+            Ordering::Less => {
+                while self
+                    .levels
+                    .last()
+                    .is_some_and(|level| *level > indent.len())
+                {
+                    self.levels.pop();
+                    self.events.push_back(Event::Dedent(line.empty()));
+                }
+                if self.levels.last() != Some(&indent.len()) {
+                    return Err(IndentError::Unmatched(indent));
+                }
+            }
+            Ordering::Equal => {}
+        }
+        self.events.push_back(Event::Line(line));
+        Ok(())
+    }
+}
+impl Iterator for Layout {
+    type Item = Result<Event, IndentError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_empty() && !self.done {
+            if let Err(e) = self.advance() {
+                self.done = true;
+                self.events.clear();
+                return Some(Err(e));
+            }
+        }
+        self.events.pop_front().map(Ok)
+    }
+}
+
+/// The lines of the input, with an [`Event::Indent`] before each line which
+/// opens a block and an [`Event::Dedent`] for each block a line closes. Blocks
+/// still open at the end of the input are closed there.
+pub fn layout(input: &Span) -> Layout {
+    Layout {
+        remaining: input.clone(),
+        levels: vec![0],
+        blank: None,
+        events: VecDeque::new(),
+        done: false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::Expected;
+
+    type TestResult<O> = ParserResult<Span, O, Expected<Span>, IndentError>;
+
+    #[derive(Debug, PartialEq)]
+    enum Node {
+        Leaf(String, String),
+        Branch(String, Vec<Node>),
+    }
+
+    /// `key: value`, or `key:` then a block of entries.
+    fn entry(s: &Span) -> TestResult<Node> {
+        let Some((key, rest)) = s.take_while(char::is_alphanumeric) else {
+            return Err(ParserError::Error(Expected::new(
+                s.clone(),
+                Expectation::label("key"),
+            )));
+        };
+        let Some((_, rest)) = rest.pop(&":") else {
+            return Err(ParserError::Error(Expected::new(
+                rest,
+                Expectation::tag(":"),
+            )));
+        };
+        let key = key.as_str().to_string();
+        if let Some((_, rest)) = rest.pop(&" ") {
+            let (value, rest) = rest
+                .take_while(|c| c != '\n')
+                .unwrap_or_else(|| (rest.empty(), rest.clone()));
+            return Ok((Node::Leaf(key, value.as_str().to_string()), rest));
+        }
+        let (entries, rest) = indented_block(entry).parse(&rest)?;
+        Ok((Node::Branch(key, entries), rest))
+    }
+
+    fn leaf(key: &str, value: &str) -> Node {
+        Node::Leaf(key.to_string(), value.to_string())
+    }
+
+    fn failure(s: &str) -> IndentError {
+        match same_indent(entry).parse(&Span::anonymous(s)) {
+            Err(ParserError::Failure(f)) => f,
+            other => panic!("Expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn blocks() {
+        let input = Span::anonymous(
+            "server:\n  port: 80\n  hosts:\n    main: a\n\n    backup: b\n  debug: no\nname: x\n",
+        );
+        let (nodes, remaining) = same_indent(entry).parse(&input).unwrap();
+        assert_eq!(
+            nodes,
+            [
+                Node::Branch(
+                    "server".to_string(),
+                    vec![
+                        leaf("port", "80"),
+                        Node::Branch(
+                            "hosts".to_string(),
+                            vec![leaf("main", "a"), leaf("backup", "b")]
+                        ),
+                        leaf("debug", "no"),
+                    ]
+                ),
+                leaf("name", "x"),
+            ]
+        );
+        assert_eq!(remaining.as_str(), "\n");
+
+        // Tab indentation, and items which stop at a line they don't match.
+        let input = Span::anonymous("a:\n\tb: 1\n\tc: 2\n- d");
+        let (nodes, remaining) = same_indent(entry).parse(&input).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(remaining.position(), (3, 6));
+
+        let input = Span::anonymous("a:\nb: 1");
+        let Err(ParserError::Error(e)) = entry(&input) else {
+            panic!("Expected an error")
+        };
+        assert_eq!(e.input().position(), (2, 1));
+    }
+
+    #[test]
+    fn columns() {
+        let input = Span::anonymous("- x\n  y");
+        let x = input.skip(2);
+        let parser = at_column(
+            3,
+            same_indent(|s: &Span| -> TestResult<Span> {
+                s.take_while(char::is_alphabetic).ok_or_else(|| {
+                    ParserError::Error(Expected::new(s.clone(), Expectation::label("word")))
+                })
+            }),
+        );
+        let (words, _) = parser.parse(&x).unwrap();
+        assert_eq!(
+            words.iter().map(|w| w.position()).collect::<Vec<_>>(),
+            [(1, 3), (2, 3)]
+        );
+        assert!(matches!(parser.parse(&input), Err(ParserError::Error(_))));
+
+        let (indent, rest) = indentation::<Never, IndentError>(&Span::anonymous("    x")).unwrap();
+        assert_eq!((indent.len(), rest.as_str()), (4, "x"));
+    }
+
+    #[test]
+    fn errors() {
+        let e = failure("a:\n    b: 1\n  c: 2");
+        assert!(matches!(e, IndentError::Unmatched(_)));
+        assert_eq!(e.span().position(), (3, 1));
+
+        let e = failure("a: 1\n  b: 2");
+        assert!(matches!(e, IndentError::Unexpected(_)));
+        assert_eq!(e.span().position(), (2, 1));
+
+        let e = failure("a:\n\tb: 1\n  c: 2");
+        assert!(matches!(e, IndentError::Mixed(_)));
+        assert_eq!(e.span().position(), (3, 1));
+
+        let e = failure("a:\n  \tb: 1");
+        assert!(matches!(e, IndentError::Mixed(_)));
+        assert_eq!(e.span().position(), (2, 3));
+        assert_eq!(e.to_string(), "tabs & spaces mixed in indentation");
+    }
+
+    fn events(s: &str) -> Vec<Result<String, (usize, usize)>> {
+        layout(&Span::anonymous(s))
+            .map(|event| match event {
+                Ok(Event::Indent(indent)) => Ok(format!("indent {}", indent.len())),
+                Ok(Event::Dedent(_)) => Ok("dedent".to_string()),
+                Ok(Event::Line(line)) => Ok(line.as_str().to_string()),
+                Err(e) => Err(e.span().position()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layout_events() {
+        assert_eq!(
+            events("a:\n  b\n  c:\n\n    d\r\n  \ne\n  f"),
+            [
+                Ok("a:".to_string()),
+                Ok("indent 2".to_string()),
+                Ok("b".to_string()),
+                Ok("c:".to_string()),
+                Ok("indent 4".to_string()),
+                Ok("d".to_string()),
+                Ok("dedent".to_string()),
+                Ok("dedent".to_string()),
+                Ok("e".to_string()),
+                Ok("indent 2".to_string()),
+                Ok("f".to_string()),
+                Ok("dedent".to_string()),
+            ]
+        );
+        assert!(events("\n  \n").is_empty());
+
+        let mut layout = layout(&Span::anonymous("a\n  b\n    c\n"));
+        layout.by_ref().take(5).for_each(drop);
+        assert_eq!(layout.depth(), 2);
+        assert_eq!(layout.count(), 2);
+    }
+
+    #[test]
+    fn layout_errors() {
+        assert_eq!(
+            events("a\n    b\n  c\nd"),
+            [
+                Ok("a".to_string()),
+                Ok("indent 4".to_string()),
+                Ok("b".to_string()),
+                Err((3, 1)),
+            ]
+        );
+        assert_eq!(events("a\n  b\n\tc").last(), Some(&Err((3, 1))));
+        assert_eq!(events("a\n \tb").last(), Some(&Err((2, 2))));
+    }
+}
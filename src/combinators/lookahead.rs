@@ -0,0 +1,67 @@
+/*!
+# Lookahead
+
+- [`peek`] runs a parser without consuming any input.
+- [`not`] succeeds, without consuming input, only where a parser doesn't.
+- [`recognize`] outputs the input consumed by a parser, instead of its output.
+
+*/
+
+use crate::{
+    input::Slice,
+    parse::{ExpectError, Expectation, Parser, ParserError},
+};
+
+pub fn peek<I: Clone, O, E, F, P: Parser<I, O, E, F>>(p: P) -> impl Parser<I, O, E, F> {
+    move |input: &I| {
+        let (output, _) = p.parse(input)?;
+        Ok((output, input.clone()))
+    }
+}
+
+/// Failures from `p` are passed on; only an error counts as not matching.
+pub fn not<I: Clone, O, E: ExpectError<I>, F, P: Parser<I, O, E, F>>(
+    p: P,
+) -> impl Parser<I, (), E, F> {
+    move |input: &I| match p.parse(input) {
+        Ok(_) => Err(ParserError::Error(E::expected(
+            input,
+            Expectation::Label("something else"),
+        ))),
+        Err(ParserError::Error(_)) => Ok(((), input.clone())),
+        Err(ParserError::Failure(f)) => Err(ParserError::Failure(f)),
+    }
+}
+
+pub fn recognize<I: Slice, O, E, F, P: Parser<I, O, E, F>>(p: P) -> impl Parser<I, I, E, F> {
+    move |input: &I| {
+        let (_, remaining) = p.parse(input)?;
+        Ok((input.take(input.len() - remaining.len()), remaining))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parse::{NotFound, Sequence},
+        primitives::numbers::digits,
+    };
+
+    #[test]
+    fn lookahead() {
+        assert_eq!(peek(digits::<_, NotFound>).parse(&"12a"), Ok(("12", "12a")));
+        assert_eq!(
+            (not("-"), digits).and().parse(&"12"),
+            Ok::<_, ParserError<NotFound, _>>((((), "12"), ""))
+        );
+        assert_eq!(
+            (not("-"), digits::<_, NotFound>).and().parse(&"-12"),
+            Err(ParserError::Error(NotFound))
+        );
+        assert_eq!(
+            recognize((digits::<_, NotFound>, ".", digits).and()).parse(&"1.5x"),
+            Ok(("1.5", "x"))
+        );
+    }
+}
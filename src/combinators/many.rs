@@ -1,15 +1,11 @@
 use crate::{
-    input::Input,
+    input::Slice,
     parse::{NotFound, Parser, ParserError},
 };
 
-pub fn many<I: Input, O, E, F, P: Parser<I, O, E, F>>(p: P) -> impl Parser<I, Vec<O>, NotFound, F> {
+pub fn many<I: Slice, O, E, F, P: Parser<I, O, E, F>>(p: P) -> impl Parser<I, Vec<O>, E, F> {
     move |input: &I| {
-        let (first, mut remaining) = match p.parse(input) {
-            Ok(x) => x,
-            Err(ParserError::Error(_)) => return Err(ParserError::Error(NotFound)),
-            Err(ParserError::Failure(e)) => return Err(ParserError::Failure(e)),
-        };
+        let (first, mut remaining) = p.parse(input)?;
         let mut output = vec![first];
         while !remaining.is_empty() {
             let (o, r) = match p.parse(&remaining) {
@@ -17,6 +13,10 @@ pub fn many<I: Input, O, E, F, P: Parser<I, O, E, F>>(p: P) -> impl Parser<I, Ve
                 Err(ParserError::Error(_)) => break,
                 Err(ParserError::Failure(e)) => return Err(ParserError::Failure(e)),
             };
+            if r.len() == remaining.len() {
+                // No progress was made, so we would loop forever.
+                break;
+            }
             output.push(o);
             remaining = r;
         }
@@ -26,7 +26,7 @@ pub fn many<I: Input, O, E, F, P: Parser<I, O, E, F>>(p: P) -> impl Parser<I, Ve
 }
 
 pub fn delimited<
-    I: Input,
+    I: Slice,
     Output,
     DelimiterOutput,
     E,
@@ -36,13 +36,9 @@ pub fn delimited<
 >(
     p: P,
     delimiter: Delimiter,
-) -> impl Parser<I, Vec<Output>, NotFound, F> {
+) -> impl Parser<I, Vec<Output>, E, F> {
     move |input: &I| {
-        let (first, mut remaining) = match p.parse(input) {
-            Ok(x) => x,
-            Err(ParserError::Error(_)) => return Err(ParserError::Error(NotFound)),
-            Err(ParserError::Failure(e)) => return Err(ParserError::Failure(e)),
-        };
+        let (first, mut remaining) = p.parse(input)?;
         let mut output = vec![first];
         while !remaining.is_empty() {
             let (_, r) = match delimiter.parse(&remaining) {
@@ -56,6 +52,9 @@ pub fn delimited<
                 Err(ParserError::Error(_)) => break,
                 Err(ParserError::Failure(e)) => return Err(ParserError::Failure(e)),
             };
+            if r.len() == remaining.len() {
+                break;
+            }
             output.push(o);
             remaining = r;
         }
@@ -64,7 +63,7 @@ pub fn delimited<
     }
 }
 
-pub fn repeat<I: Input, O, E, F, P: Parser<I, O, E, F>>(
+pub fn repeat<I: Slice, O, E, F, P: Parser<I, O, E, F>>(
     p: P,
     c: usize,
 ) -> impl Parser<I, Vec<O>, E, F> {
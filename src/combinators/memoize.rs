@@ -0,0 +1,396 @@
+/*!
+# Packrat memoization
+
+- [`memoize`] wraps a parser so that its result at each position of an
+  [`Addressable`] input is computed at most once, and is afterwards looked up in
+  a [`Memo`].
+- [`left_recursive`] additionally allows a rule to refer to itself at the
+  start of an alternative, such as `expr := expr "-" term | term`.
+
+A grammar which backtracks heavily, e.g. through [`Choice`][crate::parse::Choice],
+may re-run the same parser at the same position many times. Memoizing the
+parsers which are retried keeps the whole parse linear in the input length.
+
+A [`Memo`] may be shared by several rules with the same output type: each
+wrapped parser keeps its own results in it.
+
+*/
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
+
+use crate::{
+    input::Addressable,
+    parse::{ExpectError, Expectation, Parser, ParserError, ParserResult},
+};
+
+/// Counts of cache lookups made through a [`Memo`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// A rule's id, and the offset & length of the input a result was parsed from.
+type MemoKey = (usize, usize, usize);
+
+struct MemoTable<I, O, E, F> {
+    /// Some input from the source which `entries` refer to.
+    source: Option<I>,
+    /// The number of parsers wrapped with this table, used as their ids.
+    rules: usize,
+    entries: HashMap<MemoKey, ParserResult<I, O, E, F>>,
+    /// Keys in insertion order, for eviction.
+    order: VecDeque<MemoKey>,
+    /// Results of left recursive rules which are still being grown. These are
+    /// never evicted.
+    seeds: HashMap<MemoKey, ParserResult<I, O, E, F>>,
+    capacity: Option<usize>,
+    stats: MemoStats,
+}
+
+/// A shared cache of a parser's results, for use with [`memoize`].
+/// Cloning a `Memo` produces a handle to the same cache.
+///
+/// The cache holds results for one source at a time; parsing input from a
+/// different source clears it.
+pub struct Memo<I, O, E, F> {
+    table: Rc<RefCell<MemoTable<I, O, E, F>>>,
+}
+impl<I, O, E, F> Memo<I, O, E, F> {
+    pub fn new() -> Self {
+        Self {
+            table: Rc::new(RefCell::new(MemoTable {
+                source: None,
+                rules: 0,
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+                seeds: HashMap::new(),
+                capacity: None,
+                stats: MemoStats::default(),
+            })),
+        }
+    }
+    /// Keep at most `capacity` results, evicting the oldest first.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        self.table.borrow_mut().capacity = Some(capacity);
+        self
+    }
+    pub fn len(&self) -> usize {
+        self.table.borrow().entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.table.borrow().entries.is_empty()
+    }
+    /// Forget every cached result. Statistics are kept.
+    pub fn clear(&self) {
+        let mut table = self.table.borrow_mut();
+        table.source = None;
+        table.entries.clear();
+        table.order.clear();
+        table.seeds.clear();
+    }
+    pub fn stats(&self) -> MemoStats {
+        self.table.borrow().stats
+    }
+    pub fn reset_stats(&self) {
+        self.table.borrow_mut().stats = MemoStats::default();
+    }
+}
+impl<I, O, E, F> Clone for Memo<I, O, E, F> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+        }
+    }
+}
+impl<I, O, E, F> Default for Memo<I, O, E, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, O, E, F> Memo<I, O, E, F> {
+    /// A new id for a parser wrapped with this memo.
+    fn rule(&self) -> usize {
+        let mut table = self.table.borrow_mut();
+        table.rules += 1;
+        table.rules
+    }
+}
+
+impl<I: Addressable, O: Clone, E: Clone, F: Clone> MemoTable<I, O, E, F> {
+    fn lookup(&mut self, rule: usize, input: &I) -> Option<ParserResult<I, O, E, F>> {
+        match &self.source {
+            Some(source) if source.same_source(input) => {}
+            _ => {
+                self.entries.clear();
+                self.order.clear();
+                self.source = Some(input.clone());
+            }
+        }
+
+        let key = (rule, input.offset(), input.len());
+        let cached = self
+            .seeds
+            .get(&key)
+            .or_else(|| self.entries.get(&key))
+            .cloned();
+        match cached {
+            Some(_) => self.stats.hits += 1,
+            None => self.stats.misses += 1,
+        }
+        cached
+    }
+    fn insert(&mut self, rule: usize, input: &I, result: ParserResult<I, O, E, F>) {
+        if self.capacity == Some(0) {
+            return;
+        }
+        let key = (rule, input.offset(), input.len());
+        if let Some(entry) = self.entries.get_mut(&key) {
+            *entry = result;
+            return;
+        }
+        if self.capacity.is_some_and(|c| self.entries.len() >= c) {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, result);
+        self.order.push_back(key);
+    }
+}
+
+/// Cache the results of `p` in `memo`, by the position of the input they were
+/// parsed from.
+///
+/// `p` must be pure: given the same input it must always give the same result.
+pub fn memoize<I: Addressable, O: Clone, E: Clone, F: Clone, P: Parser<I, O, E, F>>(
+    p: P,
+    memo: &Memo<I, O, E, F>,
+) -> impl Parser<I, O, E, F> {
+    let memo = memo.clone();
+    let rule = memo.rule();
+    move |input: &I| {
+        if let Some(result) = memo.table.borrow_mut().lookup(rule, input) {
+            return result;
+        }
+        // `p` may recurse into this parser, so the table mustn't stay borrowed.
+        let result = p.parse(input);
+        memo.table.borrow_mut().insert(rule, input, result.clone());
+        result
+    }
+}
+
+/// As [`memoize`], for a rule which may be left recursive: `p` may refer to
+/// this parser before consuming any input.
+///
+/// On reaching the same position again, the innermost call gives the result
+/// found so far (initially an error), and `p` is retried for as long as it
+/// consumes more input than before. This grows a left associative result, as
+/// described by Warth et al. in "Packrat Parsers Can Support Left Recursion".
+///
+/// For indirectly left recursive rules, wrap one rule of each cycle.
+pub fn left_recursive<
+    I: Addressable,
+    O: Clone,
+    E: ExpectError<I> + Clone,
+    F: Clone,
+    P: Parser<I, O, E, F>,
+>(
+    p: P,
+    memo: &Memo<I, O, E, F>,
+) -> impl Parser<I, O, E, F> {
+    let memo = memo.clone();
+    let rule = memo.rule();
+    move |input: &I| {
+        if let Some(result) = memo.table.borrow_mut().lookup(rule, input) {
+            return result;
+        }
+
+        let key = (rule, input.offset(), input.len());
+        let seed = ParserError::Error(E::expected(input, Expectation::Label("left operand")));
+        memo.table.borrow_mut().seeds.insert(key, Err(seed));
+
+        let mut result = p.parse(input);
+        while let Ok((_, best)) = &result {
+            memo.table.borrow_mut().seeds.insert(key, result.clone());
+            match p.parse(input) {
+                Ok((o, remaining)) if remaining.len() < best.len() => {
+                    result = Ok((o, remaining));
+                }
+                Err(ParserError::Failure(f)) => {
+                    result = Err(ParserError::Failure(f));
+                    break;
+                }
+                // No further growth, so the previous result is the longest.
+                _ => break,
+            }
+        }
+
+        let mut table = memo.table.borrow_mut();
+        table.seeds.remove(&key);
+        table.insert(rule, input, result.clone());
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        combinators::recursive::{recursive, Deferred},
+        input::{span::Span, string::SharedString, Input, Slice},
+        parse::{Choice, Expected, Never, NotFound, Sequence},
+        primitives::numbers::digits,
+    };
+
+    #[test]
+    fn backtracking_reuses_results() {
+        let calls = Cell::new(0);
+        let memo = Memo::new();
+        let number = memoize(
+            |input: &Span| {
+                calls.set(calls.get() + 1);
+                digits::<_, NotFound>.parse(input)
+            },
+            &memo,
+        );
+        let number = |input: &Span| number.parse(input);
+        let p = (
+            (number, "+", number).and().map(|_| "sum"),
+            (number, "-", number).and().map(|_| "difference"),
+            number.map(|_| "number"),
+        )
+            .or();
+
+        let (output, remaining) = p.parse(&Span::anonymous("12-34")).unwrap();
+        assert_eq!(output, "difference");
+        assert!(remaining.is_empty());
+        // "12" once, then "34" once
+        assert_eq!(calls.get(), 2);
+        assert_eq!(memo.stats(), MemoStats { hits: 1, misses: 2 });
+
+        // A new source doesn't see old results.
+        let (output, _) = p.parse(&Span::anonymous("5")).unwrap();
+        assert_eq!(output, "number");
+        assert_eq!(calls.get(), 3);
+        assert_eq!(memo.len(), 1);
+    }
+
+    #[test]
+    fn errors_are_cached() {
+        let memo = Memo::new();
+        let number = memoize(digits::<SharedString, NotFound>, &memo);
+        let input = SharedString::new("x".to_string());
+
+        assert_eq!(
+            number.parse(&input),
+            Err::<(SharedString, _), _>(ParserError::Error(NotFound))
+        );
+        assert_eq!(number.parse(&input).ok(), None);
+        assert_eq!(memo.stats(), MemoStats { hits: 1, misses: 1 });
+
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(number.parse(&input).ok(), None);
+        assert_eq!(memo.stats().misses, 2);
+    }
+
+    #[test]
+    fn bounded() {
+        let memo = Memo::new().with_capacity(2);
+        let number = memoize(digits::<Span, NotFound>, &memo);
+        let input = Span::anonymous("1 2 3");
+
+        for offset in [0, 2, 4] {
+            number.parse(&input.skip(offset)).unwrap();
+        }
+        assert_eq!(memo.len(), 2);
+
+        // The oldest result, at offset 0, was evicted.
+        number.parse(&input).unwrap();
+        number.parse(&input.skip(4)).unwrap();
+        assert_eq!(memo.stats(), MemoStats { hits: 1, misses: 4 });
+    }
+
+    #[test]
+    fn shared_between_rules() {
+        let memo = Memo::new();
+        let number = memoize(digits::<Span, NotFound>.map(|_| "number"), &memo);
+        let word = memoize(
+            (digits::<Span, NotFound>.map(|_| "digits"), "x".map(|_| "x")).or(),
+            &memo,
+        );
+        let input = Span::anonymous("12");
+
+        assert_eq!(number.parse(&input).unwrap().0, "number");
+        assert_eq!(word.parse(&input).unwrap().0, "digits");
+        assert_eq!(number.parse(&input).unwrap().0, "number");
+        assert_eq!(memo.len(), 2);
+        assert_eq!(memo.stats(), MemoStats { hits: 1, misses: 2 });
+    }
+
+    #[test]
+    fn replacing_doesnt_evict() {
+        let memo = Memo::<Span, (), NotFound, Never>::new().with_capacity(2);
+        let input = Span::anonymous("1 2");
+        let mut table = memo.table.borrow_mut();
+        table.insert(0, &input, Ok(((), input.clone())));
+        table.insert(0, &input.skip(2), Ok(((), input.clone())));
+        table.insert(0, &input.skip(2), Ok(((), input.skip(3))));
+
+        assert_eq!(table.entries.len(), 2);
+        assert_eq!(table.order.len(), 2);
+        assert!(table.entries.contains_key(&(0, 0, 3)));
+        assert_eq!(table.entries[&(0, 2, 1)].as_ref().unwrap().1.as_str(), "");
+    }
+
+    #[test]
+    fn left_recursion() {
+        // expr := expr "-" number | number
+        let memo = Memo::new();
+        let number = |input: &Span| {
+            digits
+                .map(|n: Span| n.as_str().parse::<i64>().unwrap())
+                .parse(input)
+        };
+        let expr = recursive(|expr| {
+            left_recursive(
+                ((expr, "-", number).and().map(|(l, _, r)| l - r), number).or(),
+                &memo,
+            )
+        });
+
+        let (output, remaining) = expr.parse(&Span::anonymous("10-3-2+1")).unwrap();
+        assert_eq!(output, 5);
+        assert_eq!(remaining.as_str(), "+1");
+
+        let Err(ParserError::Error(e)) = expr.parse(&Span::anonymous("x")) else {
+            panic!("Expected an error")
+        };
+        let e: Expected<Span> = e;
+        assert_eq!(e.input().position(), (1, 1));
+    }
+
+    #[test]
+    fn indirect_left_recursion() {
+        // expr := call | digits
+        // call := expr "()"
+        let memo = Memo::new();
+        let call = Deferred::<Span, usize, NotFound, _>::new();
+        let expr = recursive(|expr| {
+            call.define((expr, "()").and().map(|(n, _)| n + 1));
+            left_recursive((call.clone(), digits.map(|_| 0)).or(), &memo)
+        });
+
+        let (output, remaining) = expr.parse(&Span::anonymous("1()()x")).unwrap();
+        assert_eq!(output, 2);
+        assert_eq!(remaining.as_str(), "x");
+    }
+}
@@ -1,13 +1,26 @@
 pub mod escaped;
+pub mod expression;
 pub mod fuse;
+pub mod grammar;
+pub mod indent;
+pub mod lookahead;
 pub mod many;
+pub mod memoize;
+pub mod recover;
+pub mod recursive;
 pub mod required;
 pub mod sandwich;
 pub mod take_until;
 
-pub use crate::fuse;
+pub use crate::{fuse, grammar};
 pub use escaped::*;
+pub use expression::*;
+pub use indent::*;
+pub use lookahead::*;
 pub use many::*;
+pub use memoize::*;
+pub use recover::*;
+pub use recursive::*;
 pub use required::*;
 pub use sandwich::*;
 pub use take_until::*;
@@ -0,0 +1,349 @@
+/*!
+# Error recovery
+
+- [`recover_with`] wraps a parser so that when it fails, the error is recorded
+  in a [`Recovery`], input is skipped up to a synchronization point, and parsing
+  continues with a placeholder (`None`) output.
+- [`parse_recovering`] runs a parser and returns its output alongside every
+  error which was recovered from.
+- Recovered errors are undone when the parse they belong to is abandoned:
+  [`recover_with`] discards those recorded while running a parser which then
+  fails, and [`backtracking`] does the same for an alternative of a
+  [`Choice`][crate::parse::Choice] which loses. Only one error is recorded at
+  each position, however many times it is retried.
+
+*/
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    input::Input,
+    parse::{Parser, ParserError},
+    util::splits::splits,
+};
+
+/// A shared collection of the errors recovered from during a parse.
+/// Cloning a `Recovery` produces a handle to the same collection.
+pub struct Recovery<E> {
+    /// Each error, with the length of the input remaining where it was found,
+    /// which identifies its position within a single parse.
+    errors: Rc<RefCell<Vec<(usize, E)>>>,
+}
+impl<E> Recovery<E> {
+    pub fn new() -> Self {
+        Self {
+            errors: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+    /// Record an error found where the remaining input had length `remaining`,
+    /// unless one has already been recorded there.
+    pub fn report(&self, remaining: usize, error: E) {
+        let mut errors = self.errors.borrow_mut();
+        if errors.iter().all(|(at, _)| *at != remaining) {
+            errors.push((remaining, error));
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.errors.borrow().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.errors.borrow().is_empty()
+    }
+    /// A point to [`rollback`][Self::rollback] to: the number of errors
+    /// recorded so far.
+    pub fn checkpoint(&self) -> usize {
+        self.len()
+    }
+    /// Discard every error recorded since `checkpoint`.
+    pub fn rollback(&self, checkpoint: usize) {
+        self.errors.borrow_mut().truncate(checkpoint);
+    }
+    /// Remove & return every error recorded so far.
+    pub fn take(&self) -> Vec<E> {
+        std::mem::take(&mut *self.errors.borrow_mut())
+            .into_iter()
+            .map(|(_, e)| e)
+            .collect()
+    }
+}
+impl<E> Clone for Recovery<E> {
+    fn clone(&self) -> Self {
+        Self {
+            errors: self.errors.clone(),
+        }
+    }
+}
+impl<E> Default for Recovery<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// On an error or failure from `p`, record it in `recovery`, skip input using
+/// `skip` and output `None`. Any errors recovered from within `p` before it
+/// failed are discarded, as its output is.
+/// If `skip` fails, the original error is returned instead.
+pub fn recover_with<
+    I: Input,
+    O,
+    SkipOutput,
+    E: From<F>,
+    F,
+    P: Parser<I, O, E, F>,
+    Skip: Parser<I, SkipOutput, E, F>,
+>(
+    p: P,
+    skip: Skip,
+    recovery: &Recovery<E>,
+) -> impl Parser<I, Option<O>, E, F> {
+    let recovery = recovery.clone();
+    move |input: &I| {
+        let checkpoint = recovery.checkpoint();
+        let error = match p.parse(input) {
+            Ok((o, remaining)) => return Ok((Some(o), remaining)),
+            Err(ParserError::Error(e)) => ParserError::Error(e),
+            Err(ParserError::Failure(e)) => ParserError::Failure(e),
+        };
+        recovery.rollback(checkpoint);
+        match skip.parse(input) {
+            Ok((_, remaining)) => {
+                recovery.report(
+                    input.len(),
+                    match error {
+                        ParserError::Error(e) => e,
+                        ParserError::Failure(e) => e.into(),
+                    },
+                );
+                Ok((None, remaining))
+            }
+            Err(ParserError::Error(_)) => Err(error),
+            Err(ParserError::Failure(e)) => Err(ParserError::Failure(e)),
+        }
+    }
+}
+
+/// Run `p`, discarding any errors recovered from within it if it ends in an
+/// error or failure. Wrap the alternatives of a [`Choice`][crate::parse::Choice]
+/// in this, so that one which recovers and then loses leaves no errors behind.
+pub fn backtracking<I, O, E, F, P: Parser<I, O, E, F>, R>(
+    p: P,
+    recovery: &Recovery<R>,
+) -> impl Parser<I, O, E, F> {
+    let recovery = recovery.clone();
+    move |input: &I| {
+        let checkpoint = recovery.checkpoint();
+        let result = p.parse(input);
+        if result.is_err() {
+            recovery.rollback(checkpoint);
+        }
+        result
+    }
+}
+
+/// Skip input until `sync` would match, or until the end of input. The
+/// synchronization point itself is not consumed.
+/// `sync` is typically a tag, and may not fail permanently.
+pub fn skip_until<I: Input, O, E, F, P: Parser<I, O, E>>(sync: P) -> impl Parser<I, I, E, F> {
+    move |input: &I| {
+        for (skipped, remaining) in splits(input) {
+            match sync.parse(&remaining) {
+                Ok(_) => return Ok((skipped, remaining)),
+                Err(ParserError::Error(_)) => (),
+                Err(ParserError::Failure(never)) => unreachable!(),
+            }
+        }
+
+        Ok(input.take_all())
+    }
+}
+
+/// Skip input until `sync` matches, and consume the match. If `sync` never
+/// matches, skip to the end of input.
+pub fn skip_past<I: Input, O, E, F, P: Parser<I, O, E>>(sync: P) -> impl Parser<I, I, E, F> {
+    move |input: &I| {
+        for (skipped, remaining) in splits(input) {
+            match sync.parse(&remaining) {
+                Ok((_, r)) => return Ok(input.split_at(input.len() - r.len())),
+                Err(ParserError::Error(_)) => (),
+                Err(ParserError::Failure(never)) => unreachable!(),
+            }
+        }
+
+        Ok(input.take_all())
+    }
+}
+
+/// The result of [`parse_recovering`].
+#[derive(Debug, PartialEq)]
+pub struct Recovered<I, O, E, F> {
+    /// The output & remaining input, if the top-level parser succeeded. The
+    /// output may contain placeholders for sections which were recovered.
+    pub output: Option<(O, I)>,
+    /// Every error recovered from, in the order they occurred, followed by the
+    /// error which stopped parsing (if any).
+    pub errors: Vec<E>,
+    /// The failure which stopped parsing, if any.
+    pub failure: Option<F>,
+}
+impl<I, O, E, F> Recovered<I, O, E, F> {
+    /// Parsing completed without encountering any errors.
+    pub fn is_clean(&self) -> bool {
+        self.output.is_some() && self.errors.is_empty() && self.failure.is_none()
+    }
+}
+
+/// Run `parser`, collecting every error recorded in `recovery` along the way.
+pub fn parse_recovering<I, O, E, F, P: Parser<I, O, E, F>>(
+    parser: &P,
+    input: &I,
+    recovery: &Recovery<E>,
+) -> Recovered<I, O, E, F> {
+    let result = parser.parse(input);
+    let mut errors = recovery.take();
+    match result {
+        Ok(output) => Recovered {
+            output: Some(output),
+            errors,
+            failure: None,
+        },
+        Err(ParserError::Error(e)) => {
+            errors.push(e);
+            Recovered {
+                output: None,
+                errors,
+                failure: None,
+            }
+        }
+        Err(ParserError::Failure(e)) => Recovered {
+            output: None,
+            errors,
+            failure: Some(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        combinators::many::{delimited, many},
+        input::{span::Span, Slice},
+        parse::{Choice, Expected, Sequence},
+        primitives::{numbers::digits, whitespace::whitespace},
+    };
+
+    type E = Expected<Span>;
+
+    #[test]
+    fn statements() {
+        let recovery = Recovery::<E>::new();
+        let body = (whitespace, "=", whitespace, digits, ";")
+            .and()
+            .to_failure::<E>()
+            .or_fail();
+        let statement = (whitespace.opt().to_failure(), "let".to_failure(), body)
+            .and()
+            .map(|(_, _, (_, _, _, n, _)): (_, _, (_, _, _, Span, _))| n.as_str().to_string());
+        let statement = recover_with(statement, skip_past(";"), &recovery)
+            .map(|n| n.unwrap_or_else(|| "?".to_string()));
+        let program = many(statement);
+
+        let input = Span::anonymous("let = 1;\nlet = x;\nlet = 3\nlet = 4;");
+        let recovered = parse_recovering(&program, &input, &recovery);
+        let (output, remaining) = recovered.output.unwrap();
+
+        assert_eq!(output, vec!["1", "?", "?"]);
+        assert!(remaining.is_empty());
+        let positions = recovered
+            .errors
+            .iter()
+            .map(|e| e.input().position())
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(2, 7), (3, 8)]);
+    }
+
+    #[test]
+    fn delimited_elements() {
+        let recovery = Recovery::<E>::new();
+        let element = recover_with(digits, skip_until((",", "]").or()), &recovery);
+        let array = ("[", delimited(element, ","), "]")
+            .and()
+            .map(|(_, elements, _)| elements);
+
+        let input = Span::anonymous("[1,,x2,3]");
+        let recovered = parse_recovering(&array, &input, &recovery);
+        let (output, _) = recovered.output.unwrap();
+        let output = output
+            .into_iter()
+            .map(|n| n.map(|n| n.as_str().to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(output, vec![Some("1".into()), None, None, Some("3".into())]);
+        assert_eq!(recovered.errors.len(), 2);
+    }
+
+    #[test]
+    fn unrecovered_error_is_reported_last() {
+        let recovery = Recovery::<E>::new();
+        let element = recover_with(digits, skip_until(","), &recovery);
+        let array = ("[", delimited(element, ","), "]").and();
+
+        let input = Span::anonymous("[x,1");
+        let recovered = parse_recovering(&array, &input, &recovery);
+
+        assert!(recovered.output.is_none());
+        let positions = recovered
+            .errors
+            .iter()
+            .map(|e| e.input().position())
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1, 2), (1, 5)]);
+    }
+
+    #[test]
+    fn losing_alternative_is_rolled_back() {
+        let recovery = Recovery::<E>::new();
+        let element = recover_with(digits, skip_until(";"), &recovery);
+        let statement = (
+            backtracking((element, "!").and().map(|_| "shout"), &recovery),
+            (whitespace.opt(), "x;").and().map(|_| "x"),
+        )
+            .or();
+
+        let input = Span::anonymous("x;");
+        let recovered = parse_recovering(&statement, &input, &recovery);
+        assert_eq!(recovered.output.unwrap().0, "x");
+        assert!(recovered.errors.is_empty());
+    }
+
+    #[test]
+    fn failed_parser_discards_inner_errors() {
+        let recovery = Recovery::<E>::new();
+        let inner = recover_with(digits, skip_until(","), &recovery);
+        let pair = (inner, ",", digits).and();
+        let outer = recover_with(pair, skip_past(";"), &recovery);
+
+        let input = Span::anonymous("x,y;");
+        let recovered = parse_recovering(&outer, &input, &recovery);
+        assert!(recovered.output.unwrap().0.is_none());
+        let positions = recovered
+            .errors
+            .iter()
+            .map(|e| e.input().position())
+            .collect::<Vec<_>>();
+        // Only the error at `y` which stopped the pair, not the one at `x`.
+        assert_eq!(positions, vec![(1, 3)]);
+    }
+
+    #[test]
+    fn one_error_per_position() {
+        let recovery = Recovery::<E>::new();
+        let elements = || many(recover_with(digits, skip_until(","), &recovery));
+        let retried = (elements(), elements()).and();
+
+        let input = Span::anonymous(",1");
+        let recovered = parse_recovering(&retried, &input, &recovery);
+        assert!(recovered.output.is_some());
+        assert_eq!(recovered.errors.len(), 1);
+    }
+}
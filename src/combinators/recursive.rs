@@ -0,0 +1,278 @@
+/*!
+# Recursive parsers
+
+- [`recursive`] builds a parser which refers to itself, such as nested arrays.
+- [`Deferred`] is a parser which is declared first and defined later, so that
+  several parsers can refer to each other.
+- [`recursive_stream`] & [`StreamingDeferred`] are the streaming equivalents.
+
+NB: Every clone of a `Deferred` keeps its definition alive. A definition which
+contains a clone of itself is a reference cycle, and will never be freed. The
+handle passed to [`recursive`]'s closure does not have this problem, and
+[`Deferred::weak`] gives such a handle for other definitions.
+
+*/
+
+use std::{
+    cell::OnceCell,
+    rc::{Rc, Weak},
+};
+
+use crate::parse::{Parser, ParserResult, StreamingParser, StreamingResult};
+
+type Boxed<'a, I, O, E, F> = Box<dyn Fn(&I) -> ParserResult<I, O, E, F> + 'a>;
+type StreamingBoxed<'a, I, O, E, F> = Box<dyn Fn(&I) -> StreamingResult<I, O, E, F> + 'a>;
+
+/// A shared reference to a parser's definition. References from within the
+/// definition itself are weak, so that it can be freed.
+enum Handle<T> {
+    Strong(Rc<OnceCell<T>>),
+    Weak(Weak<OnceCell<T>>),
+}
+impl<T> Handle<T> {
+    fn new() -> Self {
+        Self::Strong(Rc::new(OnceCell::new()))
+    }
+    fn downgrade(&self) -> Self {
+        match self {
+            Handle::Strong(rc) => Handle::Weak(Rc::downgrade(rc)),
+            Handle::Weak(weak) => Handle::Weak(weak.clone()),
+        }
+    }
+    fn define(&self, definition: T) {
+        let Handle::Strong(rc) = self else {
+            panic!("A recursive parser can't be redefined from within itself")
+        };
+        if rc.set(definition).is_err() {
+            panic!("Deferred parser defined twice")
+        }
+    }
+    fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let rc = match self {
+            Handle::Strong(rc) => rc.clone(),
+            Handle::Weak(weak) => weak
+                .upgrade()
+                .expect("Recursive parser used after it was dropped"),
+        };
+        let definition = rc.get().expect("Deferred parser used before being defined");
+        f(definition)
+    }
+}
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Handle::Strong(rc) => Handle::Strong(rc.clone()),
+            Handle::Weak(weak) => Handle::Weak(weak.clone()),
+        }
+    }
+}
+
+/// A parser which is declared before it is defined. Cloning a `Deferred`
+/// produces a handle to the same definition.
+///
+/// Parsing with a `Deferred` which hasn't been [`define`][Deferred::define]d
+/// panics.
+pub struct Deferred<'a, I, O, E, F> {
+    handle: Handle<Boxed<'a, I, O, E, F>>,
+}
+impl<'a, I, O, E, F> Deferred<'a, I, O, E, F> {
+    pub fn new() -> Self {
+        Self {
+            handle: Handle::new(),
+        }
+    }
+    /// Panics if this parser has already been defined.
+    pub fn define(&self, parser: impl Parser<I, O, E, F> + 'a) {
+        self.handle
+            .define(Box::new(move |input: &I| parser.parse(input)));
+    }
+    /// A handle which doesn't keep the definition alive, for use within the
+    /// definition itself. Parsing with it after every other handle has been
+    /// dropped panics.
+    pub fn weak(&self) -> Self {
+        Self {
+            handle: self.handle.downgrade(),
+        }
+    }
+}
+impl<I, O, E, F> Clone for Deferred<'_, I, O, E, F> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+        }
+    }
+}
+impl<I, O, E, F> Default for Deferred<'_, I, O, E, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<I, O, E, F> Parser<I, O, E, F> for Deferred<'_, I, O, E, F> {
+    fn parse(&self, input: &I) -> ParserResult<I, O, E, F> {
+        self.handle.with(|parser| parser(input))
+    }
+}
+
+/// Build a parser which refers to itself. `f` is given a handle to the parser
+/// being built.
+pub fn recursive<'a, I, O, E, F, P: Parser<I, O, E, F> + 'a>(
+    f: impl FnOnce(Deferred<'a, I, O, E, F>) -> P,
+) -> Deferred<'a, I, O, E, F> {
+    let deferred = Deferred::new();
+    deferred.define(f(deferred.weak()));
+    deferred
+}
+
+/// As [`Deferred`], for a [`StreamingParser`].
+pub struct StreamingDeferred<'a, I, O, E, F> {
+    handle: Handle<StreamingBoxed<'a, I, O, E, F>>,
+}
+impl<'a, I, O, E, F> StreamingDeferred<'a, I, O, E, F> {
+    pub fn new() -> Self {
+        Self {
+            handle: Handle::new(),
+        }
+    }
+    /// Panics if this parser has already been defined.
+    pub fn define(&self, parser: impl StreamingParser<I, O, E, F> + 'a) {
+        self.handle
+            .define(Box::new(move |input: &I| parser.parse_stream(input)));
+    }
+    /// As [`Deferred::weak`].
+    pub fn weak(&self) -> Self {
+        Self {
+            handle: self.handle.downgrade(),
+        }
+    }
+}
+impl<I, O, E, F> Clone for StreamingDeferred<'_, I, O, E, F> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+        }
+    }
+}
+impl<I, O, E, F> Default for StreamingDeferred<'_, I, O, E, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<I, O, E, F> StreamingParser<I, O, E, F> for StreamingDeferred<'_, I, O, E, F> {
+    fn parse_stream(&self, input: &I) -> StreamingResult<I, O, E, F> {
+        self.handle.with(|parser| parser(input))
+    }
+}
+
+/// As [`recursive`], for a [`StreamingParser`].
+pub fn recursive_stream<'a, I, O, E, F, P: StreamingParser<I, O, E, F> + 'a>(
+    f: impl FnOnce(StreamingDeferred<'a, I, O, E, F>) -> P,
+) -> StreamingDeferred<'a, I, O, E, F> {
+    let deferred = StreamingDeferred::new();
+    deferred.define(f(deferred.weak()));
+    deferred
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        combinators::many::delimited,
+        parse::{
+            Choice, Incomplete, IntoStreamingParser, NotFound, ParserError, Sequence,
+            StreamingChoice, StreamingError, StreamingOk, StreamingSequence,
+        },
+        primitives::numbers::digits,
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum Value {
+        Number(u32),
+        Array(Vec<Value>),
+    }
+
+    #[test]
+    fn nested_arrays() {
+        let value = recursive(|value| {
+            (
+                digits.map(|n: &str| Value::Number(n.parse().unwrap())),
+                ("[", delimited(value, ",").opt(), "]")
+                    .and()
+                    .map(|(_, values, _)| Value::Array(values.unwrap_or_default())),
+            )
+                .or()
+        });
+
+        assert_eq!(
+            value.parse(&"[1,[2,[]],[[3]]]x"),
+            Ok((
+                Value::Array(vec![
+                    Value::Number(1),
+                    Value::Array(vec![Value::Number(2), Value::Array(vec![])]),
+                    Value::Array(vec![Value::Array(vec![Value::Number(3)])]),
+                ]),
+                "x"
+            ))
+        );
+        assert_eq!(
+            value.parse(&"[1,"),
+            Err::<(_, &str), _>(ParserError::Error(NotFound))
+        );
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        // expr := term ("+" expr)?
+        // term := digits | "(" expr ")"
+        let expr = Deferred::<&str, u32, NotFound, _>::new();
+        let term = Deferred::new();
+        expr.define(
+            (term.weak(), ("+", expr.weak()).and().opt())
+                .and()
+                .map(|(l, r): (u32, Option<(_, u32)>)| l + r.map_or(0, |(_, r)| r)),
+        );
+        term.define(
+            (
+                digits.map(|n: &str| n.parse::<u32>().unwrap()),
+                ("(", expr.weak(), ")").and().map(|(_, n, _)| n),
+            )
+                .or(),
+        );
+
+        assert_eq!(expr.parse(&"1+(2+3)+4"), Ok((10, "")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Deferred parser used before being defined")]
+    fn undefined() {
+        let p = Deferred::<&str, (), NotFound, NotFound>::new();
+        let _ = p.parse(&"");
+    }
+
+    fn tag(
+        t: &'static str,
+    ) -> impl StreamingParser<&'static str, &'static str, NotFound, Incomplete> {
+        t.stream().to_failure()
+    }
+
+    fn end(s: &&'static str) -> StreamingResult<&'static str, (), NotFound, Incomplete> {
+        match s.is_empty() {
+            true => Err(StreamingError::Incomplete(Incomplete)),
+            false => Ok(StreamingOk::Complete((), *s)),
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let parens =
+            recursive_stream(|parens| ((tag("("), parens, tag(")")).and().map(|_| ()), end).or());
+
+        assert_eq!(
+            parens.parse_stream(&"(())x"),
+            Ok(StreamingOk::Complete((), "x"))
+        );
+        assert_eq!(
+            parens.parse_stream(&"(("),
+            Err(StreamingError::<NotFound, _>::Incomplete(Incomplete))
+        );
+    }
+}
@@ -14,8 +14,8 @@ pub fn sandwich<
 ) -> impl Parser<I, ContentOutput, E, F> {
     move |input: &I| {
         let (_, remaining) = bread.parse(input)?;
-        let (output, remaining) = content.parse(input)?;
-        let (_, remaining) = bread.parse(input)?;
+        let (output, remaining) = content.parse(&remaining)?;
+        let (_, remaining) = bread.parse(&remaining)?;
         Ok((output, remaining))
     }
 }
@@ -37,8 +37,8 @@ pub fn between<
 ) -> impl Parser<I, ContentOutput, E, F> {
     move |input: &I| {
         let (_, remaining) = start.parse(input)?;
-        let (output, remaining) = content.parse(input)?;
-        let (_, remaining) = end.parse(input)?;
+        let (output, remaining) = content.parse(&remaining)?;
+        let (_, remaining) = end.parse(&remaining)?;
         Ok((output, remaining))
     }
 }
@@ -60,8 +60,36 @@ pub fn key_value<
 ) -> impl Parser<I, (KeyOutput, ValueOutput), E, F> {
     move |input: &I| {
         let (key, remaining) = start.parse(input)?;
-        let (_, remaining) = content.parse(input)?;
-        let (value, remaining) = end.parse(input)?;
+        let (_, remaining) = content.parse(&remaining)?;
+        let (value, remaining) = end.parse(&remaining)?;
         Ok(((key, value), remaining))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parse::NotFound,
+        primitives::{numbers::digits, tag::tag},
+    };
+
+    #[test]
+    fn threads_remaining_input() {
+        assert_eq!(
+            sandwich(tag("|"), digits::<_, NotFound>).parse(&"|42| rest"),
+            Ok(("42", " rest"))
+        );
+        assert_eq!(
+            between(tag("("), digits::<_, NotFound>, tag(")")).parse(&"(7)"),
+            Ok(("7", ""))
+        );
+        assert_eq!(
+            key_value(digits::<_, NotFound>, tag("="), digits).parse(&"1=2;"),
+            Ok((("1", "2"), ";"))
+        );
+        assert!(between(tag("("), digits::<_, NotFound>, tag(")"))
+            .parse(&"(7")
+            .is_err());
+    }
+}
@@ -0,0 +1,298 @@
+/*!
+# Diagnostics
+
+- A [`Diagnostic`] is a message attached to one or more [`Span`]s, rendered in
+  the style of a compiler error:
+
+```text
+error: expected `]`, found ';'
+ --> config:2:3
+  |
+2 | [1;
+  |   ^ expected `]`
+  |
+  = note: arrays are closed with `]`
+```
+
+*/
+
+use std::fmt;
+
+use crate::{
+    input::{span::Span, Input, Slice},
+    parse::Expected,
+};
+
+mod render;
+
+pub use render::Style;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+            Severity::Note => f.write_str("note"),
+            Severity::Help => f.write_str("help"),
+        }
+    }
+}
+
+/// A region of source to underline, with an optional message.
+#[derive(Clone, Debug)]
+pub struct Label {
+    span: Span,
+    message: Option<String>,
+}
+impl Label {
+    pub fn new(span: Span) -> Self {
+        Self {
+            span,
+            message: None,
+        }
+    }
+    pub fn with_message(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: Some(message.into()),
+        }
+    }
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    primary: Label,
+    secondary: Vec<Label>,
+    notes: Vec<String>,
+}
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            primary: Label::new(span),
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Error, message, span)
+    }
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Warning, message, span)
+    }
+    /// Set the message shown under the primary span.
+    pub fn with_label(mut self, message: impl Into<String>) -> Self {
+        self.primary.message = Some(message.into());
+        self
+    }
+    /// Underline another span, which may be in a different source.
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label::with_message(span, message));
+        self
+    }
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+    pub fn primary(&self) -> &Label {
+        &self.primary
+    }
+    pub fn secondary(&self) -> &[Label] {
+        &self.secondary
+    }
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+    pub fn render(&self, style: Style) -> String {
+        render::render(self, style)
+    }
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(Style::Plain))
+    }
+}
+
+impl From<Expected<Span>> for Diagnostic {
+    fn from(value: Expected<Span>) -> Self {
+        let mut diagnostic = Diagnostic::error(value.to_string(), first_char(value.input()));
+        for frame in value.context() {
+            let message = format!("in {}", frame.label());
+            diagnostic = diagnostic.with_secondary(first_char(frame.input()), message);
+        }
+        diagnostic
+    }
+}
+
+/// Point at a single character rather than the rest of the input.
+fn first_char(span: &Span) -> Span {
+    span.take(span.as_str().chars().next().map_or(0, char::len_utf8))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parse::{Parser, ParserError, Sequence},
+        primitives::{numbers::digits, whitespace::whitespace},
+    };
+
+    #[test]
+    fn single_line() {
+        let source = Span::new("config", "[1, 2]\n[1;\n");
+        let (_, second_line) = crate::primitives::line::line::<_, crate::parse::NotFound>
+            .parse(&source)
+            .unwrap();
+        let diagnostic = Diagnostic::error("expected `]`, found ';'", second_line.slice(2..3))
+            .with_label("expected `]`")
+            .with_note("arrays are closed with `]`");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "\
+error: expected `]`, found ';'
+ --> config:2:3
+  |
+2 | [1;
+  |   ^ expected `]`
+  |
+  = note: arrays are closed with `]`
+"
+        );
+    }
+
+    #[test]
+    fn multi_line() {
+        let source = Span::new("doc", "a = {\n  b = 1\n}\nc = 2\n");
+        let diagnostic = Diagnostic::error("unexpected table", source.slice(4..15));
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "\
+error: unexpected table
+ --> doc:1:5
+  |
+1 | a = {
+  |     ^
+2 |   b = 1
+  | ^^^^^^^
+3 | }
+  | ^
+"
+        );
+    }
+
+    #[test]
+    fn secondary_labels() {
+        let source = Span::new("doc", "x = 1\ny = 2\n\n\nx = 3\n");
+        let diagnostic = Diagnostic::error("duplicate key `x`", source.slice(14..15))
+            .with_label("redefined here")
+            .with_secondary(source.slice(0..1), "first defined here");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "\
+error: duplicate key `x`
+ --> doc:5:1
+  |
+1 | x = 1
+  | - first defined here
+...
+5 | x = 3
+  | ^ redefined here
+"
+        );
+    }
+
+    #[test]
+    fn end_of_input() {
+        let source = Span::new("doc", "[1");
+        let diagnostic = Diagnostic::error("unclosed array", source.skip(2));
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "\
+error: unclosed array
+ --> doc:1:3
+  |
+1 | [1
+  |   ^
+"
+        );
+    }
+
+    #[test]
+    fn ansi() {
+        let source = Span::new("doc", "x");
+        let diagnostic = Diagnostic::warning("unused", source.clone());
+        let rendered = diagnostic.render(Style::Ansi);
+
+        assert!(rendered.starts_with("\x1b[1;33mwarning\x1b[0m"));
+        assert!(rendered.contains("\x1b[1;33m^\x1b[0m"));
+    }
+
+    #[test]
+    fn from_expected() {
+        let source = Span::new("doc", "[1;");
+        let Err(ParserError::Error(e)) = ("[", digits, "]").and().parse(&source) else {
+            panic!("Parser should have failed")
+        };
+        let e: Expected<Span> = e;
+
+        assert_eq!(
+            Diagnostic::from(e).to_string(),
+            "\
+error: expected `]`, found ';'
+ --> doc:1:3
+  |
+1 | [1;
+  |   ^
+"
+        );
+    }
+
+    #[test]
+    fn from_expected_with_context() {
+        let source = Span::new("doc", "[\n  1;\n");
+        let parser = ("[", whitespace, digits, "]").and().context("array");
+        let Err(ParserError::Error(e)) = parser.parse(&source) else {
+            panic!("Parser should have failed")
+        };
+        let e: Expected<Span> = e;
+
+        assert_eq!(
+            Diagnostic::from(e).to_string(),
+            "\
+error: expected `]`, found ';'
+ --> doc:2:4
+  |
+1 | [
+  | - in array
+2 |   1;
+  |    ^
+"
+        );
+    }
+}
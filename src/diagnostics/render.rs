@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use super::{Diagnostic, Label, Severity};
+use crate::input::span::Span;
+
+/// How a [`Diagnostic`] should be rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Style {
+    #[default]
+    Plain,
+    /// Colourize with ANSI escape codes, for display in a terminal.
+    Ansi,
+}
+impl Style {
+    fn paint(self, code: &str, s: &str) -> String {
+        match self {
+            Style::Plain => s.to_string(),
+            Style::Ansi => format!("\x1b[{}m{}\x1b[0m", code, s),
+        }
+    }
+}
+
+const BOLD: &str = "1";
+const GUTTER: &str = "1;34";
+
+fn severity_code(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "1;31",
+        Severity::Warning => "1;33",
+        Severity::Note => "1;32",
+        Severity::Help => "1;36",
+    }
+}
+
+/// A single underline beneath one line of source.
+struct Mark<'a> {
+    line: usize,
+    start: usize,
+    end: usize,
+    primary: bool,
+    message: Option<&'a str>,
+}
+
+pub(super) fn render(diagnostic: &Diagnostic, style: Style) -> String {
+    let mut output = String::new();
+    let severity = diagnostic.severity();
+    let code = severity_code(severity);
+    output.push_str(&style.paint(code, &severity.to_string()));
+    output.push_str(&style.paint(BOLD, &format!(": {}", diagnostic.message())));
+    output.push('\n');
+
+    // Labels are grouped by source, with the primary label's source first.
+    let mut groups: Vec<Vec<(&Label, bool)>> = vec![vec![(diagnostic.primary(), true)]];
+    for label in diagnostic.secondary() {
+        match groups
+            .iter_mut()
+            .find(|group| group[0].0.span().same_source(label.span()))
+        {
+            Some(group) => group.push((label, false)),
+            None => groups.push(vec![(label, false)]),
+        }
+    }
+
+    let width = groups
+        .iter()
+        .flatten()
+        .map(|(label, _)| {
+            let span = label.span();
+            let idx = span.line_index(last_offset(span));
+            span.line_number(idx).to_string().len()
+        })
+        .max()
+        .unwrap_or(1);
+    let gutter = |s: &str| style.paint(GUTTER, &format!("{:>width$} |", s));
+
+    for group in groups.iter() {
+        let (first, _) = group[0];
+        let (id, (line, column)) = first.span().location();
+        let arrow = style.paint(GUTTER, &format!("{:>width$}-->", ""));
+        if id.is_empty() {
+            let _ = writeln!(output, "{} {}:{}", arrow, line, column);
+        } else {
+            let _ = writeln!(output, "{} {}:{}:{}", arrow, id, line, column);
+        }
+        let _ = writeln!(output, "{}", gutter(""));
+
+        let span = first.span();
+        let mut marks = group
+            .iter()
+            .flat_map(|(label, primary)| marks(label, *primary))
+            .collect::<Vec<_>>();
+        marks.sort_by_key(|mark| (mark.line, mark.start));
+
+        let mut previous: Option<usize> = None;
+        for mark in marks.iter() {
+            if previous != Some(mark.line) {
+                if previous.is_some_and(|previous| previous + 1 < mark.line) {
+                    output.push_str(&style.paint(GUTTER, "..."));
+                    output.push('\n');
+                }
+                let (_, text) = span.line_text(mark.line);
+                let number = span.line_number(mark.line).to_string();
+                let _ = writeln!(output, "{} {}", gutter(&number), text);
+                previous = Some(mark.line);
+            }
+
+            let (_, text) = span.line_text(mark.line);
+            let padding = text[..mark.start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let underline = if mark.primary { "^" } else { "-" }
+                .repeat(text[mark.start..mark.end].chars().count().max(1));
+            let underline_code = if mark.primary { code } else { GUTTER };
+            let mut row = format!(
+                "{} {}{}",
+                gutter(""),
+                padding,
+                style.paint(underline_code, &underline)
+            );
+            if let Some(message) = mark.message {
+                row.push(' ');
+                row.push_str(&style.paint(underline_code, message));
+            }
+            output.push_str(row.trim_end());
+            output.push('\n');
+        }
+    }
+
+    if !diagnostic.notes().is_empty() {
+        let _ = writeln!(output, "{}", gutter(""));
+    }
+    for note in diagnostic.notes() {
+        let _ = writeln!(
+            output,
+            "{} {} {}",
+            style.paint(GUTTER, &format!("{:>width$} =", "")),
+            style.paint(BOLD, "note:"),
+            note
+        );
+    }
+
+    output
+}
+
+/// The offset of the last byte of a span, or its start if it is empty.
+fn last_offset(span: &Span) -> usize {
+    let range = span.range();
+    range.end.saturating_sub(1).max(range.start)
+}
+
+/// Break a label into one underline per line it covers. The message is shown
+/// beneath the last line.
+fn marks(label: &Label, primary: bool) -> Vec<Mark<'_>> {
+    let span = label.span();
+    let range = span.range();
+    let first = span.line_index(range.start);
+    let last = span.line_index(last_offset(span));
+
+    (first..=last)
+        .map(|line| {
+            let (offset, text) = span.line_text(line);
+            let clamp = |n: usize| n.saturating_sub(offset).min(text.len());
+            Mark {
+                line,
+                start: clamp(range.start),
+                end: clamp(range.end),
+                primary,
+                message: if line == last { label.message() } else { None },
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,558 @@
+/*!
+# Stream drivers
+
+- A [`StreamDriver`] reads from a [`std::io::Read`] into a buffer and runs a
+  [`StreamingParser`] over it repeatedly, yielding each complete output.
+- When the parser returns [`StreamingError::Incomplete`] or
+  [`StreamingOk::Partial`], more data is read and the parser is run again.
+- Input is built from the buffer once per read, and each output's remaining
+  input is reused for the next, so many small outputs don't copy the buffer
+  again each time. Positions are relative to the whole stream rather than the
+  buffer.
+- Consumed data is skipped, and only dropped from the buffer once it makes up
+  more than half of it.
+- While an output stays incomplete, the parser is only run again once more
+  data has arrived, and each read is as large as the data already buffered,
+  so an output of `n` bytes takes `O(log n)` attempts rather than `O(n)`.
+- The buffer is limited to [`DEFAULT_MAX_BUFFER_SIZE`] bytes unless set with
+  [`with_max_buffer_size`][StreamDriver::with_max_buffer_size], and an output
+  which needs more fails with [`DriverError::BufferFull`].
+- With the `tokio` feature, `AsyncStreamDriver` does the same for a
+  `tokio::io::AsyncRead`, yielding outputs as a `futures_core::Stream`.
+
+*/
+
+use std::{fmt, io, marker::PhantomData};
+
+#[cfg(feature = "tokio")]
+mod tokio;
+
+#[cfg(feature = "tokio")]
+pub use self::tokio::AsyncStreamDriver;
+
+use crate::{
+    input::{
+        bytes::SharedBytes,
+        span::{Id, Span},
+        Slice,
+    },
+    parse::{StreamingError, StreamingOk, StreamingParser},
+};
+
+/// Input which can be built from a [`StreamDriver`]'s buffer.
+pub trait StreamInput: Slice + Sized {
+    /// Where in the stream a buffer begins.
+    type Position: Clone + Default;
+
+    /// Build input over the usable prefix of `buffer`, which begins at
+    /// `position`. `complete` is set when no more data will follow.
+    fn from_buffer(buffer: &[u8], position: &Self::Position, complete: bool) -> io::Result<Self>;
+    /// The position just after `consumed`, a prefix of some input built by
+    /// [`from_buffer`][StreamInput::from_buffer].
+    fn advance(position: &Self::Position, consumed: &Self) -> Self::Position;
+}
+
+/// The location in a stream of text where a buffer begins.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextPosition {
+    pub id: Id,
+    /// The number of lines before this position.
+    pub line: usize,
+    /// The number of bytes before this position on its line.
+    pub column: usize,
+}
+impl TextPosition {
+    pub fn new(id: impl Into<Id>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl StreamInput for Span {
+    type Position = TextPosition;
+
+    fn from_buffer(buffer: &[u8], position: &TextPosition, complete: bool) -> io::Result<Self> {
+        // A character may be split across reads, in which case its start is
+        // left in the buffer until the rest arrives.
+        let valid = match std::str::from_utf8(buffer) {
+            Ok(s) => s,
+            Err(e) if e.error_len().is_none() && !complete => {
+                std::str::from_utf8(&buffer[..e.valid_up_to()]).expect("Prefix should be valid")
+            }
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        Ok(Span::new_continued_at(
+            position.id.clone(),
+            valid,
+            position.line,
+            position.column,
+        ))
+    }
+    fn advance(position: &TextPosition, consumed: &Self) -> TextPosition {
+        let (line, column) = consumed.end_position();
+        TextPosition {
+            id: position.id.clone(),
+            line: line - 1,
+            column: column - 1,
+        }
+    }
+}
+
+impl StreamInput for SharedBytes {
+    /// The byte offset from the start of the stream.
+    type Position = usize;
+
+    fn from_buffer(buffer: &[u8], position: &usize, complete: bool) -> io::Result<Self> {
+        Ok(SharedBytes::new_continued(buffer.to_vec(), *position))
+    }
+    fn advance(position: &usize, consumed: &Self) -> usize {
+        position + consumed.len()
+    }
+}
+
+/// An error from a [`StreamDriver`].
+#[derive(Debug)]
+pub enum DriverError<E, F> {
+    Io(io::Error),
+    /// The parser returned an error.
+    Error(E),
+    /// The parser failed.
+    Failure(F),
+    /// The stream ended part way through an output.
+    Incomplete(F),
+    /// An output needed more data than the maximum buffer size.
+    BufferFull,
+}
+impl<E, F> From<io::Error> for DriverError<E, F> {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+impl<E: fmt::Display, F: fmt::Display> fmt::Display for DriverError<E, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriverError::Io(e) => write!(f, "read failed: {e}"),
+            DriverError::Error(e) => e.fmt(f),
+            DriverError::Failure(e) => e.fmt(f),
+            DriverError::Incomplete(e) => write!(f, "stream ended early: {e}"),
+            DriverError::BufferFull => f.write_str("output is larger than the buffer"),
+        }
+    }
+}
+impl<E, F> std::error::Error for DriverError<E, F>
+where
+    E: fmt::Debug + fmt::Display,
+    F: fmt::Debug + fmt::Display,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DriverError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+pub const DEFAULT_MAX_BUFFER_SIZE: usize = 64 * 1024 * 1024;
+
+type Phantom<O, E, F> = PhantomData<fn() -> (O, E, F)>;
+
+/// The outcome of a single attempt to parse the buffer.
+enum Step<O, E, F> {
+    Output(O),
+    /// More data is needed before the parser can make progress.
+    Read,
+    End,
+    Error(DriverError<E, F>),
+}
+
+/// The buffer & parser shared by [`StreamDriver`] and its async counterpart,
+/// independent of how data is read.
+struct DriverState<P, I: StreamInput, O, E, F> {
+    parser: P,
+    buffer: Vec<u8>,
+    /// The length of the consumed data at the start of the buffer.
+    cursor: usize,
+    /// Input built from the unconsumed data, reused until more is read.
+    input: Option<I>,
+    /// The position of the data at the cursor.
+    position: I::Position,
+    chunk_size: usize,
+    max_buffer_size: usize,
+    /// The size of the next read, which grows while an output is incomplete.
+    read_size: usize,
+    /// The size of the chunk reserved for a read which is still pending.
+    reserved: usize,
+    /// Set when the parser needs more data than is buffered.
+    waiting: bool,
+    eof: bool,
+    done: bool,
+    phantom: Phantom<O, E, F>,
+}
+impl<P, I: StreamInput, O, E, F> DriverState<P, I, O, E, F> {
+    fn new(parser: P) -> Self {
+        Self {
+            parser,
+            buffer: Vec::new(),
+            cursor: 0,
+            input: None,
+            position: Default::default(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+            read_size: DEFAULT_CHUNK_SIZE,
+            reserved: 0,
+            waiting: false,
+            eof: false,
+            done: false,
+            phantom: PhantomData,
+        }
+    }
+    fn set_chunk_size(&mut self, chunk_size: usize) {
+        self.chunk_size = chunk_size.max(1);
+        self.read_size = self.chunk_size;
+    }
+    fn set_max_buffer_size(&mut self, max_buffer_size: usize) {
+        self.max_buffer_size = max_buffer_size.max(1);
+    }
+    /// The data which has been read but not consumed, without any chunk
+    /// reserved for a read.
+    fn buffered(&self) -> &[u8] {
+        &self.buffer[self.cursor..self.buffer.len() - self.reserved]
+    }
+    /// Extend the buffer by a chunk for a reader to fill. If a chunk is
+    /// already reserved for a pending read, it's reused. Consumed data is
+    /// dropped first if it makes up more than half the buffer.
+    fn reserve(&mut self) -> &mut [u8] {
+        if self.reserved == 0 {
+            if self.cursor > self.buffer.len() / 2 {
+                self.buffer.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            let room = self.max_buffer_size - self.buffered().len();
+            self.reserved = self.read_size.min(room);
+            self.buffer.resize(self.buffer.len() + self.reserved, 0);
+        }
+        let len = self.buffer.len() - self.reserved;
+        &mut self.buffer[len..]
+    }
+    /// Keep the data read into the last [`reserve`][Self::reserve]d chunk.
+    fn commit(&mut self, read: io::Result<usize>) -> io::Result<()> {
+        let len = self.buffer.len() - std::mem::take(&mut self.reserved);
+        match read {
+            Ok(read) => {
+                self.buffer.truncate(len + read);
+                self.eof = read == 0;
+                self.waiting = false;
+                self.input = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.buffer.truncate(len);
+                self.done = true;
+                Err(e)
+            }
+        }
+    }
+    /// Move the cursor past the consumed prefix of `input`, keeping the rest
+    /// to parse next.
+    fn consume(&mut self, input: &I, remaining: I) {
+        let consumed = input.take(input.len() - remaining.len());
+        self.position = I::advance(&self.position, &consumed);
+        self.cursor += consumed.len();
+        self.input = Some(remaining);
+        self.read_size = self.chunk_size;
+    }
+    /// Wait for more data, reading at least as much as is already buffered
+    /// so that the buffer grows geometrically.
+    fn wait(&mut self) -> Step<O, E, F> {
+        let buffered = self.buffered().len();
+        if buffered >= self.max_buffer_size {
+            return self.fail(DriverError::BufferFull);
+        }
+        self.waiting = true;
+        self.read_size = buffered.max(self.chunk_size);
+        Step::Read
+    }
+    fn fail(&mut self, e: DriverError<E, F>) -> Step<O, E, F> {
+        self.done = true;
+        Step::Error(e)
+    }
+    fn step(&mut self) -> Step<O, E, F>
+    where
+        P: StreamingParser<I, O, E, F>,
+    {
+        if self.done {
+            return Step::End;
+        }
+        if self.waiting || self.reserved > 0 {
+            return Step::Read;
+        }
+        if self.buffered().is_empty() {
+            if self.eof {
+                self.done = true;
+                return Step::End;
+            }
+            return Step::Read;
+        }
+
+        let input = match self.input.take() {
+            Some(input) => input,
+            None => match I::from_buffer(self.buffered(), &self.position, self.eof) {
+                Ok(input) => input,
+                Err(e) => return self.fail(e.into()),
+            },
+        };
+        match self.parser.parse_stream(&input) {
+            Ok(StreamingOk::Complete(output, remaining)) => {
+                self.consume(&input, remaining);
+                Step::Output(output)
+            }
+            Ok(StreamingOk::Partial(output, remaining)) if self.eof => {
+                self.consume(&input, remaining);
+                Step::Output(output)
+            }
+            Ok(StreamingOk::Partial(_, _)) => self.wait(),
+            Err(StreamingError::Incomplete(f)) if self.eof => self.fail(DriverError::Incomplete(f)),
+            Err(StreamingError::Incomplete(_)) => self.wait(),
+            Err(StreamingError::Error(e)) => self.fail(DriverError::Error(e)),
+            Err(StreamingError::Failure(f)) => self.fail(DriverError::Failure(f)),
+        }
+    }
+}
+
+/// Feeds a reader into a streaming parser, yielding each output in turn.
+/// Iteration ends at the end of the stream, or after the first error.
+///
+/// NB: A parser which succeeds without consuming any input will be run again
+/// at the same point, so should not be used while more data may follow.
+pub struct StreamDriver<R, P, I: StreamInput, O, E, F> {
+    reader: R,
+    state: DriverState<P, I, O, E, F>,
+}
+impl<R, P, I: StreamInput, O, E, F> StreamDriver<R, P, I, O, E, F> {
+    pub fn new(reader: R, parser: P) -> Self {
+        Self {
+            reader,
+            state: DriverState::new(parser),
+        }
+    }
+    /// The number of bytes to request from the reader at a time. Larger reads
+    /// are made while an output is incomplete.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.state.set_chunk_size(chunk_size);
+        self
+    }
+    /// The most data to buffer while waiting for an output to complete, after
+    /// which it fails with [`DriverError::BufferFull`].
+    pub fn with_max_buffer_size(mut self, max_buffer_size: usize) -> Self {
+        self.state.set_max_buffer_size(max_buffer_size);
+        self
+    }
+    /// Set the position of the start of the stream, e.g. to give it an id.
+    pub fn starting_at(mut self, position: I::Position) -> Self {
+        self.state.position = position;
+        self
+    }
+    /// The position of the first unconsumed data.
+    pub fn position(&self) -> &I::Position {
+        &self.state.position
+    }
+    /// Data which has been read but not yet consumed by the parser.
+    pub fn buffered(&self) -> &[u8] {
+        self.state.buffered()
+    }
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+impl<R: io::Read, P: StreamingParser<I, O, E, F>, I: StreamInput, O, E, F> Iterator
+    for StreamDriver<R, P, I, O, E, F>
+{
+    type Item = Result<O, DriverError<E, F>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.state.step() {
+                Step::Output(output) => return Some(Ok(output)),
+                Step::Read => {
+                    let buffer = self.state.reserve();
+                    let read = loop {
+                        match self.reader.read(buffer) {
+                            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                            read => break read,
+                        }
+                    };
+                    if let Err(e) = self.state.commit(read) {
+                        return Some(Err(e.into()));
+                    }
+                }
+                Step::End => return None,
+                Step::Error(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::{bytes::ByteInput, Addressable, Input},
+        parse::{Incomplete, NotFound, Parser, StreamingResult},
+        primitives::{
+            binary::{be_u16_stream, length_prefixed_stream},
+            bytes::take,
+        },
+    };
+
+    /// A reader which returns at most `n` bytes at a time.
+    struct Trickle<'a>(&'a [u8], usize);
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.1.min(buf.len()).min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    /// The value of a `key=value` line.
+    fn value(s: &Span) -> StreamingResult<Span, Span, NotFound, Incomplete> {
+        let Some((line, remaining)) = s.take_until(|c| c == '\n') else {
+            return Err(StreamingError::Incomplete(Incomplete));
+        };
+        let (_, value) = line
+            .take_until(|c| c == '=')
+            .ok_or(StreamingError::Error(NotFound))?;
+        Ok(StreamingOk::Complete(value.skip(1), remaining.skip(1)))
+    }
+
+    #[test]
+    fn positions_across_chunks() {
+        let reader = Trickle("a=1\nbb=22\nccc=ß\n".as_bytes(), 3);
+        let values = StreamDriver::new(reader, value)
+            .with_chunk_size(2)
+            .starting_at(TextPosition::new("stream"))
+            .map(|v| {
+                let v = v.unwrap();
+                (v.as_str().to_string(), v.location().1, v.id().to_string())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            vec![
+                ("1".to_string(), (1, 3), "stream".to_string()),
+                ("22".to_string(), (2, 4), "stream".to_string()),
+                ("ß".to_string(), (3, 5), "stream".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_at_end_of_stream() {
+        let mut driver = StreamDriver::new(Trickle(b"a=1\nb=", 4), value);
+        assert!(matches!(driver.next(), Some(Ok(_))));
+        assert!(matches!(
+            driver.next(),
+            Some(Err(DriverError::Incomplete(Incomplete)))
+        ));
+        assert!(driver.next().is_none());
+        assert_eq!(driver.buffered(), b"b=");
+        assert_eq!(
+            driver.position(),
+            &TextPosition {
+                id: Id::default(),
+                line: 1,
+                column: 0
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut driver = StreamDriver::new(Trickle(b"a=\xff\n", 4), value);
+        assert!(matches!(driver.next(), Some(Err(DriverError::Io(_)))));
+    }
+
+    #[test]
+    fn binary_frames() {
+        let frame = length_prefixed_stream(be_u16_stream, take::<_, NotFound>(3).to_failure(), 16);
+        let data = b"\x00\x03abc\x00\x03def\x00\x03gh";
+        let mut driver = StreamDriver::new(Trickle(data, 5), frame).with_chunk_size(4);
+
+        let frame: SharedBytes = driver.next().unwrap().unwrap();
+        assert_eq!((frame.as_bytes(), frame.offset()), (&b"abc"[..], 2));
+        let frame = driver.next().unwrap().unwrap();
+        assert_eq!((frame.as_bytes(), frame.offset()), (&b"def"[..], 7));
+        assert_eq!(driver.position(), &10);
+        assert!(matches!(
+            driver.next(),
+            Some(Err(DriverError::Incomplete(Incomplete)))
+        ));
+    }
+
+    #[test]
+    fn large_outputs() {
+        const SIZE: usize = 1 << 20;
+        let data = vec![b'x'; 2 * SIZE];
+        let attempts = std::cell::Cell::new(0);
+        let block =
+            |s: &SharedBytes| -> StreamingResult<SharedBytes, SharedBytes, NotFound, Incomplete> {
+                attempts.set(attempts.get() + 1);
+                match s.split_at_checked(SIZE) {
+                    Some((block, remaining)) => Ok(StreamingOk::Complete(block, remaining)),
+                    None => Err(StreamingError::Incomplete(Incomplete)),
+                }
+            };
+
+        let driver = StreamDriver::new(&data[..], block).with_chunk_size(16);
+        let blocks = driver.map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].offset(), SIZE);
+        // Reads double in size while a block is incomplete, so there are
+        // about log2(SIZE / 16) attempts per block rather than SIZE / 16.
+        assert!(attempts.get() < 50, "{} attempts", attempts.get());
+    }
+
+    #[test]
+    fn outputs_share_a_read() {
+        let frame = length_prefixed_stream(be_u16_stream, take::<_, NotFound>(1).to_failure(), 16);
+        let data = b"\x00\x01a\x00\x01b\x00\x01c";
+        let frames = StreamDriver::new(&data[..], frame)
+            .map(Result::unwrap)
+            .collect::<Vec<SharedBytes>>();
+        assert_eq!(frames.len(), 3);
+        // All three are views of the input built for the one read.
+        assert!(frames[0].same_source(&frames[2]));
+        assert_eq!(frames[2].offset(), 8);
+    }
+
+    #[test]
+    fn consumed_data_is_compacted() {
+        let data = "a=1\n".repeat(1000);
+        let mut driver = StreamDriver::new(Trickle(data.as_bytes(), 64), value).with_chunk_size(64);
+        for _ in 0..1000 {
+            assert_eq!(driver.next().unwrap().unwrap().as_str(), "1");
+        }
+        assert!(driver.next().is_none());
+        assert!(
+            driver.state.buffer.len() <= 128,
+            "{}",
+            driver.state.buffer.len()
+        );
+    }
+
+    #[test]
+    fn buffer_limit() {
+        let data = "a=".repeat(100);
+        let mut driver = StreamDriver::new(data.as_bytes(), value)
+            .with_chunk_size(4)
+            .with_max_buffer_size(32);
+        assert!(matches!(driver.next(), Some(Err(DriverError::BufferFull))));
+        assert_eq!(driver.buffered().len(), 32);
+        assert!(driver.next().is_none());
+    }
+}
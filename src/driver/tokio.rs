@@ -0,0 +1,212 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use ::tokio::io::{AsyncRead, ReadBuf};
+use futures_core::Stream;
+
+use super::{DriverError, DriverState, Step, StreamInput};
+use crate::parse::StreamingParser;
+
+/// As [`StreamDriver`][super::StreamDriver], reading from an [`AsyncRead`]
+/// and yielding outputs as a [`Stream`].
+pub struct AsyncStreamDriver<R, P, I: StreamInput, O, E, F> {
+    reader: R,
+    state: DriverState<P, I, O, E, F>,
+}
+impl<R, P, I: StreamInput, O, E, F> AsyncStreamDriver<R, P, I, O, E, F> {
+    pub fn new(reader: R, parser: P) -> Self {
+        Self {
+            reader,
+            state: DriverState::new(parser),
+        }
+    }
+    /// The number of bytes to request from the reader at a time. Larger reads
+    /// are made while an output is incomplete.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.state.set_chunk_size(chunk_size);
+        self
+    }
+    /// The most data to buffer while waiting for an output to complete, after
+    /// which it fails with [`DriverError::BufferFull`].
+    pub fn with_max_buffer_size(mut self, max_buffer_size: usize) -> Self {
+        self.state.set_max_buffer_size(max_buffer_size);
+        self
+    }
+    /// Set the position of the start of the stream, e.g. to give it an id.
+    pub fn starting_at(mut self, position: I::Position) -> Self {
+        self.state.position = position;
+        self
+    }
+    /// The position of the first unconsumed data.
+    pub fn position(&self) -> &I::Position {
+        &self.state.position
+    }
+    /// Data which has been read but not yet consumed by the parser.
+    pub fn buffered(&self) -> &[u8] {
+        self.state.buffered()
+    }
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+impl<R, P, I, O, E, F> Stream for AsyncStreamDriver<R, P, I, O, E, F>
+where
+    R: AsyncRead + Unpin,
+    P: StreamingParser<I, O, E, F> + Unpin,
+    I: StreamInput + Unpin,
+    I::Position: Unpin,
+{
+    type Item = Result<O, DriverError<E, F>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.state.step() {
+                Step::Output(output) => return Poll::Ready(Some(Ok(output))),
+                Step::Read => {
+                    let mut buffer = ReadBuf::new(this.state.reserve());
+                    let read = match Pin::new(&mut this.reader).poll_read(cx, &mut buffer) {
+                        Poll::Ready(read) => read.map(|_| buffer.filled().len()),
+                        // The chunk stays reserved, and the parser isn't run
+                        // again, until the read completes.
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if let Err(e) = this.state.commit(read) {
+                        return Poll::Ready(Some(Err(e.into())));
+                    }
+                }
+                Step::End => return Poll::Ready(None),
+                Step::Error(e) => return Poll::Ready(Some(Err(e))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{future::poll_fn, io};
+
+    use ::tokio::io::{duplex, AsyncWriteExt};
+
+    use super::*;
+    use crate::{
+        driver::TextPosition,
+        input::{span::Span, Input, Slice},
+        parse::{Incomplete, NotFound, StreamingError, StreamingOk, StreamingResult},
+    };
+
+    /// A `\n` terminated line, without its terminator.
+    fn line(s: &Span) -> StreamingResult<Span, Span, NotFound, Incomplete> {
+        match s.take_until(|c| c == '\n') {
+            Some((line, remaining)) => Ok(StreamingOk::Complete(line, remaining.skip(1))),
+            None => Err(StreamingError::Incomplete(Incomplete)),
+        }
+    }
+
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[::tokio::test]
+    async fn lines_from_pipe() {
+        let (mut client, server) = duplex(4);
+        let writer = ::tokio::spawn(async move {
+            for chunk in ["HELO a", "\nMAIL", " b\nQUIT\n"] {
+                client.write_all(chunk.as_bytes()).await.unwrap();
+            }
+        });
+
+        let mut driver = AsyncStreamDriver::new(server, line)
+            .with_chunk_size(3)
+            .starting_at(TextPosition::new("socket"));
+        let mut lines = Vec::new();
+        while let Some(line) = next(&mut driver).await {
+            let line = line.unwrap();
+            lines.push((line.as_str().to_string(), line.position()));
+        }
+        writer.await.unwrap();
+
+        assert_eq!(
+            lines,
+            vec![
+                ("HELO a".to_string(), (1, 1)),
+                ("MAIL b".to_string(), (2, 1)),
+                ("QUIT".to_string(), (3, 1)),
+            ]
+        );
+    }
+
+    #[::tokio::test]
+    async fn unterminated_line() {
+        let (mut client, server) = duplex(64);
+        client.write_all(b"HELO").await.unwrap();
+        drop(client);
+
+        let mut driver = AsyncStreamDriver::new(server, line);
+        assert!(matches!(
+            next(&mut driver).await,
+            Some(Err(DriverError::Incomplete(Incomplete)))
+        ));
+        assert!(next(&mut driver).await.is_none());
+        assert_eq!(driver.buffered(), b"HELO");
+    }
+
+    #[::tokio::test]
+    async fn buffer_limit() {
+        let (mut client, server) = duplex(64);
+        client.write_all(b"HELO HELO HELO").await.unwrap();
+
+        let mut driver = AsyncStreamDriver::new(server, line)
+            .with_chunk_size(4)
+            .with_max_buffer_size(8);
+        assert!(matches!(
+            next(&mut driver).await,
+            Some(Err(DriverError::BufferFull))
+        ));
+        assert_eq!(driver.buffered(), b"HELO HEL");
+    }
+
+    /// A reader which is pending on every other poll, and otherwise returns
+    /// a byte at a time.
+    struct Stutter(&'static [u8], bool);
+    impl AsyncRead for Stutter {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            this.1 = !this.1;
+            if this.1 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            if let Some((first, rest)) = this.0.split_first() {
+                buf.put_slice(&[*first]);
+                this.0 = rest;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[::tokio::test]
+    async fn pending_reads() {
+        let attempts = std::cell::Cell::new(0);
+        let counted = |s: &Span| {
+            attempts.set(attempts.get() + 1);
+            line(s)
+        };
+        let mut driver = AsyncStreamDriver::new(Stutter(b"abc\nd", false), counted);
+        let first = next(&mut driver).await.unwrap().unwrap();
+        assert_eq!(first.as_str(), "abc");
+        // One attempt per byte read, and none after a pending read.
+        assert_eq!(attempts.get(), 4);
+        assert!(matches!(
+            next(&mut driver).await,
+            Some(Err(DriverError::Incomplete(Incomplete)))
+        ));
+        assert_eq!(driver.buffered(), b"d");
+    }
+}
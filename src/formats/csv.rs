@@ -0,0 +1,516 @@
+/*!
+# CSV
+
+- [`parse`] reads [RFC 4180] CSV into [`Record`]s, and [`parse_table`] maps
+  each record's fields to the names in its header row.
+- [`Options`] set the delimiter, the quote character and which line endings
+  end a record. [`Options::tsv`] reads tab-separated values.
+- Quoted fields may contain delimiters & line breaks, and a doubled quote mark
+  stands for a single one. These are read with [`doubled_quoted_stream`]
+  rather than [`double_quoted`], whose backslash escapes CSV doesn't have.
+- Every line is a record, including a blank one, which has a single empty
+  field.
+- [`record_stream`] is a [`StreamingParser`] yielding one record at a time, for
+  use with a [`StreamDriver`][crate::driver::StreamDriver]. A quoted field cut
+  off by the end of a chunk is [`Incomplete`][StreamingError::Incomplete], and
+  a record which may continue in the next chunk is
+  [`Partial`][StreamingOk::Partial].
+
+```
+use parlance::{formats::csv::{self, Options}, input::span::Span};
+
+let input = Span::new("people.csv", "name,city\r\nAda,\"London, UK\"\r\n");
+let table = csv::parse_table(&input, &Options::default()).unwrap();
+assert_eq!(table.headers.get(&table.records[0], "city"), Some("London, UK"));
+```
+
+[RFC 4180]: https://www.rfc-editor.org/rfc/rfc4180
+[`double_quoted`]: crate::primitives::quote::double_quoted
+
+*/
+
+use std::fmt;
+
+use crate::{
+    diagnostics::Diagnostic,
+    input::{span::Span, Input, Slice},
+    parse::{
+        Expectation, Expected, NotFound, Parser, StreamingError, StreamingOk, StreamingParser,
+        StreamingResult,
+    },
+    primitives::quote::doubled_quoted_stream,
+};
+
+/// Which line endings end a record.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\r\n` or `\n`.
+    #[default]
+    Any,
+    /// Only `\r\n`, so a lone `\n` is part of a field.
+    Crlf,
+    /// Only `\n`, so a `\r` before it is part of the last field.
+    Lf,
+}
+
+/// The dialect of CSV to accept. The default is RFC 4180: fields separated by
+/// `,` and quoted with `"`, accepting either `\r\n` or `\n` line endings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    delimiter: char,
+    quote: Option<char>,
+    line_ending: LineEnding,
+}
+impl Options {
+    pub fn new() -> Self {
+        Self {
+            delimiter: ',',
+            quote: Some('"'),
+            line_ending: LineEnding::Any,
+        }
+    }
+    /// Tab-separated values, in which fields can't be quoted.
+    pub fn tsv() -> Self {
+        Self::new().with_delimiter('\t').with_quote(None)
+    }
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+    /// The character which quotes a field, or `None` to treat quotes as part
+    /// of the field.
+    pub fn with_quote(mut self, quote: Option<char>) -> Self {
+        self.quote = quote;
+        self
+    }
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// The length of the line ending at the start of `s`.
+    fn line_ending(&self, s: &str) -> Option<usize> {
+        match self.line_ending {
+            LineEnding::Any | LineEnding::Crlf if s.starts_with("\r\n") => Some(2),
+            LineEnding::Any | LineEnding::Lf if s.starts_with('\n') => Some(1),
+            _ => None,
+        }
+    }
+    /// Whether `s` is the start of a `\r\n` cut off by the end of the input.
+    fn is_cut_off_line_ending(&self, s: &str) -> bool {
+        s == "\r" && self.line_ending != LineEnding::Lf
+    }
+    /// Whether the field starting at `s` ends before it.
+    fn ends_field(&self, s: &str) -> bool {
+        s.starts_with(self.delimiter)
+            || self.line_ending(s).is_some()
+            || self.is_cut_off_line_ending(s)
+    }
+}
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A line of fields, with any quotes removed.
+#[derive(Clone, Debug)]
+pub struct Record {
+    span: Span,
+    fields: Vec<String>,
+}
+impl Record {
+    /// The text of the record, without its line ending.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.fields.get(index).map(String::as_str)
+    }
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+    pub fn into_fields(self) -> Vec<String> {
+        self.fields
+    }
+}
+
+/// The names of the columns, from a header row.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Headers {
+    names: Vec<String>,
+}
+impl Headers {
+    pub fn new(names: Vec<String>) -> Self {
+        Self { names }
+    }
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+    /// The index of the first column called `name`.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+    /// The field of `record` in the column called `name`.
+    pub fn get<'r>(&self, record: &'r Record, name: &str) -> Option<&'r str> {
+        record.get(self.index(name)?)
+    }
+    /// Each field of `record` with the name of its column.
+    pub fn pairs<'a>(&'a self, record: &'a Record) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .zip(record.fields.iter().map(String::as_str))
+    }
+}
+impl From<Record> for Headers {
+    fn from(value: Record) -> Self {
+        Self::new(value.fields)
+    }
+}
+
+/// The output of [`parse_table`].
+#[derive(Clone, Debug)]
+pub struct Table {
+    pub headers: Headers,
+    /// The records after the header row.
+    pub records: Vec<Record>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Error {
+    /// A quoted field which is never closed. The span is the opening quote.
+    UnterminatedQuote(Span),
+    /// A quote mark within an unquoted field.
+    StrayQuote(Span),
+    /// Something other than a delimiter or line ending after a closing quote.
+    AfterQuote(Span),
+    /// A record with a different number of fields to the header row.
+    FieldCount {
+        record: Span,
+        expected: usize,
+        found: usize,
+    },
+}
+impl Error {
+    pub fn span(&self) -> &Span {
+        match self {
+            Error::UnterminatedQuote(span) | Error::StrayQuote(span) | Error::AfterQuote(span) => {
+                span
+            }
+            Error::FieldCount { record, .. } => record,
+        }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnterminatedQuote(_) => f.write_str("unterminated quoted field"),
+            Error::StrayQuote(_) => f.write_str("quote mark in an unquoted field"),
+            Error::AfterQuote(span) => {
+                write!(f, "unexpected {:?} after a quoted field", span.as_str())
+            }
+            Error::FieldCount {
+                expected, found, ..
+            } => write!(f, "expected {expected} fields, found {found}"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<Error> for Diagnostic {
+    fn from(value: Error) -> Self {
+        Diagnostic::error(value.to_string(), value.span().clone())
+    }
+}
+
+pub fn parse(input: &Span, options: &Options) -> Result<Vec<Record>, Error> {
+    let mut records = Vec::new();
+    let mut remaining = input.clone();
+    while !remaining.is_empty() {
+        let (record, rest) = match scan_record(options, &remaining) {
+            Ok(StreamingOk::Complete(record, rest) | StreamingOk::Partial(record, rest)) => {
+                (record, rest)
+            }
+            Err(StreamingError::Incomplete(e) | StreamingError::Failure(e)) => return Err(e),
+            Err(StreamingError::Error(_)) => unreachable!("Non-empty input is always a record"),
+        };
+        records.push(record);
+        remaining = rest;
+    }
+    Ok(records)
+}
+
+/// Parse a header row followed by records, each of which must have as many
+/// fields as there are headers. Empty input has no headers & no records.
+pub fn parse_table(input: &Span, options: &Options) -> Result<Table, Error> {
+    let mut records = parse(input, options)?.into_iter();
+    let headers = records.next().map(Headers::from).unwrap_or_default();
+    let records = records.collect::<Vec<_>>();
+    if let Some(record) = records.iter().find(|r| r.len() != headers.names.len()) {
+        return Err(Error::FieldCount {
+            record: record.span.clone(),
+            expected: headers.names.len(),
+            found: record.len(),
+        });
+    }
+    Ok(Table { headers, records })
+}
+
+/// A single record, including its line ending.
+pub fn record(options: Options) -> impl Parser<Span, Record, Expected<Span>, Error> {
+    record_stream(options).complete()
+}
+
+/// A single record, including its line ending. The result is only
+/// [`Complete`][StreamingOk::Complete] once the line ending has been read,
+/// since until then the last field may continue.
+pub fn record_stream(
+    options: Options,
+) -> impl StreamingParser<Span, Record, Expected<Span>, Error> {
+    move |input: &Span| scan_record(&options, input)
+}
+
+fn scan_record(
+    options: &Options,
+    input: &Span,
+) -> StreamingResult<Span, Record, Expected<Span>, Error> {
+    if input.is_empty() {
+        return Err(StreamingError::Error(Expected::new(
+            input.clone(),
+            Expectation::label("record"),
+        )));
+    }
+
+    let mut fields = Vec::new();
+    let mut remaining = input.clone();
+    loop {
+        let (field, rest) = match options.quote {
+            Some(quote) if remaining.as_str().starts_with(quote) => {
+                quoted_field(quote, &remaining)?
+            }
+            _ => unquoted_field(options, &remaining)?,
+        };
+        fields.push(field);
+
+        let s = rest.as_str();
+        if s.starts_with(options.delimiter) {
+            remaining = rest.skip(options.delimiter.len_utf8());
+            continue;
+        }
+        let record = Record {
+            span: input.take(input.len() - rest.len()),
+            fields,
+        };
+        return if s.is_empty() || options.is_cut_off_line_ending(s) {
+            Ok(StreamingOk::Partial(record, rest.skip(s.len())))
+        } else if let Some(len) = options.line_ending(s) {
+            Ok(StreamingOk::Complete(record, rest.skip(len)))
+        } else {
+            let next = s.chars().next().map_or(0, char::len_utf8);
+            Err(StreamingError::Failure(Error::AfterQuote(rest.take(next))))
+        };
+    }
+}
+
+fn quoted_field(
+    quote: char,
+    input: &Span,
+) -> Result<(String, Span), StreamingError<Expected<Span>, Error>> {
+    match doubled_quoted_stream::<_, NotFound>(quote).parse_stream(input) {
+        Ok(StreamingOk::Complete(field, rest) | StreamingOk::Partial(field, rest)) => {
+            let doubled = format!("{quote}{quote}");
+            Ok((
+                field
+                    .as_str()
+                    .replace(&doubled, quote.encode_utf8(&mut [0; 4])),
+                rest,
+            ))
+        }
+        Err(_) => Err(StreamingError::Incomplete(Error::UnterminatedQuote(
+            input.take(quote.len_utf8()),
+        ))),
+    }
+}
+
+fn unquoted_field(
+    options: &Options,
+    input: &Span,
+) -> Result<(String, Span), StreamingError<Expected<Span>, Error>> {
+    let s = input.as_str();
+    let end = s
+        .char_indices()
+        .find(|(idx, c)| Some(*c) == options.quote || options.ends_field(&s[*idx..]))
+        .map_or(s.len(), |(idx, _)| idx);
+    if let Some(quote) = options.quote.filter(|q| s[end..].starts_with(*q)) {
+        return Err(StreamingError::Failure(Error::StrayQuote(
+            input.slice(end..end + quote.len_utf8()),
+        )));
+    }
+    let (field, rest) = input.split_at(end);
+    Ok((field.as_str().to_string(), rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::{DriverError, StreamDriver, TextPosition};
+
+    fn parse_str(s: &str, options: &Options) -> Result<Vec<Vec<String>>, Error> {
+        let records = parse(&Span::anonymous(s), options)?;
+        Ok(records.into_iter().map(Record::into_fields).collect())
+    }
+
+    #[test]
+    fn rfc() {
+        let records = parse_str(
+            "a,b,c\r\n\"x, y\",\"say \"\"hi\"\"\",\"two\r\nlines\"\r\n,,\r\n\r\nlast,line",
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                vec!["a", "b", "c"],
+                vec!["x, y", "say \"hi\"", "two\r\nlines"],
+                vec!["", "", ""],
+                vec![""],
+                vec!["last", "line"],
+            ]
+        );
+
+        let records = parse(&Span::anonymous("a,\"b\"\nc,d\n"), &Options::default()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].span().as_str(), "a,\"b\"");
+        assert_eq!(records[1].span().position(), (2, 1));
+        assert_eq!(records[1].get(1), Some("d"));
+    }
+
+    #[test]
+    fn dialects() {
+        assert_eq!(
+            parse_str("a\t\"b\"\tc d\n1\t2\t3\n", &Options::tsv()).unwrap(),
+            vec![vec!["a", "\"b\"", "c d"], vec!["1", "2", "3"]]
+        );
+        assert_eq!(
+            parse_str(
+                "'a;b';'it''s'\n",
+                &Options::new().with_delimiter(';').with_quote(Some('\''))
+            )
+            .unwrap(),
+            vec![vec!["a;b", "it's"]]
+        );
+        assert_eq!(
+            parse_str(
+                "a\r\nb\nc",
+                &Options::new().with_line_ending(LineEnding::Lf)
+            )
+            .unwrap(),
+            vec![vec!["a\r"], vec!["b"], vec!["c"]]
+        );
+        assert_eq!(
+            parse_str(
+                "a\nb\r\nc",
+                &Options::new().with_line_ending(LineEnding::Crlf)
+            )
+            .unwrap(),
+            vec![vec!["a\nb"], vec!["c"]]
+        );
+    }
+
+    #[test]
+    fn table() {
+        let input = Span::anonymous("id,name\n1,Ada\n2,\"Grace\"\n");
+        let table = parse_table(&input, &Options::default()).unwrap();
+        assert_eq!(table.headers.names(), ["id", "name"]);
+        assert_eq!(table.headers.index("name"), Some(1));
+        assert_eq!(table.headers.get(&table.records[1], "name"), Some("Grace"));
+        assert_eq!(table.headers.get(&table.records[1], "age"), None);
+        assert_eq!(
+            table.headers.pairs(&table.records[0]).collect::<Vec<_>>(),
+            vec![("id", "1"), ("name", "Ada")]
+        );
+
+        let table = parse_table(&Span::anonymous(""), &Options::default()).unwrap();
+        assert!(table.headers.names().is_empty() && table.records.is_empty());
+
+        let e = parse_table(&Span::anonymous("a,b\n1,2\n3\n"), &Options::default()).unwrap_err();
+        assert_eq!(e.to_string(), "expected 2 fields, found 1");
+        assert_eq!(e.span().position(), (3, 1));
+    }
+
+    #[test]
+    fn errors() {
+        let e = parse(&Span::anonymous("a,b\nc,\"d\n"), &Options::default()).unwrap_err();
+        assert_eq!(e.to_string(), "unterminated quoted field");
+        assert_eq!(e.span().position(), (2, 3));
+
+        let e = parse(&Span::anonymous("a,b\"c\n"), &Options::default()).unwrap_err();
+        assert_eq!(e.to_string(), "quote mark in an unquoted field");
+        assert_eq!(e.span().position(), (1, 4));
+
+        let e = parse(&Span::anonymous("\"a\"b,c\n"), &Options::default()).unwrap_err();
+        assert_eq!(e.to_string(), "unexpected \"b\" after a quoted field");
+        assert_eq!(e.span().position(), (1, 4));
+
+        let diagnostic = Diagnostic::from(e);
+        assert_eq!(
+            diagnostic.message(),
+            "unexpected \"b\" after a quoted field"
+        );
+    }
+
+    #[test]
+    fn streaming() {
+        let parser = record_stream(Options::default());
+        let partial = |s: &str| match parser.parse_stream(&Span::anonymous(s)) {
+            Ok(StreamingOk::Partial(record, _)) => record.into_fields(),
+            other => panic!("Expected a partial record, got {other:?}"),
+        };
+        assert_eq!(partial("a,b"), vec!["a", "b"]);
+        assert_eq!(partial("a,\"b\""), vec!["a", "b"]);
+        assert_eq!(partial("a,b\r"), vec!["a", "b"]);
+        assert!(matches!(
+            parser.parse_stream(&Span::anonymous("a,\"b\nc")),
+            Err(StreamingError::Incomplete(Error::UnterminatedQuote(_)))
+        ));
+        assert!(matches!(
+            parser.parse_stream(&Span::anonymous("a,b\r\nc")),
+            Ok(StreamingOk::Complete(_, _))
+        ));
+
+        let data = "id,note\r\n1,\"spans\r\nchunks, \"\"quoted\"\"\"\r\n2,plain\r\n3,end";
+        let records = StreamDriver::new(data.as_bytes(), record_stream(Options::default()))
+            .with_chunk_size(4)
+            .starting_at(TextPosition::new("stream"))
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records
+                .iter()
+                .map(|r| r.fields().to_vec())
+                .collect::<Vec<_>>(),
+            vec![
+                vec!["id", "note"],
+                vec!["1", "spans\r\nchunks, \"quoted\""],
+                vec!["2", "plain"],
+                vec!["3", "end"],
+            ]
+        );
+        assert_eq!(records[2].span().location().1, (4, 1));
+
+        let mut driver =
+            StreamDriver::new("a\n\"b\nc".as_bytes(), record_stream(Options::default()))
+                .with_chunk_size(2);
+        assert!(matches!(driver.next(), Some(Ok(_))));
+        assert!(matches!(
+            driver.next(),
+            Some(Err(DriverError::Incomplete(Error::UnterminatedQuote(_))))
+        ));
+    }
+}
@@ -0,0 +1,706 @@
+/*!
+# HTTP/1.1
+
+- [`request`] & [`response`] are [`StreamingParser`]s for a message head: the
+  request or status line, then header fields up to the blank line which ends
+  the head. Until that line arrives the result is
+  [`Incomplete`][StreamingError::Incomplete].
+- [`chunk`] reads one chunk of a `chunked` body, or the last chunk with its
+  trailer fields, as in [RFC 9112 §7.1].
+- Any [`ByteInput`] can be read, such as `&[u8]` or the
+  [`SharedBytes`][crate::input::bytes::SharedBytes] of a
+  [`StreamDriver`][crate::driver::StreamDriver]. Methods, targets, names &
+  values are slices of the input.
+- [`Options`] limit the length of each line, the number of header fields and
+  the size of each chunk. Input over a limit fails as soon as it arrives,
+  without waiting for the rest of the line, head or chunk.
+- Header values folded over several lines (obs-fold) are accepted, and
+  [`Header::unfolded`] joins them with spaces.
+- Malformed input fails with an [`Error`] giving the byte offset of the
+  problem from the start of the input.
+- Only the framing is parsed. Targets & header values aren't interpreted,
+  except by [`Headers::content_length`] and [`Headers::is_chunked`].
+
+```
+use parlance::{
+    formats::http1::{self, Options, Version},
+    parse::{StreamingOk, StreamingParser},
+};
+
+let input: &[u8] = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+let Ok(StreamingOk::Complete(request, _)) = http1::request(Options::default()).parse_stream(&input) else {
+    panic!("Expected a request")
+};
+assert_eq!(request.method, b"GET");
+assert_eq!(request.version, Version { major: 1, minor: 1 });
+assert_eq!(request.headers.get("host").unwrap().value, b"example.com");
+```
+
+[RFC 9112 §7.1]: https://www.rfc-editor.org/rfc/rfc9112#section-7.1
+
+*/
+
+use std::{borrow::Cow, fmt};
+
+use crate::{
+    input::{bytes::ByteInput, Slice},
+    parse::{Never, StreamingError, StreamingOk, StreamingParser, StreamingResult},
+};
+
+type HttpResult<I, O> = StreamingResult<I, O, Never, Error>;
+/// The result of a step within a head, which fails or needs more input.
+type Step<T> = Result<T, StreamingError<Never, Error>>;
+
+/// Limits on the input accepted. The defaults are lines of 8 KiB, 100 header
+/// fields and chunks of 16 MiB.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    max_line_length: usize,
+    max_headers: usize,
+    max_chunk_size: usize,
+}
+impl Options {
+    pub fn new() -> Self {
+        Self {
+            max_line_length: 8 * 1024,
+            max_headers: 100,
+            max_chunk_size: 16 * 1024 * 1024,
+        }
+    }
+    /// The longest line accepted, in bytes, not counting its CRLF. A folded
+    /// header value is limited on each line.
+    pub fn with_max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+    /// The most header fields accepted in a head, or trailer fields after the
+    /// last chunk.
+    pub fn with_max_headers(mut self, max_headers: usize) -> Self {
+        self.max_headers = max_headers;
+        self
+    }
+    /// The largest chunk accepted, in bytes. As a chunk is only returned once
+    /// all of its data has arrived, this bounds how much a
+    /// [`StreamDriver`][crate::driver::StreamDriver] buffers for it.
+    pub fn with_max_chunk_size(mut self, max_chunk_size: usize) -> Self {
+        self.max_chunk_size = max_chunk_size;
+        self
+    }
+}
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP/{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header<I> {
+    pub name: I,
+    /// The value without the whitespace around it. A folded value includes
+    /// the line breaks of each fold.
+    pub value: I,
+}
+impl<I: ByteInput> Header<I> {
+    /// Whether this is called `name`, ignoring ASCII case.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.as_bytes().eq_ignore_ascii_case(name.as_bytes())
+    }
+    /// The value with each fold, and the whitespace around it, replaced by a
+    /// single space.
+    pub fn unfolded(&self) -> Cow<'_, [u8]> {
+        let value = self.value.as_bytes();
+        if !value.contains(&b'\n') {
+            return Cow::Borrowed(value);
+        }
+        let mut unfolded = Vec::with_capacity(value.len());
+        for (i, line) in value.split(|b| *b == b'\n').enumerate() {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if i > 0 {
+                unfolded.truncate(unfolded.trim_ascii_end().len());
+                unfolded.push(b' ');
+            }
+            unfolded.extend_from_slice(line.trim_ascii_start());
+        }
+        Cow::Owned(unfolded)
+    }
+}
+
+/// Header fields, in the order received.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Headers<I> {
+    pub fields: Vec<Header<I>>,
+}
+impl<I: ByteInput> Headers<I> {
+    /// The first field called `name`, ignoring ASCII case.
+    pub fn get(&self, name: &str) -> Option<&Header<I>> {
+        self.fields.iter().find(|h| h.is(name))
+    }
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Header<I>> {
+        self.fields.iter().filter(move |h| h.is(name))
+    }
+    /// The `Content-Length`, unless it is missing, invalid or given more than
+    /// once with different values.
+    pub fn content_length(&self) -> Option<u64> {
+        let mut length = None;
+        for header in self.get_all("content-length") {
+            for n in header.value.as_bytes().split(|b| *b == b',') {
+                let n = n.trim_ascii();
+                if n.is_empty() || !n.iter().all(u8::is_ascii_digit) {
+                    return None;
+                }
+                let n = std::str::from_utf8(n).ok()?.parse().ok()?;
+                if length.is_some_and(|length| length != n) {
+                    return None;
+                }
+                length = Some(n);
+            }
+        }
+        length
+    }
+    /// Whether the last `Transfer-Encoding` is `chunked`, so the body is read
+    /// with [`chunk`].
+    pub fn is_chunked(&self) -> bool {
+        self.get_all("transfer-encoding")
+            .flat_map(|h| h.value.as_bytes().split(|b| *b == b','))
+            .map(<[u8]>::trim_ascii)
+            .filter(|coding| !coding.is_empty())
+            .last()
+            .is_some_and(|coding| coding.eq_ignore_ascii_case(b"chunked"))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request<I> {
+    pub method: I,
+    pub target: I,
+    pub version: Version,
+    pub headers: Headers<I>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response<I> {
+    pub version: Version,
+    pub status: u16,
+    /// The reason phrase, which may be empty.
+    pub reason: I,
+    pub headers: Headers<I>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Chunk<I> {
+    Data(I),
+    /// The last chunk, with any trailer fields.
+    Last(Headers<I>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Something else was expected, such as a `method` or `CRLF`.
+    Expected(&'static str),
+    LineTooLong,
+    TooManyHeaders,
+    /// A chunk size over the limit.
+    ChunkTooLarge,
+    /// The input ended within a head or chunk.
+    Incomplete,
+}
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Expected(expected) => write!(f, "expected {expected}"),
+            ErrorKind::LineTooLong => f.write_str("line too long"),
+            ErrorKind::TooManyHeaders => f.write_str("too many header fields"),
+            ErrorKind::ChunkTooLarge => f.write_str("chunk too large"),
+            ErrorKind::Incomplete => f.write_str("incomplete message"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// The byte offset of the problem from the start of the input. For
+    /// [`ErrorKind::Incomplete`], this is the length of the input. From a
+    /// [`StreamDriver`][crate::driver::StreamDriver], add its `position()`
+    /// for the offset in the stream.
+    pub offset: usize,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)
+    }
+}
+impl std::error::Error for Error {}
+
+/// A request head, up to & including the blank line which ends it. Blank
+/// lines before the request line are ignored, but count towards its length.
+pub fn request<I: ByteInput>(
+    options: Options,
+) -> impl StreamingParser<I, Request<I>, Never, Error> {
+    move |input: &I| Reader::new(input, &options).request()
+}
+
+/// A response head, up to & including the blank line which ends it.
+pub fn response<I: ByteInput>(
+    options: Options,
+) -> impl StreamingParser<I, Response<I>, Never, Error> {
+    move |input: &I| Reader::new(input, &options).response()
+}
+
+/// A chunk of a `chunked` body, including the CRLF after its data. Chunk
+/// extensions are skipped. The whole of a chunk's data must be in the input
+/// before it is returned.
+pub fn chunk<I: ByteInput>(options: Options) -> impl StreamingParser<I, Chunk<I>, Never, Error> {
+    move |input: &I| Reader::new(input, &options).chunk()
+}
+
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+fn is_blank(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+/// Visible characters, spaces, tabs & non-ASCII bytes.
+fn is_text(b: u8) -> bool {
+    is_blank(b) || (b > b' ' && b != 0x7f)
+}
+
+/// A line of input, without its CRLF.
+struct Line<I> {
+    text: I,
+    /// The offset of the line from the start of the input.
+    start: usize,
+    /// The input after the CRLF.
+    rest: I,
+}
+impl<I: Slice> Line<I> {
+    /// The offset of `at`, a suffix of the line, from the start of the input.
+    fn offset(&self, at: &I) -> usize {
+        self.start + self.text.len() - at.len()
+    }
+}
+
+struct Reader<'o, I> {
+    input: &'o I,
+    options: &'o Options,
+}
+impl<'o, I: ByteInput> Reader<'o, I> {
+    fn new(input: &'o I, options: &'o Options) -> Self {
+        Self { input, options }
+    }
+
+    fn fail<T>(&self, kind: ErrorKind, offset: usize) -> Step<T> {
+        Err(StreamingError::Failure(Error { kind, offset }))
+    }
+    fn incomplete<T>(&self) -> Step<T> {
+        Err(StreamingError::Incomplete(Error {
+            kind: ErrorKind::Incomplete,
+            offset: self.input.len(),
+        }))
+    }
+
+    /// The line starting at `at`, a suffix of the input.
+    fn line(&self, at: &I) -> Step<Line<I>> {
+        self.line_within(at, self.options.max_line_length)
+    }
+
+    /// As [`line`][Self::line], with a line no longer than `max` bytes.
+    fn line_within(&self, at: &I, max: usize) -> Step<Line<I>> {
+        let start = self.input.len() - at.len();
+        match at.find(b"\r\n") {
+            Some(len) if len <= max => Ok(Line {
+                text: at.take(len),
+                start,
+                rest: at.skip(len + 2),
+            }),
+            // The line may yet end with a CR before its LF.
+            None if at.len() <= max.saturating_add(1) => self.incomplete(),
+            _ => self.fail(ErrorKind::LineTooLong, start),
+        }
+    }
+
+    fn space(&self, line: &Line<I>, at: &I) -> Step<I> {
+        match at.pop(b" ") {
+            Some((_, rest)) => Ok(rest),
+            None => self.fail(ErrorKind::Expected("space"), line.offset(at)),
+        }
+    }
+
+    fn version(&self, line: &Line<I>, at: &I) -> Step<(Version, I)> {
+        match at.as_bytes() {
+            [b'H', b'T', b'T', b'P', b'/', major @ b'0'..=b'9', b'.', minor @ b'0'..=b'9', ..] => {
+                let version = Version {
+                    major: major - b'0',
+                    minor: minor - b'0',
+                };
+                Ok((version, at.skip(8)))
+            }
+            _ => self.fail(ErrorKind::Expected("HTTP version"), line.offset(at)),
+        }
+    }
+
+    fn end(&self, line: &Line<I>, at: &I) -> Step<()> {
+        if at.is_empty() {
+            Ok(())
+        } else {
+            self.fail(ErrorKind::Expected("CRLF"), line.offset(at))
+        }
+    }
+
+    fn request(&self) -> HttpResult<I, Request<I>> {
+        let mut at = self.input.clone();
+        while let Some((_, rest)) = at.pop(b"\r\n") {
+            at = rest;
+        }
+        let Some(max) = self
+            .options
+            .max_line_length
+            .checked_sub(self.input.len() - at.len())
+        else {
+            return self.fail(ErrorKind::LineTooLong, 0);
+        };
+        let line = self.line_within(&at, max)?;
+
+        let Some((method, at)) = line.text.take_while(is_tchar) else {
+            return self.fail(ErrorKind::Expected("method"), line.start);
+        };
+        let at = self.space(&line, &at)?;
+        let Some((target, at)) = at.take_while(|b| b.is_ascii_graphic()) else {
+            return self.fail(ErrorKind::Expected("request target"), line.offset(&at));
+        };
+        let at = self.space(&line, &at)?;
+        let (version, at) = self.version(&line, &at)?;
+        self.end(&line, &at)?;
+
+        let (headers, rest) = self.fields(&line.rest)?;
+        let request = Request {
+            method,
+            target,
+            version,
+            headers,
+        };
+        Ok(StreamingOk::Complete(request, rest))
+    }
+
+    fn response(&self) -> HttpResult<I, Response<I>> {
+        let line = self.line(self.input)?;
+
+        let (version, at) = self.version(&line, &line.text)?;
+        let at = self.space(&line, &at)?;
+        let status = match at.as_bytes() {
+            [a, b, c, ..] if [a, b, c].iter().all(|d| d.is_ascii_digit()) => [a, b, c]
+                .iter()
+                .fold(0, |n, d| n * 10 + u16::from(**d - b'0')),
+            _ => return self.fail(ErrorKind::Expected("status code"), line.offset(&at)),
+        };
+        let at = at.skip(3);
+        // The space before an empty reason is often left out.
+        let reason = if at.is_empty() {
+            at
+        } else {
+            self.space(&line, &at)?
+        };
+        if let Some(idx) = reason.as_bytes().iter().position(|b| !is_text(*b)) {
+            return self.fail(
+                ErrorKind::Expected("reason phrase"),
+                line.offset(&reason) + idx,
+            );
+        }
+
+        let (headers, rest) = self.fields(&line.rest)?;
+        let response = Response {
+            version,
+            status,
+            reason,
+            headers,
+        };
+        Ok(StreamingOk::Complete(response, rest))
+    }
+
+    /// Header fields, and the blank line after them.
+    fn fields(&self, at: &I) -> Step<(Headers<I>, I)> {
+        let mut fields: Vec<Header<I>> = Vec::new();
+        // The offset of the last field's value, which a fold extends.
+        let mut value_start = 0;
+        let mut at = at.clone();
+        loop {
+            let line = self.line(&at)?;
+            if line.text.is_empty() {
+                return Ok((Headers { fields }, line.rest));
+            }
+
+            if line.text.as_bytes().first().copied().is_some_and(is_blank) {
+                let Some(field) = fields.last_mut() else {
+                    return self.fail(ErrorKind::Expected("header name"), line.start);
+                };
+                let value = self.value(&line, &line.text)?;
+                if !value.is_empty() {
+                    if field.value.is_empty() {
+                        value_start = line.offset(&value);
+                    }
+                    let end = line.offset(&value) + value.len();
+                    field.value = self.input.slice(value_start..end);
+                }
+            } else {
+                if fields.len() == self.options.max_headers {
+                    return self.fail(ErrorKind::TooManyHeaders, line.start);
+                }
+                let Some((name, rest)) = line.text.take_while(is_tchar) else {
+                    return self.fail(ErrorKind::Expected("header name"), line.start);
+                };
+                let Some((_, rest)) = rest.pop(b":") else {
+                    return self.fail(ErrorKind::Expected("`:`"), line.offset(&rest));
+                };
+                let value = self.value(&line, &rest)?;
+                value_start = line.offset(&value);
+                fields.push(Header { name, value });
+            }
+            at = line.rest;
+        }
+    }
+
+    /// The rest of a line, without the whitespace around it.
+    fn value(&self, line: &Line<I>, at: &I) -> Step<I> {
+        if let Some(idx) = at.as_bytes().iter().position(|b| !is_text(*b)) {
+            return self.fail(ErrorKind::Expected("header value"), line.offset(at) + idx);
+        }
+        let bytes = at.as_bytes();
+        let start = bytes.len() - bytes.trim_ascii_start().len();
+        let end = bytes.trim_ascii_end().len().max(start);
+        Ok(at.slice(start..end))
+    }
+
+    fn chunk(&self) -> HttpResult<I, Chunk<I>> {
+        let line = self.line(self.input)?;
+
+        let Some((digits, at)) = line.text.take_while(|b| b.is_ascii_hexdigit()) else {
+            return self.fail(ErrorKind::Expected("chunk size"), line.start);
+        };
+        let size = digits.as_bytes().iter().try_fold(0usize, |n, d| {
+            let d = char::from(*d).to_digit(16).expect("Digits were checked");
+            n.checked_mul(16)?.checked_add(d as usize)
+        });
+        let Some(size) = size.filter(|size| *size <= self.options.max_chunk_size) else {
+            return self.fail(ErrorKind::ChunkTooLarge, line.start);
+        };
+        let extensions = at.take_while(is_blank).map_or(at, |(_, rest)| rest);
+        if !extensions.is_empty() && !extensions.as_bytes().starts_with(b";") {
+            return self.fail(ErrorKind::Expected("`;` or CRLF"), line.offset(&extensions));
+        }
+
+        if size == 0 {
+            let (trailers, rest) = self.fields(&line.rest)?;
+            return Ok(StreamingOk::Complete(Chunk::Last(trailers), rest));
+        }
+        let Some((data, after)) = line.rest.split_at_checked(size) else {
+            return self.incomplete();
+        };
+        match after.as_bytes() {
+            [b'\r', b'\n', ..] => Ok(StreamingOk::Complete(Chunk::Data(data), after.skip(2))),
+            [] | [b'\r'] => self.incomplete(),
+            _ => self.fail(ErrorKind::Expected("CRLF"), self.input.len() - after.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        driver::{DriverError, StreamDriver},
+        input::bytes::SharedBytes,
+    };
+
+    type Bytes<'a> = &'a [u8];
+
+    fn parse_request(input: Bytes) -> HttpResult<Bytes, Request<Bytes>> {
+        request(Options::default()).parse_stream(&input)
+    }
+
+    fn failure<O: fmt::Debug>(result: HttpResult<Bytes, O>) -> Error {
+        match result {
+            Err(StreamingError::Failure(e)) => e,
+            other => panic!("Expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn requests() {
+        let input: Bytes = b"\r\nPOST /form?q=1 HTTP/1.1\r\nHost: example.com\r\n\
+            content-length: 5\r\nContent-Length:5  \r\nX-Empty:\r\n\r\nhello";
+        let Ok(StreamingOk::Complete(request, rest)) = parse_request(input) else {
+            panic!("Expected a request")
+        };
+        assert_eq!(request.method, b"POST");
+        assert_eq!(request.target, b"/form?q=1");
+        assert_eq!(request.version.to_string(), "HTTP/1.1");
+        assert_eq!(request.headers.fields.len(), 4);
+        assert_eq!(request.headers.get("HOST").unwrap().value, b"example.com");
+        assert_eq!(request.headers.get("x-empty").unwrap().value, b"");
+        assert_eq!(request.headers.content_length(), Some(5));
+        assert!(!request.headers.is_chunked());
+        assert_eq!(rest, b"hello");
+
+        let head = input.len() - rest.len();
+        for len in 0..head {
+            assert_eq!(
+                parse_request(&input[..len]),
+                Err(StreamingError::Incomplete(Error {
+                    kind: ErrorKind::Incomplete,
+                    offset: len
+                })),
+                "{len}"
+            );
+        }
+    }
+
+    #[test]
+    fn responses() {
+        let input: Bytes = b"HTTP/1.0 404 Not \xe9 Found\r\nX-Folded: one\r\n  two \r\n\tthree\r\n\
+            X-Late:\r\n late\r\nTransfer-Encoding: gzip, chunked\r\n\r\n";
+        let Ok(StreamingOk::Complete(head, rest)) =
+            response(Options::default()).parse_stream(&input)
+        else {
+            panic!("Expected a response")
+        };
+        assert!(rest.is_empty());
+        assert_eq!(head.version, Version { major: 1, minor: 0 });
+        assert_eq!(head.status, 404);
+        assert_eq!(head.reason, b"Not \xe9 Found");
+        let folded = head.headers.get("x-folded").unwrap();
+        assert_eq!(folded.value, b"one\r\n  two \r\n\tthree");
+        assert_eq!(folded.unfolded(), &b"one two three"[..]);
+        assert_eq!(head.headers.get("x-late").unwrap().unfolded(), &b"late"[..]);
+        assert!(head.headers.is_chunked());
+        assert_eq!(head.headers.content_length(), None);
+
+        let input: Bytes = b"HTTP/1.1 204\r\n\r\n";
+        let Ok(StreamingOk::Complete(head, _)) = response(Options::default()).parse_stream(&input)
+        else {
+            panic!("Expected a response")
+        };
+        assert_eq!((head.status, head.reason), (204, &b""[..]));
+    }
+
+    #[test]
+    fn errors() {
+        let cases: &[(&[u8], &str, usize)] = &[
+            (b"GET /a HTTP/1.x\r\n\r\n", "HTTP version", 7),
+            (b"GET  /a HTTP/1.1\r\n\r\n", "request target", 4),
+            (b"GET\t/a HTTP/1.1\r\n\r\n", "space", 3),
+            (b"GET /a HTTP/1.1\nHost: x\r\n\r\n", "CRLF", 15),
+            (b"GET /a HTTP/1.1\r\nBad Name: x\r\n\r\n", "`:`", 20),
+            (b"GET /a HTTP/1.1\r\n Host: x\r\n\r\n", "header name", 17),
+            (b"GET /a HTTP/1.1\r\nX: a\x01b\r\n\r\n", "header value", 21),
+        ];
+        for (input, expected, offset) in cases {
+            let e = failure(parse_request(input));
+            assert_eq!(e.kind, ErrorKind::Expected(expected), "{input:?}");
+            assert_eq!(e.offset, *offset, "{input:?}");
+        }
+
+        let input: Bytes = b"HTTP/1.1 2000 OK\r\n\r\n";
+        let e = failure(response(Options::default()).parse_stream(&input));
+        assert_eq!(e.to_string(), "expected space at byte 12");
+    }
+
+    #[test]
+    fn limits() {
+        let options = Options::new().with_max_line_length(16).with_max_headers(2);
+
+        // The line is too long before its end arrives.
+        let input: Bytes = b"GET /a-long-path-name";
+        let e = failure(request(options.clone()).parse_stream(&input));
+        assert_eq!((e.kind, e.offset), (ErrorKind::LineTooLong, 0));
+        let input: Bytes = b"GET / HTTP/1.1\r\nX: 12345678901234\r\n";
+        let e = failure(request(options.clone()).parse_stream(&input));
+        assert_eq!((e.kind, e.offset), (ErrorKind::LineTooLong, 16));
+
+        let input: Bytes = b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n";
+        let e = failure(request(options.clone()).parse_stream(&input));
+        assert_eq!((e.kind, e.offset), (ErrorKind::TooManyHeaders, 28));
+        let input: Bytes = b"0\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n";
+        let e = failure(chunk(options.clone()).parse_stream(&input));
+        assert_eq!((e.kind, e.offset), (ErrorKind::TooManyHeaders, 15));
+
+        // Blank lines before a request count towards its line.
+        let input = b"\r\n".repeat(9);
+        let e = failure(request(options.clone()).parse_stream(&&input[..]));
+        assert_eq!((e.kind, e.offset), (ErrorKind::LineTooLong, 0));
+        let input: Bytes = b"\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        let e = failure(request(options.clone()).parse_stream(&input));
+        assert_eq!((e.kind, e.offset), (ErrorKind::LineTooLong, 4));
+        let input: Bytes = b"\r\nGET / HTTP/1.1\r\n\r\n";
+        assert!(request(options.clone()).parse_stream(&input).is_ok());
+
+        let options = options.with_max_chunk_size(4);
+        let input: Bytes = b"4\r\nWiki\r\n";
+        assert!(chunk(options.clone()).parse_stream(&input).is_ok());
+        // Too large before any data arrives.
+        let input: Bytes = b"5\r\n";
+        let e = failure(chunk(options).parse_stream(&input));
+        assert_eq!((e.kind, e.offset), (ErrorKind::ChunkTooLarge, 0));
+
+        let unlimited = Options::new().with_max_line_length(usize::MAX);
+        let input: Bytes = b"GET / HTTP/1.1\r\nHost: x";
+        assert!(matches!(
+            request(unlimited).parse_stream(&input),
+            Err(StreamingError::Incomplete(_))
+        ));
+    }
+
+    #[test]
+    fn chunked() {
+        let body = b"4\r\nWiki\r\n5;name=value\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\n";
+        let mut driver = StreamDriver::new(&body[..], chunk(Options::default())).with_chunk_size(3);
+        let mut data = Vec::new();
+        let trailers = loop {
+            match driver.next() {
+                Some(Ok(Chunk::Data(d))) => data.push(d),
+                Some(Ok(Chunk::Last(trailers))) => break trailers,
+                other => panic!("Expected a chunk, got {other:?}"),
+            }
+        };
+        let data: Vec<SharedBytes> = data;
+        assert_eq!(
+            data.iter().map(|d| d.as_bytes()).collect::<Vec<_>>(),
+            [&b"Wiki"[..], b"pedia", b" in\r\n\r\nchunks."]
+        );
+        assert_eq!(trailers.get("expires").unwrap().value.as_bytes(), b"never");
+        assert!(driver.next().is_none());
+
+        let errors: &[(&[u8], ErrorKind, usize)] = &[
+            (b"zz\r\n", ErrorKind::Expected("chunk size"), 0),
+            (b"4 x\r\nWiki\r\n", ErrorKind::Expected("`;` or CRLF"), 2),
+            (b"4\r\nWikiX\r\n", ErrorKind::Expected("CRLF"), 7),
+            (b"ffffffffffffffffffff\r\n", ErrorKind::ChunkTooLarge, 0),
+        ];
+        for (input, kind, offset) in errors {
+            let e = failure(chunk(Options::default()).parse_stream(input));
+            assert_eq!((e.kind, e.offset), (*kind, *offset), "{input:?}");
+        }
+
+        let mut driver =
+            StreamDriver::new(&b"4\r\nWi"[..], chunk::<SharedBytes>(Options::default()));
+        assert!(matches!(
+            driver.next(),
+            Some(Err(DriverError::Incomplete(Error {
+                kind: ErrorKind::Incomplete,
+                offset: 5
+            })))
+        ));
+    }
+}
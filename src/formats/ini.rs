@@ -0,0 +1,569 @@
+/*!
+# INI
+
+- [`parse`] reads INI files, git config and dotenv files into a [`Document`]:
+  its [`Section`]s and their [`Entry`]s in the order written. Every header,
+  key & value keeps its [`Span`], so a bad setting can be reported at the line
+  it came from.
+- Sections begin with `[section]` or git's `[section "subsection"]`. Entries
+  before the first header belong to an unnamed section.
+- Entries are `key = value`, `key: value` or dotenv's `export KEY=value`. A key
+  alone, as git config allows, has no value.
+- Lines starting with `;` or `#` are comments, as is the rest of a line after
+  whitespace and `;` or `#`.
+- A value continues on the next line after a `\` at the end of the line.
+  Values in `"` have escapes such as `\n` and `\"` decoded, values in `'` are
+  taken as written, and backslashes elsewhere are left alone, so Windows paths
+  need no quoting.
+- Entries are read with [`key_value`]. Any [`Error`] converts to a
+  [`Diagnostic`].
+
+```
+use parlance::{formats::ini, input::span::Span};
+
+let input = Span::new(".git/config", "[remote \"origin\"]\n\turl = https://example.com/repo.git\n");
+let document = ini::parse(&input).unwrap();
+let url = document.get("remote", Some("origin"), "url").unwrap();
+assert_eq!(url.text(), Some("https://example.com/repo.git"));
+assert_eq!(url.value.as_ref().unwrap().span.position(), (2, 8));
+```
+
+[`Diagnostic`]: crate::diagnostics::Diagnostic
+
+*/
+
+use std::fmt;
+
+use crate::{
+    combinators::sandwich::key_value,
+    input::{span::Span, Input, Slice},
+    parse::{Choice, Expectation, Expected, Parser, ParserError, ParserResult, Sequence},
+    primitives::whitespace::{blank, space},
+};
+
+use super::symbol;
+
+type IniResult<O> = ParserResult<Span, O, Expected<Span>, Error>;
+
+/// Every section of a file, in order. The first holds the entries before any
+/// header, and is always present.
+#[derive(Clone, Debug)]
+pub struct Document {
+    pub sections: Vec<Section>,
+}
+impl Document {
+    /// The entries before the first header.
+    pub fn global(&self) -> &Section {
+        &self.sections[0]
+    }
+    /// The sections with the given name & subsection, in order. A section may
+    /// be repeated, adding to its entries.
+    pub fn sections<'a>(
+        &'a self,
+        name: &'a str,
+        subsection: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a Section> {
+        self.sections.iter().filter(move |s| s.is(name, subsection))
+    }
+    /// The last entry for `key` in the given section.
+    pub fn get(&self, section: &str, subsection: Option<&str>, key: &str) -> Option<&Entry> {
+        self.sections
+            .iter()
+            .rev()
+            .filter(|s| s.is(section, subsection))
+            .find_map(|s| s.get(key))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Section {
+    /// `None` for the entries before the first header.
+    pub header: Option<Header>,
+    pub entries: Vec<Entry>,
+}
+impl Section {
+    pub fn name(&self) -> Option<&str> {
+        self.header.as_ref().map(|h| h.name.as_str())
+    }
+    pub fn subsection(&self) -> Option<&str> {
+        self.header
+            .as_ref()
+            .and_then(|h| h.subsection.as_ref())
+            .map(|s| s.text.as_str())
+    }
+    /// The last entry for `key`.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().rev().find(|e| e.key.as_str() == key)
+    }
+    fn is(&self, name: &str, subsection: Option<&str>) -> bool {
+        self.name() == Some(name) && self.subsection() == subsection
+    }
+}
+
+/// A `[section]` or `[section "subsection"]` line.
+#[derive(Clone, Debug)]
+pub struct Header {
+    /// From `[` to `]`.
+    pub span: Span,
+    pub name: Span,
+    pub subsection: Option<Value>,
+}
+
+/// A key and its value.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// From the key, or `export`, to the end of the value.
+    pub span: Span,
+    pub key: Span,
+    /// `None` for a key alone, as opposed to an empty value after `=`.
+    pub value: Option<Value>,
+    /// Whether the entry began with dotenv's `export`.
+    pub export: bool,
+}
+impl Entry {
+    /// The decoded value.
+    pub fn text(&self) -> Option<&str> {
+        self.value.as_ref().map(|v| v.text.as_str())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Value {
+    /// The value as written, including any quotes & continuations, but not
+    /// the whitespace or comment after it.
+    pub span: Span,
+    /// The value with quotes removed, escapes decoded and continued lines
+    /// joined.
+    pub text: String,
+}
+
+span_error! {
+    /// A quoted value or subsection which isn't closed on the same line. The
+    /// span is the opening quote.
+    UnterminatedQuote,
+    /// A backslash in double quotes which doesn't begin a valid escape.
+    InvalidEscape,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(e) => fmt::Display::fmt(e, f),
+            Error::UnterminatedQuote(_) => f.write_str("unterminated quote"),
+            Error::InvalidEscape(span) => write!(f, "invalid escape `{}`", span.as_str()),
+        }
+    }
+}
+
+pub fn parse(input: &Span) -> Result<Document, Error> {
+    let mut sections = vec![Section {
+        header: None,
+        entries: Vec::new(),
+    }];
+    let mut remaining = input.clone();
+    while !remaining.is_empty() {
+        let (line, rest) = match line(&remaining) {
+            Ok(ok) => ok,
+            Err(ParserError::Error(e)) => return Err(Error::Syntax(e)),
+            Err(ParserError::Failure(f)) => return Err(f),
+        };
+        match line {
+            Line::Header(header) => sections.push(Section {
+                header: Some(header),
+                entries: Vec::new(),
+            }),
+            Line::Entry(entry) => sections
+                .last_mut()
+                .expect("There is always a section")
+                .entries
+                .push(entry),
+            Line::Blank => {}
+        }
+        remaining = rest;
+    }
+    Ok(Document { sections })
+}
+
+enum Line {
+    Header(Header),
+    Entry(Entry),
+    /// A blank or comment line.
+    Blank,
+}
+
+/// A line, including its line ending.
+fn line(input: &Span) -> IniResult<Line> {
+    let (_, input) = blank(input)?;
+    (
+        (header, line_end).and().map(|(h, _)| Line::Header(h)),
+        (entry, line_end).and().map(|(e, _)| Line::Entry(e)),
+        line_end.map(|_| Line::Blank),
+    )
+        .or()
+        .parse(&input)
+}
+
+/// Any whitespace & comment, then the end of the line.
+fn line_end(input: &Span) -> IniResult<()> {
+    let (_, remaining) = blank(input)?;
+    let s = remaining.as_str();
+    let comment = if s.starts_with([';', '#']) {
+        s.find(['\r', '\n']).unwrap_or(s.len())
+    } else {
+        0
+    };
+    let s = &s[comment..];
+    let end = if s.is_empty() {
+        0
+    } else if s.starts_with('\n') {
+        1
+    } else if s.starts_with("\r\n") {
+        2
+    } else {
+        return Err(ParserError::Error(Expected::new(
+            remaining.skip(comment),
+            Expectation::label("end of line"),
+        )));
+    };
+    Ok(((), remaining.skip(comment + end)))
+}
+
+/// A `[section]` or `[section "subsection"]` header, up to its `]`.
+fn header(input: &Span) -> IniResult<Header> {
+    let (_, remaining) = symbol("[").parse(input)?;
+    let subsection = (blank, double_quoted).and().map(|(_, s)| s);
+    let ((name, subsection, _, _), remaining) =
+        (section_name, subsection.opt(), blank, symbol("]"))
+            .and()
+            .or_fail()
+            .parse(&remaining)?;
+    let header = Header {
+        span: input.take(input.len() - remaining.len()),
+        name,
+        subsection,
+    };
+    Ok((header, remaining))
+}
+
+/// Up to a `]`, `"` or the end of the line, without trailing whitespace.
+fn section_name(input: &Span) -> IniResult<Span> {
+    let s = input.as_str();
+    let len = s
+        .find(['"', ']', '\r', '\n'])
+        .map_or(s, |end| &s[..end])
+        .trim_end()
+        .len();
+    if len == 0 || s.starts_with(char::is_whitespace) {
+        return Err(ParserError::Error(Expected::new(
+            input.clone(),
+            Expectation::label("section name"),
+        )));
+    }
+    Ok(input.split_at(len))
+}
+
+fn entry(input: &Span) -> IniResult<Entry> {
+    let key_and_value = |input: &Span| {
+        let delimiter = (blank, (symbol("="), symbol(":")).or(), blank).and();
+        (
+            key_value(key, delimiter, value).map(|(k, v)| (k, Some(v))),
+            key.map(|k| (k, None)),
+        )
+            .or()
+            .parse(input)
+    };
+    let exported = (symbol("export"), space, key_and_value)
+        .and()
+        .map(|(_, _, kv)| (true, kv));
+    let ((export, (key, value)), remaining) = (exported, key_and_value.map(|kv| (false, kv)))
+        .or()
+        .parse(input)?;
+    let entry = Entry {
+        span: input.take(input.len() - remaining.len()),
+        key,
+        value,
+        export,
+    };
+    Ok((entry, remaining))
+}
+
+/// Up to a delimiter, comment or the end of the line, without trailing
+/// whitespace.
+fn key(input: &Span) -> IniResult<Span> {
+    let s = input.as_str();
+    let len = s
+        .find(['=', ':', ';', '#', '[', ']', '"', '\r', '\n'])
+        .map_or(s, |end| &s[..end])
+        .trim_end()
+        .len();
+    if len == 0 {
+        return Err(ParserError::Error(Expected::new(
+            input.clone(),
+            Expectation::label("key"),
+        )));
+    }
+    Ok(input.split_at(len))
+}
+
+/// The rest of the line, continued past any `\` at its end, up to any
+/// comment.
+fn value(input: &Span) -> IniResult<Value> {
+    let mut text = String::new();
+    // The length of the input & text up to the last character which isn't
+    // trailing whitespace.
+    let mut end = 0;
+    let mut kept = 0;
+    let mut after_blank = true;
+    let mut remaining = input.clone();
+    loop {
+        let s = remaining.as_str();
+        let Some(c) = s.chars().next() else {
+            break;
+        };
+        if let Some(continuation) = ["\\\n", "\\\r\n"].iter().find(|c| s.starts_with(**c)) {
+            remaining = remaining.skip(continuation.len());
+            after_blank = true;
+            continue;
+        }
+        match c {
+            '"' | '\'' => {
+                let (quoted, rest) = if c == '"' {
+                    double_quoted(&remaining)?
+                } else {
+                    single_quoted(&remaining)?
+                };
+                text.push_str(&quoted.text);
+                remaining = rest;
+            }
+            '\n' => break,
+            '\r' if s.starts_with("\r\n") => break,
+            ';' | '#' if after_blank => break,
+            c if c == ' ' || c == '\t' => {
+                text.push(c);
+                remaining = remaining.skip(c.len_utf8());
+                after_blank = true;
+                continue;
+            }
+            c => {
+                text.push(c);
+                remaining = remaining.skip(c.len_utf8());
+            }
+        }
+        after_blank = false;
+        end = input.len() - remaining.len();
+        kept = text.len();
+    }
+    text.truncate(kept);
+    let value = Value {
+        span: input.take(end),
+        text,
+    };
+    Ok((value, input.skip(end)))
+}
+
+/// Text in `"`, with escapes decoded. The quotes may not span lines, except by
+/// a `\` at the end of a line.
+fn double_quoted(input: &Span) -> IniResult<Value> {
+    let (_, remaining) = symbol("\"").parse(input)?;
+    let s = remaining.as_str();
+    let mut text = String::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => {
+                let end = 1 + idx + 1;
+                let value = Value {
+                    span: input.take(end),
+                    text,
+                };
+                return Ok((value, input.skip(end)));
+            }
+            '\\' => {
+                let decoded = match chars.next() {
+                    Some((_, '\n')) => None,
+                    Some((_, '\r')) if chars.next_if(|(_, c)| *c == '\n').is_some() => None,
+                    Some((_, 'n')) => Some('\n'),
+                    Some((_, 't')) => Some('\t'),
+                    Some((_, 'r')) => Some('\r'),
+                    Some((_, c)) if matches!(c, '\\' | '"' | '\'') => Some(c),
+                    Some((_, c)) => {
+                        let escape = remaining.slice(idx..idx + 1 + c.len_utf8());
+                        return Err(ParserError::Failure(Error::InvalidEscape(escape)));
+                    }
+                    None => break,
+                };
+                text.extend(decoded);
+            }
+            '\n' => break,
+            c => text.push(c),
+        }
+    }
+    Err(ParserError::Failure(Error::UnterminatedQuote(
+        input.take(1),
+    )))
+}
+
+/// Text in `'`, taken as written.
+fn single_quoted(input: &Span) -> IniResult<Value> {
+    let (_, remaining) = symbol("'").parse(input)?;
+    let s = remaining.as_str();
+    match s.find(['\'', '\n']) {
+        Some(idx) if s[idx..].starts_with('\'') => {
+            let end = 1 + idx + 1;
+            let value = Value {
+                span: input.take(end),
+                text: s[..idx].to_string(),
+            };
+            Ok((value, input.skip(end)))
+        }
+        _ => Err(ParserError::Failure(Error::UnterminatedQuote(
+            input.take(1),
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diagnostics::Diagnostic;
+
+    fn parse_str(s: &str) -> Result<Document, Error> {
+        parse(&Span::anonymous(s))
+    }
+
+    #[test]
+    fn git_config() {
+        let document = parse_str(
+            "[core]\n\tbare = false\n\
+             [remote \"origin\"]\n\
+             \turl = https://example.com/repo.git ; where from\n\
+             \tfetch = +refs/heads/*:refs/remotes/origin/*\n\
+             [branch \"say \\\"hi\\\"\"]\n\tmerged\n\
+             [core]\n\tbare = true\n",
+        )
+        .unwrap();
+
+        assert_eq!(document.sections.len(), 5);
+        assert!(document.global().entries.is_empty());
+        assert_eq!(
+            document.get("core", None, "bare").unwrap().text(),
+            Some("true")
+        );
+        assert_eq!(
+            document
+                .get("remote", Some("origin"), "fetch")
+                .unwrap()
+                .text(),
+            Some("+refs/heads/*:refs/remotes/origin/*")
+        );
+
+        let url = document.get("remote", Some("origin"), "url").unwrap();
+        assert_eq!(url.key.position(), (4, 2));
+        let value = url.value.as_ref().unwrap();
+        assert_eq!(value.span.as_str(), "https://example.com/repo.git");
+        assert_eq!(value.span.position(), (4, 8));
+
+        let branch = document
+            .sections("branch", Some("say \"hi\""))
+            .next()
+            .unwrap();
+        assert_eq!(
+            branch.header.as_ref().unwrap().span.as_str(),
+            "[branch \"say \\\"hi\\\"\"]"
+        );
+        let merged = branch.get("merged").unwrap();
+        assert!(merged.value.is_none());
+        assert_eq!(merged.span.as_str(), "merged");
+    }
+
+    #[test]
+    fn ini() {
+        let document = parse_str(
+            "; settings\r\n\
+             name: parlance\r\n\
+             \r\n\
+             [My Section]\r\n\
+             path = C:\\dir\\file.txt\r\n\
+             long = one \\\r\n   two # comment\r\n\
+             quoted = \"  padded  \" ; comment\r\n\
+             mixed = 'it''s' \"a\\tb\"\r\n\
+             hash = a#b\r\n\
+             empty =\r\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            document.global().get("name").unwrap().text(),
+            Some("parlance")
+        );
+        let section = document.sections("My Section", None).next().unwrap();
+        let text = |key| section.get(key).unwrap().text().unwrap();
+        assert_eq!(text("path"), "C:\\dir\\file.txt");
+        assert_eq!(text("long"), "one    two");
+        assert_eq!(text("quoted"), "  padded  ");
+        assert_eq!(text("mixed"), "its a\tb");
+        assert_eq!(text("hash"), "a#b");
+        assert_eq!(text("empty"), "");
+
+        let long = section.get("long").unwrap();
+        assert_eq!(
+            long.value.as_ref().unwrap().span.as_str(),
+            "one \\\r\n   two"
+        );
+        assert_eq!(long.span.position(), (6, 1));
+    }
+
+    #[test]
+    fn dotenv() {
+        let document = parse_str(
+            "# dotenv\n\
+             export API_KEY=secret\n\
+             GREETING=\"hello\\nworld\"\n\
+             RAW='no \\n escapes'\n\
+             export = not exported\n\
+             URL=http://example.com # comment\n",
+        )
+        .unwrap();
+        let global = document.global();
+
+        let key = global.get("API_KEY").unwrap();
+        assert!(key.export);
+        assert_eq!(key.span.as_str(), "export API_KEY=secret");
+        assert_eq!(global.get("GREETING").unwrap().text(), Some("hello\nworld"));
+        assert_eq!(global.get("RAW").unwrap().text(), Some("no \\n escapes"));
+        let export = global.get("export").unwrap();
+        assert!(!export.export);
+        assert_eq!(export.text(), Some("not exported"));
+        assert_eq!(
+            global.get("URL").unwrap().text(),
+            Some("http://example.com")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let e = parse_str("[core\nbare = true\n").unwrap_err();
+        assert!(e.to_string().starts_with("expected `]`"), "{e}");
+        assert_eq!(e.span().position(), (1, 6));
+
+        let e = parse_str("[core]\nname = \"unclosed\nnext = 1\n").unwrap_err();
+        assert_eq!(e.to_string(), "unterminated quote");
+        assert_eq!(e.span().position(), (2, 8));
+
+        let e = parse_str("a = \"\\q\"\n").unwrap_err();
+        assert_eq!(e.to_string(), "invalid escape `\\q`");
+        assert_eq!(e.span().position(), (1, 6));
+
+        let e = parse_str("[core] bare\n").unwrap_err();
+        assert!(e.to_string().starts_with("expected end of line"), "{e}");
+        assert_eq!(e.span().position(), (1, 8));
+
+        let e = parse_str("a = 1\n= 2\n").unwrap_err();
+        assert_eq!(e.span().position(), (2, 1));
+        let diagnostic = Diagnostic::from(e);
+        assert!(
+            diagnostic.message().contains("key"),
+            "{}",
+            diagnostic.message()
+        );
+    }
+}
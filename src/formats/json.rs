@@ -0,0 +1,791 @@
+/*!
+# JSON
+
+- [`parse`] reads [RFC 8259] JSON into a [`Value`] which owns its strings.
+  [`parse_borrowed`] produces a `Value<Text>` instead, whose strings & numbers
+  are [`Span`]s of the input, except for strings whose escapes were decoded.
+- Numbers are kept as written, so no precision is lost before they are read
+  with [`Value::as_i64`], [`Value::as_f64`] etc.
+- [`Options`] limit how deeply arrays & objects nest, and switch on the
+  comments, trailing commas & single-quoted strings of JSONC and JSON5.
+- Syntax errors are [`Expected`] errors. Invalid escapes, control characters
+  and excessive nesting fail at once, with the offending span. Any [`Error`]
+  converts to a [`Diagnostic`].
+- The grammar is built from [`tag`], [`digits`], [`recognize`] and tuple
+  [`Choice`]s & [`Sequence`]s, as an example of a complete parser.
+
+```
+use parlance::{formats::json::{self, Options, Value}, input::span::Span};
+
+let input = Span::new("config.json", r#"{"name": "parlance", "tags": ["parser"]}"#);
+let value = json::parse(&input, &Options::default()).unwrap();
+assert_eq!(value.get("name").and_then(Value::as_str), Some("parlance"));
+```
+
+[RFC 8259]: https://www.rfc-editor.org/rfc/rfc8259
+[`Diagnostic`]: crate::diagnostics::Diagnostic
+[`tag`]: fn@crate::primitives::tag::tag
+
+*/
+
+use std::{fmt, marker::PhantomData};
+
+use crate::{
+    combinators::lookahead::recognize,
+    input::{span::Span, Input, Slice},
+    parse::{Choice, Expectation, Expected, Parser, ParserError, ParserResult, Sequence},
+    primitives::{line::eof, numbers::digits, tag::tag_no_case},
+};
+
+use super::symbol;
+
+type JsonResult<O> = ParserResult<Span, O, Expected<Span>, Error>;
+
+/// A JSON value, with strings & numbers of type `S`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<S = String> {
+    Null,
+    Bool(bool),
+    /// A number, as written.
+    Number(S),
+    String(S),
+    Array(Vec<Value<S>>),
+    /// Members in the order written, including any duplicate keys.
+    Object(Vec<(S, Value<S>)>),
+}
+impl<S: AsRef<str>> Value<S> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s.as_ref()),
+            _ => None,
+        }
+    }
+    /// The number as written, such as `"-1.5e3"`.
+    pub fn as_number(&self) -> Option<&str> {
+        match self {
+            Value::Number(n) => Some(n.as_ref()),
+            _ => None,
+        }
+    }
+    /// The number, if it is written as an integer which fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number()?.parse().ok()
+    }
+    /// The number, if it is written as an integer which fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()?.parse().ok()
+    }
+    /// The nearest `f64` to the number.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number()?.parse().ok()
+    }
+    pub fn as_array(&self) -> Option<&[Value<S>]> {
+        match self {
+            Value::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+    pub fn as_object(&self) -> Option<&[(S, Value<S>)]> {
+        match self {
+            Value::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+    /// The value of the last member named `key`, if this is an object.
+    pub fn get(&self, key: &str) -> Option<&Value<S>> {
+        self.as_object()?
+            .iter()
+            .rev()
+            .find(|(k, _)| k.as_ref() == key)
+            .map(|(_, v)| v)
+    }
+}
+impl<S: Into<String>> Value<S> {
+    pub fn into_owned(self) -> Value {
+        match self {
+            Value::Null => Value::Null,
+            Value::Bool(b) => Value::Bool(b),
+            Value::Number(n) => Value::Number(n.into()),
+            Value::String(s) => Value::String(s.into()),
+            Value::Array(elements) => {
+                Value::Array(elements.into_iter().map(Value::into_owned).collect())
+            }
+            Value::Object(members) => Value::Object(
+                members
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// How a [`Value`] holds the strings & numbers it was parsed from.
+pub trait JsonString: Sized {
+    /// Build a string from its contents in the input, between the quotes.
+    /// `decoded` is given if those contents contained escapes.
+    fn from_span(span: Span, decoded: Option<String>) -> Self;
+}
+impl JsonString for String {
+    fn from_span(span: Span, decoded: Option<String>) -> Self {
+        decoded.unwrap_or_else(|| span.as_str().to_string())
+    }
+}
+
+/// A string from [`parse_borrowed`]: a view of the input, or the decoded string
+/// if it contained escapes.
+#[derive(Clone, Debug)]
+pub enum Text {
+    Borrowed(Span),
+    Owned(String),
+}
+impl Text {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Text::Borrowed(span) => span.as_str(),
+            Text::Owned(s) => s,
+        }
+    }
+}
+impl JsonString for Text {
+    fn from_span(span: Span, decoded: Option<String>) -> Self {
+        match decoded {
+            Some(s) => Text::Owned(s),
+            None => Text::Borrowed(span),
+        }
+    }
+}
+impl AsRef<str> for Text {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl PartialEq for Text {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+impl Eq for Text {}
+impl PartialEq<str> for Text {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl PartialEq<&str> for Text {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+impl From<Text> for String {
+    fn from(value: Text) -> Self {
+        match value {
+            Text::Borrowed(span) => span.as_str().to_string(),
+            Text::Owned(s) => s,
+        }
+    }
+}
+
+/// The dialect of JSON to accept. The default is strict RFC 8259, with arrays &
+/// objects nested at most 128 deep.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    max_depth: usize,
+    comments: bool,
+    trailing_commas: bool,
+    single_quotes: bool,
+}
+impl Options {
+    pub fn new() -> Self {
+        Self {
+            max_depth: 128,
+            comments: false,
+            trailing_commas: false,
+            single_quotes: false,
+        }
+    }
+    /// JSON with comments, as in many editors' settings: comments and trailing
+    /// commas.
+    pub fn jsonc() -> Self {
+        Self::new().with_comments(true).with_trailing_commas(true)
+    }
+    /// The parts of JSON5 these options cover: comments, trailing commas and
+    /// single-quoted strings.
+    pub fn json5() -> Self {
+        Self::jsonc().with_single_quotes(true)
+    }
+    /// Each array or object is one level. Parsing recurses once per level, so
+    /// very large limits risk overflowing the stack.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+    /// `// line` and `/* block */` comments, wherever whitespace is allowed.
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+    /// A comma after the last element of an array or object.
+    pub fn with_trailing_commas(mut self, trailing_commas: bool) -> Self {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+    /// Strings quoted with `'`, in which `"` needn't be escaped. `\'` is then
+    /// accepted in either kind of string.
+    pub fn with_single_quotes(mut self, single_quotes: bool) -> Self {
+        self.single_quotes = single_quotes;
+        self
+    }
+}
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+span_error! {
+    /// A backslash which doesn't begin a valid escape.
+    InvalidEscape,
+    /// A `\u` escape of half a UTF-16 surrogate pair, without the other half.
+    LoneSurrogate,
+    /// A control character in a string, where it must be escaped.
+    ControlCharacter,
+    /// An array or object nested deeper than [`Options::with_max_depth`].
+    TooDeep,
+    /// A `/*` comment which is never closed.
+    UnterminatedComment,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(e) => fmt::Display::fmt(e, f),
+            Error::InvalidEscape(span) => write!(f, "invalid escape `{}`", span.as_str()),
+            Error::LoneSurrogate(span) => write!(f, "unpaired surrogate `{}`", span.as_str()),
+            Error::ControlCharacter(span) => {
+                write!(f, "unescaped control character {:?}", span.as_str())
+            }
+            Error::TooDeep(_) => f.write_str("arrays & objects are nested too deeply"),
+            Error::UnterminatedComment(_) => f.write_str("unterminated comment"),
+        }
+    }
+}
+
+pub fn parse(input: &Span, options: &Options) -> Result<Value, Error> {
+    document(input, options)
+}
+
+/// Parse without copying strings which contain no escapes.
+pub fn parse_borrowed(input: &Span, options: &Options) -> Result<Value<Text>, Error> {
+    document(input, options)
+}
+
+/// A single value, with any whitespace around it, for use within a larger
+/// grammar.
+pub fn value<S: JsonString>(
+    options: Options,
+) -> impl Parser<Span, Value<S>, Expected<Span>, Error> {
+    move |input: &Span| Grammar::new(&options).padded(input, 0)
+}
+
+fn document<S: JsonString>(input: &Span, options: &Options) -> Result<Value<S>, Error> {
+    let grammar = Grammar::new(options);
+    let document = (|i: &Span| grammar.padded(i, 0), eof.to_failure()).and();
+    match document.parse(input) {
+        Ok(((value, _), _)) => Ok(value),
+        Err(ParserError::Error(e)) => Err(Error::Syntax(e)),
+        Err(ParserError::Failure(f)) => Err(f),
+    }
+}
+
+/// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+fn number(input: &Span) -> JsonResult<Span> {
+    if !input
+        .as_str()
+        .starts_with(|c: char| c == '-' || c.is_ascii_digit())
+    {
+        return Err(ParserError::Error(Expected::new(
+            input.clone(),
+            Expectation::label("number"),
+        )));
+    }
+    // Digits must follow a `.` or `e`, so their absence is a failure rather
+    // than the end of the number.
+    let fraction = (symbol("."), digits.to_failure().or_fail()).and();
+    let exponent = (
+        tag_no_case("e").to_failure(),
+        (symbol("+"), symbol("-")).or().opt(),
+        digits.to_failure().or_fail(),
+    )
+        .and();
+    recognize(
+        (
+            symbol("-").opt(),
+            (symbol("0"), digits.to_failure()).or(),
+            fraction.opt(),
+            exponent.opt(),
+        )
+            .and(),
+    )
+    .parse(input)
+}
+
+/// A `\uXXXX` escape at the start of `input`, as a UTF-16 code unit.
+fn code_unit(input: &Span) -> JsonResult<u16> {
+    let hex = input.as_str()[2..]
+        .bytes()
+        .take(4)
+        .take_while(u8::is_ascii_hexdigit)
+        .count();
+    if hex < 4 {
+        return Err(ParserError::Failure(Error::InvalidEscape(
+            input.take(2 + hex),
+        )));
+    }
+    let unit = u16::from_str_radix(&input.as_str()[2..6], 16).expect("Digits were checked");
+    Ok((unit, input.skip(6)))
+}
+
+/// A `\u` escape at the start of `input`, combined with the next if they form
+/// a surrogate pair.
+fn unicode_escape(input: &Span) -> JsonResult<char> {
+    let (unit, remaining) = code_unit(input)?;
+    let lone = || ParserError::Failure(Error::LoneSurrogate(input.take(6)));
+    match unit {
+        0xD800..=0xDBFF => {
+            if !remaining.as_str().starts_with("\\u") {
+                return Err(lone());
+            }
+            let (low, remaining) = code_unit(&remaining)?;
+            match char::decode_utf16([unit, low]).next() {
+                Some(Ok(c)) => Ok((c, remaining)),
+                _ => Err(lone()),
+            }
+        }
+        0xDC00..=0xDFFF => Err(lone()),
+        _ => Ok((
+            char::from_u32(unit.into()).expect("Not a surrogate"),
+            remaining,
+        )),
+    }
+}
+
+struct Grammar<'o, S> {
+    options: &'o Options,
+    string: PhantomData<S>,
+}
+impl<'o, S: JsonString> Grammar<'o, S> {
+    fn new(options: &'o Options) -> Self {
+        Self {
+            options,
+            string: PhantomData,
+        }
+    }
+
+    /// Whitespace, and comments if enabled.
+    fn trivia(&self, input: &Span) -> JsonResult<()> {
+        let mut remaining = input.clone();
+        loop {
+            if let Some((_, rest)) = remaining.take_while(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            {
+                remaining = rest;
+            } else if !self.options.comments {
+                break;
+            } else if let Some((_, rest)) = remaining.pop(&"//") {
+                remaining = rest.skip(rest.as_str().find('\n').unwrap_or(rest.len()));
+            } else if let Some((open, rest)) = remaining.pop(&"/*") {
+                match rest.as_str().find("*/") {
+                    Some(end) => remaining = rest.skip(end + 2),
+                    None => return Err(ParserError::Failure(Error::UnterminatedComment(open))),
+                }
+            } else {
+                break;
+            }
+        }
+        Ok(((), remaining))
+    }
+
+    /// A value, with any trivia around it.
+    fn padded(&self, input: &Span, depth: usize) -> JsonResult<Value<S>> {
+        let ((_, value, _), remaining) = (
+            |i: &Span| self.trivia(i),
+            |i: &Span| self.value(i, depth),
+            |i: &Span| self.trivia(i),
+        )
+            .and()
+            .parse(input)?;
+        Ok((value, remaining))
+    }
+
+    fn value(&self, input: &Span, depth: usize) -> JsonResult<Value<S>> {
+        (
+            |i: &Span| self.object(i, depth),
+            |i: &Span| self.array(i, depth),
+            (|i: &Span| self.string(i)).map(Value::String),
+            number.map(|n| Value::Number(S::from_span(n, None))),
+            symbol("true").map(|_| Value::Bool(true)),
+            symbol("false").map(|_| Value::Bool(false)),
+            symbol("null").map(|_| Value::Null),
+        )
+            .or()
+            .parse(input)
+    }
+
+    /// The depth within an array or object opened by `open`.
+    fn nest(&self, open: &Span, depth: usize) -> Result<usize, ParserError<Expected<Span>, Error>> {
+        if depth < self.options.max_depth {
+            Ok(depth + 1)
+        } else {
+            Err(ParserError::Failure(Error::TooDeep(open.clone())))
+        }
+    }
+
+    fn array(&self, input: &Span, depth: usize) -> JsonResult<Value<S>> {
+        let (open, remaining) = symbol("[").parse(input)?;
+        let depth = self.nest(&open, depth)?;
+        let element = |i: &Span| self.padded(i, depth);
+        let (elements, remaining) = self.list(&remaining, "]", element)?;
+        Ok((Value::Array(elements), remaining))
+    }
+
+    fn object(&self, input: &Span, depth: usize) -> JsonResult<Value<S>> {
+        let (open, remaining) = symbol("{").parse(input)?;
+        let depth = self.nest(&open, depth)?;
+        let member = (
+            |i: &Span| self.trivia(i),
+            |i: &Span| self.string(i),
+            |i: &Span| self.trivia(i),
+            symbol(":"),
+            |i: &Span| self.padded(i, depth),
+        )
+            .and()
+            .map(|(_, key, _, _, value)| (key, value));
+        let (members, remaining) = self.list(&remaining, "}", member)?;
+        Ok((Value::Object(members), remaining))
+    }
+
+    /// Comma-separated items up to `close`, which is consumed. Items parse the
+    /// trivia around themselves.
+    fn list<O>(
+        &self,
+        input: &Span,
+        close: &'static str,
+        item: impl Parser<Span, O, Expected<Span>, Error>,
+    ) -> JsonResult<Vec<O>> {
+        let (_, remaining) = self.trivia(input)?;
+        let (first, mut remaining) = (
+            symbol(close).map(|_| None),
+            (|i: &Span| item.parse(i)).map(Some),
+        )
+            .or()
+            .parse(&remaining)?;
+        let mut items = Vec::from_iter(first);
+        if items.is_empty() {
+            return Ok((items, remaining));
+        }
+        loop {
+            let (more, rest) = (symbol(",").map(|_| true), symbol(close).map(|_| false))
+                .or()
+                .parse(&remaining)?;
+            if !more {
+                return Ok((items, rest));
+            }
+            if self.options.trailing_commas {
+                let (_, after) = self.trivia(&rest)?;
+                if let Ok((_, after)) = symbol::<_, Error>(close).parse(&after) {
+                    return Ok((items, after));
+                }
+            }
+            let (o, rest) = item.parse(&rest)?;
+            items.push(o);
+            remaining = rest;
+        }
+    }
+
+    fn string(&self, input: &Span) -> JsonResult<S> {
+        let quote = match input.as_str().chars().next() {
+            Some('"') => '"',
+            Some('\'') if self.options.single_quotes => '\'',
+            _ => {
+                return Err(ParserError::Error(Expected::new(
+                    input.clone(),
+                    Expectation::label("string"),
+                )))
+            }
+        };
+        let content = input.skip(1);
+        let mut remaining = content.clone();
+        // Only allocated once an escape is found.
+        let mut decoded: Option<String> = None;
+        loop {
+            let plain = remaining
+                .as_str()
+                .find(|c: char| c == quote || c == '\\' || c < ' ')
+                .unwrap_or(remaining.len());
+            if let Some(decoded) = &mut decoded {
+                decoded.push_str(&remaining.as_str()[..plain]);
+            }
+            remaining = remaining.skip(plain);
+
+            match remaining.as_str().chars().next() {
+                Some('\\') => {
+                    let (c, rest) = self.escape(&remaining)?;
+                    decoded
+                        .get_or_insert_with(|| {
+                            let before = content.take(content.len() - remaining.len());
+                            before.as_str().to_string()
+                        })
+                        .push(c);
+                    remaining = rest;
+                }
+                Some(c) if c == quote => {
+                    let text = content.take(content.len() - remaining.len());
+                    return Ok((S::from_span(text, decoded), remaining.skip(1)));
+                }
+                Some(c) => {
+                    return Err(ParserError::Failure(Error::ControlCharacter(
+                        remaining.take(c.len_utf8()),
+                    )))
+                }
+                None => {
+                    return Err(ParserError::Error(Expected::new(
+                        remaining,
+                        Expectation::tag(quote.to_string()),
+                    )))
+                }
+            }
+        }
+    }
+
+    /// A backslash escape at the start of `input`.
+    fn escape(&self, input: &Span) -> JsonResult<char> {
+        let c = match input.as_str()[1..].chars().next() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('\'') if self.options.single_quotes => '\'',
+            Some('u') => return unicode_escape(input),
+            Some(c) => {
+                return Err(ParserError::Failure(Error::InvalidEscape(
+                    input.take(1 + c.len_utf8()),
+                )))
+            }
+            None => {
+                return Err(ParserError::Error(Expected::new(
+                    input.skip(1),
+                    Expectation::label("escape"),
+                )))
+            }
+        };
+        Ok((c, input.skip(2)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use super::*;
+    use crate::diagnostics::Diagnostic;
+
+    fn parse_str(s: &str, options: &Options) -> Result<Value, Error> {
+        parse(&Span::anonymous(s), options)
+    }
+
+    #[test]
+    fn values() {
+        let value = parse_str(
+            r#" {"a": [1, -2.5e3, true, false, null], "b": {"c": "x\ty\u00e9\ud83d\ude00"}, "a": 3} "#,
+            &Options::default(),
+        )
+        .unwrap();
+
+        assert_eq!(value.get("a").and_then(Value::as_i64), Some(3));
+        let members = value.as_object().unwrap();
+        assert_eq!(
+            members[0].1,
+            Value::Array(vec![
+                Value::Number("1".to_string()),
+                Value::Number("-2.5e3".to_string()),
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Null,
+            ])
+        );
+        assert_eq!(members[0].1.as_array().unwrap()[1].as_f64(), Some(-2500.0));
+        assert_eq!(
+            value
+                .get("b")
+                .and_then(|b| b.get("c"))
+                .and_then(Value::as_str),
+            Some("x\tyé😀")
+        );
+    }
+
+    #[test]
+    fn borrowed() {
+        let value = parse_borrowed(
+            &Span::anonymous(r#"["plain", "esc\"aped", 10]"#),
+            &Options::default(),
+        )
+        .unwrap();
+        let elements = value.as_array().unwrap();
+
+        let Value::String(Text::Borrowed(plain)) = &elements[0] else {
+            panic!("Expected a borrowed string")
+        };
+        assert_eq!(plain.position(), (1, 3));
+        assert_eq!(
+            elements[1],
+            Value::String(Text::Owned("esc\"aped".to_string()))
+        );
+        assert!(matches!(&elements[2], Value::Number(Text::Borrowed(_))));
+        assert_eq!(
+            value.into_owned(),
+            parse_str(r#"["plain", "esc\"aped", 10]"#, &Options::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |s: &str| {
+            let e = parse_str(s, &Options::default()).unwrap_err();
+            (e.to_string(), e.span().position())
+        };
+
+        assert_eq!(
+            error("[1, 2,]"),
+            (
+                "expected `[`, `false`, `null`, `true`, `{`, number or string, found ']'"
+                    .to_string(),
+                (1, 7)
+            )
+        );
+        assert_eq!(
+            error("{\"a\" 1}"),
+            ("expected `:`, found '1'".to_string(), (1, 6))
+        );
+        assert_eq!(
+            error("[1.]"),
+            ("expected digit, found ']'".to_string(), (1, 4))
+        );
+        assert_eq!(
+            error("[01]"),
+            ("expected `,` or `]`, found '1'".to_string(), (1, 3))
+        );
+        assert_eq!(
+            error("[\"a\\qb\"]"),
+            ("invalid escape `\\q`".to_string(), (1, 4))
+        );
+        assert_eq!(
+            error("[\"\\ud800x\"]"),
+            ("unpaired surrogate `\\ud800`".to_string(), (1, 3))
+        );
+        assert_eq!(
+            error("\"a\nb\""),
+            ("unescaped control character \"\\n\"".to_string(), (1, 3))
+        );
+        assert_eq!(
+            error("{} x"),
+            ("expected end of input, found 'x'".to_string(), (1, 4))
+        );
+
+        let deep = parse_str("[[[1]]]", &Options::default().with_max_depth(2)).unwrap_err();
+        assert!(matches!(deep, Error::TooDeep(_)));
+        assert_eq!(deep.span().position(), (1, 3));
+
+        let diagnostic = Diagnostic::from(parse_str("[tru]", &Options::default()).unwrap_err());
+        assert_eq!(
+            diagnostic.to_string(),
+            "\
+error: expected `[`, `]`, `false`, `null`, `true`, `{`, number or string, found 't'
+ --> 1:2
+  |
+1 | [tru]
+  |  ^
+"
+        );
+    }
+
+    #[test]
+    fn dialects() {
+        let text = "{\n  // comment\n  'a': [1, /* two */ 2,],\n}";
+        assert!(parse_str(text, &Options::default()).is_err());
+        assert!(parse_str(text, &Options::jsonc()).is_err());
+
+        let value = parse_str(text, &Options::json5()).unwrap();
+        assert_eq!(
+            value,
+            Value::Object(vec![(
+                "a".to_string(),
+                Value::Array(vec![
+                    Value::Number("1".to_string()),
+                    Value::Number("2".to_string())
+                ])
+            )])
+        );
+        assert_eq!(
+            parse_str(r#"['it\'s "quoted"']"#, &Options::json5())
+                .unwrap()
+                .as_array()
+                .unwrap()[0]
+                .as_str(),
+            Some("it's \"quoted\"")
+        );
+        assert!(matches!(
+            parse_str("[1] /* open", &Options::jsonc()),
+            Err(Error::UnterminatedComment(_))
+        ));
+    }
+
+    /// Cases from JSONTestSuite: `y_` files must be accepted, `n_` files
+    /// rejected, and `i_` files may be either.
+    #[test]
+    fn test_suite() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/JSONTestSuite/test_parsing");
+        let mut wrong = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let accepted = match String::from_utf8(fs::read(&path).unwrap()) {
+                Ok(text) => {
+                    let input = Span::new(name.clone(), text);
+                    let owned = parse(&input, &Options::default());
+                    let borrowed = parse_borrowed(&input, &Options::default());
+                    assert_eq!(
+                        owned.as_ref().ok(),
+                        borrowed.map(Value::into_owned).as_ref().ok()
+                    );
+                    owned.is_ok()
+                }
+                Err(_) => false,
+            };
+            let right = match &name[..2] {
+                "y_" => accepted,
+                "n_" => !accepted,
+                _ => true,
+            };
+            if !right {
+                wrong.push(name);
+            }
+        }
+        assert!(wrong.is_empty(), "Wrongly handled: {:?}", wrong);
+    }
+}
@@ -0,0 +1,469 @@
+/*!
+# logfmt
+
+- [`parse_line`] splits a [logfmt] line such as
+  `level=info msg="request done" dur=12ms` into a [`Line`] of [`Field`]s, in
+  the order written.
+- Any [`Input`] can be read, such as a [`Span`] or `&str`. Keys & values are
+  slices of it, except quoted values containing escapes, which are decoded
+  into a [`Text::Owned`].
+- Quoted values are found with [`double_quoted`], and may contain `\"`, `\\`,
+  `\n`, `\t`, `\r` and `\uXXXX` escapes. Fields are read with [`key_value`].
+- A key without `=` is a flag, which [`Field::as_bool`] reads as `true`.
+- [`Field::integer`], [`Field::unsigned`] & [`Field::real`] read a value
+  with [`integer`], [`unsigned_integer`] & [`real`], and [`Field::quantity`] reads a number with a unit, such as
+  `12ms`. [`Line`] has the same accessors by key.
+- [`line_stream`] reads one line at a time, for use with a
+  [`StreamDriver`][crate::driver::StreamDriver].
+
+```
+use parlance::formats::logfmt;
+
+let line = logfmt::parse_line(&r#"level=info msg="request done" dur=12ms status=200 cached"#).unwrap();
+assert_eq!(line.text("msg"), Some("request done"));
+assert_eq!(line.unsigned::<u16>("status"), Some(200));
+assert_eq!(line.quantity::<f64>("dur"), Some((12.0, "ms")));
+assert_eq!(line.get("cached").and_then(|f| f.as_bool()), Some(true));
+```
+
+[logfmt]: https://brandur.org/logfmt
+
+*/
+
+use std::fmt;
+
+use crate::{
+    combinators::sandwich::key_value,
+    diagnostics::Diagnostic,
+    input::{span::Span, Input, Slice},
+    parse::{
+        Choice, Expectation, Expected, Never, NotFound, Parser, ParserError, ParserResult,
+        Sequence, StreamingError, StreamingOk, StreamingResult,
+    },
+    primitives::{
+        line::eof,
+        numbers::{integer, real, unsigned_integer, Integer, Real, UnsignedInteger},
+        quote::double_quoted,
+        tag::tag,
+        whitespace::{blank, space},
+    },
+};
+
+type LogfmtResult<I, O> = ParserResult<I, O, Expected<I>, Error<I>>;
+
+/// A value, borrowed from the input unless escapes were decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Text<I> {
+    Borrowed(I),
+    Owned(String),
+}
+impl<I: Input> Text<I> {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Text::Borrowed(s) => s.as_str(),
+            Text::Owned(s) => s,
+        }
+    }
+}
+impl<I: Input> AsRef<str> for Text<I> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field<I> {
+    pub key: I,
+    /// `None` for a flag, as opposed to an empty value after `=`.
+    pub value: Option<Text<I>>,
+}
+impl<I: Input> Field<I> {
+    pub fn as_str(&self) -> Option<&str> {
+        self.value.as_ref().map(Text::as_str)
+    }
+    /// A flag is `true`, as is the value `true`, and the value `false` is
+    /// `false`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.as_str() {
+            None | Some("true") => Some(true),
+            Some("false") => Some(false),
+            Some(_) => None,
+        }
+    }
+    /// The value, if all of it is an integer which fits in `O`.
+    pub fn integer<O: Integer>(&self) -> Option<O> {
+        whole(integer::<_, O, NotFound>, self.as_str()?)
+    }
+    /// The value, if all of it is an unsigned integer which fits in `O`.
+    pub fn unsigned<O: UnsignedInteger>(&self) -> Option<O> {
+        whole(unsigned_integer::<_, O, NotFound>, self.as_str()?)
+    }
+    /// The value, if all of it is a number.
+    pub fn real<O: Real>(&self) -> Option<O> {
+        whole(real::<_, O, NotFound>, self.as_str()?)
+    }
+    /// A number followed by a unit of letters or `%`, such as `12ms` or
+    /// `1.5GiB`. The unit may be empty.
+    pub fn quantity<O: Real>(&self) -> Option<(O, &str)> {
+        let (n, unit) = real::<_, O, NotFound>.parse(&self.as_str()?).ok()?;
+        unit.chars()
+            .all(|c| c.is_alphabetic() || c == '%')
+            .then_some((n, unit))
+    }
+}
+
+/// Run `parser` on `s`, which it must consume entirely.
+fn whole<'s, O, F>(parser: impl Parser<&'s str, O, NotFound, F>, s: &'s str) -> Option<O> {
+    match parser.parse(&s) {
+        Ok((output, "")) => Some(output),
+        _ => None,
+    }
+}
+
+/// The fields of a line, in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line<I> {
+    pub fields: Vec<Field<I>>,
+}
+impl<I: Input> Line<I> {
+    /// The last field with `key`.
+    pub fn get(&self, key: &str) -> Option<&Field<I>> {
+        self.fields.iter().rev().find(|f| f.key.as_str() == key)
+    }
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+    pub fn integer<O: Integer>(&self, key: &str) -> Option<O> {
+        self.get(key)?.integer()
+    }
+    pub fn unsigned<O: UnsignedInteger>(&self, key: &str) -> Option<O> {
+        self.get(key)?.unsigned()
+    }
+    pub fn real<O: Real>(&self, key: &str) -> Option<O> {
+        self.get(key)?.real()
+    }
+    pub fn quantity<O: Real>(&self, key: &str) -> Option<(O, &str)> {
+        self.get(key)?.quantity()
+    }
+}
+
+#[derive(Clone)]
+pub enum Error<I> {
+    /// The line doesn't follow the grammar.
+    Syntax(Expected<I>),
+    /// A quoted value which is never closed. The input is the opening quote.
+    UnterminatedQuote(I),
+    /// A backslash which doesn't begin a valid escape.
+    InvalidEscape(I),
+}
+impl<I: Input> Error<I> {
+    /// Where the error was found. For syntax errors, this is the rest of the
+    /// line from that point.
+    pub fn input(&self) -> &I {
+        match self {
+            Error::Syntax(e) => e.input(),
+            Error::UnterminatedQuote(input) | Error::InvalidEscape(input) => input,
+        }
+    }
+}
+impl<I: Slice> fmt::Debug for Error<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(e) => f.debug_tuple("Syntax").field(e).finish(),
+            Error::UnterminatedQuote(input) => {
+                f.debug_tuple("UnterminatedQuote").field(input).finish()
+            }
+            Error::InvalidEscape(input) => f.debug_tuple("InvalidEscape").field(input).finish(),
+        }
+    }
+}
+impl<I: Input> fmt::Display for Error<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(e) => fmt::Display::fmt(e, f),
+            Error::UnterminatedQuote(_) => f.write_str("unterminated quote"),
+            Error::InvalidEscape(input) => write!(f, "invalid escape `{}`", input.as_str()),
+        }
+    }
+}
+impl<I: Input> std::error::Error for Error<I> {}
+impl<I> From<Expected<I>> for Error<I> {
+    fn from(value: Expected<I>) -> Self {
+        Error::Syntax(value)
+    }
+}
+impl<I> From<Never> for Error<I> {
+    fn from(value: Never) -> Self {
+        unreachable!()
+    }
+}
+impl From<Error<Span>> for Diagnostic {
+    fn from(value: Error<Span>) -> Self {
+        match value {
+            Error::Syntax(e) => e.into(),
+            e => Diagnostic::error(e.to_string(), e.input().clone()),
+        }
+    }
+}
+
+/// A whole line, without a line ending.
+pub fn parse_line<I: Input>(input: &I) -> Result<Line<I>, Error<I>> {
+    match (fields, eof.to_failure()).and().parse(input) {
+        Ok(((fields, _), _)) => Ok(Line { fields }),
+        Err(ParserError::Error(e)) => Err(Error::Syntax(e)),
+        Err(ParserError::Failure(f)) => Err(f),
+    }
+}
+
+/// A line, including its line ending.
+pub fn line<I: Input>(input: &I) -> LogfmtResult<I, Line<I>> {
+    match line_stream(input) {
+        Ok(StreamingOk::Complete(line, remaining) | StreamingOk::Partial(line, remaining)) => {
+            Ok((line, remaining))
+        }
+        Err(StreamingError::Error(e)) => Err(ParserError::Error(e)),
+        Err(StreamingError::Incomplete(f) | StreamingError::Failure(f)) => {
+            Err(ParserError::Failure(f))
+        }
+    }
+}
+
+/// A line, including its line ending. Until the line ending has been read the
+/// line may continue, so the result is [`Partial`][StreamingOk::Partial], and
+/// any error is [`Incomplete`][StreamingError::Incomplete]. A blank line has
+/// no fields.
+pub fn line_stream<I: Input>(input: &I) -> StreamingResult<I, Line<I>, Expected<I>, Error<I>> {
+    if input.is_empty() {
+        return Err(StreamingError::Error(Expected::new(
+            input.clone(),
+            Expectation::label("line"),
+        )));
+    }
+    let (content, remaining) = match input.take_until(|c| c == '\n') {
+        Some((content, remaining)) => (content, Some(remaining.skip(1))),
+        None => (input.clone(), None),
+    };
+    let content = match content.as_str().strip_suffix('\r') {
+        Some(s) => content.take(s.len()),
+        None => content,
+    };
+    match (parse_line(&content), remaining) {
+        (Ok(line), Some(remaining)) => Ok(StreamingOk::Complete(line, remaining)),
+        (Ok(line), None) => Ok(StreamingOk::Partial(line, input.skip(input.len()))),
+        (Err(e), Some(_)) => Err(StreamingError::Failure(e)),
+        (Err(e), None) => Err(StreamingError::Incomplete(e)),
+    }
+}
+
+/// Fields separated by spaces, with any spaces around them.
+fn fields<I: Input>(input: &I) -> LogfmtResult<I, Vec<Field<I>>> {
+    let mut fields = Vec::new();
+    let (_, mut remaining) = blank(input)?;
+    while !remaining.is_empty() {
+        let (field, rest) = field(&remaining)?;
+        fields.push(field);
+        if rest.is_empty() {
+            remaining = rest;
+        } else {
+            (_, remaining) = space(&rest)?;
+        }
+    }
+    Ok((fields, remaining))
+}
+
+/// `key=value`, or a key alone.
+fn field<I: Input>(input: &I) -> LogfmtResult<I, Field<I>> {
+    (
+        key_value(key, tag("=").to_failure(), value).map(|(key, value)| Field {
+            key,
+            value: Some(value),
+        }),
+        key.map(|key| Field { key, value: None }),
+    )
+        .or()
+        .parse(input)
+}
+
+fn is_bare(c: char) -> bool {
+    c > ' ' && c != '=' && c != '"'
+}
+
+fn key<I: Input>(input: &I) -> LogfmtResult<I, I> {
+    input
+        .take_while(is_bare)
+        .ok_or_else(|| ParserError::Error(Expected::new(input.clone(), Expectation::label("key"))))
+}
+
+/// A quoted value, or the value up to the next space, which may be empty.
+fn value<I: Input>(input: &I) -> LogfmtResult<I, Text<I>> {
+    if !input.as_str().starts_with('"') {
+        let (value, remaining) = input
+            .take_while(is_bare)
+            .unwrap_or_else(|| (input.empty(), input.clone()));
+        return Ok((Text::Borrowed(value), remaining));
+    }
+    let (quoted, remaining) = double_quoted::<_, Expected<I>>(input).map_err(|e| match e {
+        ParserError::Error(e) => ParserError::Error(e),
+        ParserError::Failure(_) => ParserError::Failure(Error::UnterminatedQuote(input.take(1))),
+    })?;
+    Ok((decode(&quoted)?, remaining))
+}
+
+fn decode<I: Input>(quoted: &I) -> Result<Text<I>, ParserError<Expected<I>, Error<I>>> {
+    let s = quoted.as_str();
+    if !s.contains('\\') {
+        return Ok(Text::Borrowed(quoted.clone()));
+    }
+    let mut text = String::with_capacity(s.len());
+    let mut chars = s.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        let invalid =
+            |len| ParserError::Failure(Error::InvalidEscape(quoted.slice(idx..idx + len)));
+        let decoded = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, c @ ('"' | '\\'))) => c,
+            Some((_, 'u')) => {
+                let c = s
+                    .get(idx + 2..idx + 6)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|hex| char::from_u32(u32::from_str_radix(hex, 16).ok()?))
+                    .ok_or_else(|| invalid(2))?;
+                chars.nth(3);
+                c
+            }
+            Some((_, c)) => return Err(invalid(1 + c.len_utf8())),
+            None => return Err(invalid(1)),
+        };
+        text.push(decoded);
+    }
+    Ok(Text::Owned(text))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::{DriverError, StreamDriver};
+
+    #[test]
+    fn fields() {
+        let line = parse_line(
+            &r#"  ts=2024-01-02T03:04:05Z http.method=GET path="/a b" err="say \"no\"\n" empty= debug a=1 a=2 "#,
+        )
+        .unwrap();
+        let keys = line.fields.iter().map(|f| f.key).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "ts",
+                "http.method",
+                "path",
+                "err",
+                "empty",
+                "debug",
+                "a",
+                "a"
+            ]
+        );
+
+        assert_eq!(
+            line.get("http.method").unwrap().value,
+            Some(Text::Borrowed("GET"))
+        );
+        assert_eq!(
+            line.get("path").unwrap().value,
+            Some(Text::Borrowed("/a b"))
+        );
+        assert_eq!(
+            line.get("err").unwrap().value,
+            Some(Text::Owned("say \"no\"\n".to_string()))
+        );
+        assert_eq!(line.text("empty"), Some(""));
+        assert_eq!(line.get("debug").unwrap().value, None);
+        assert_eq!(line.text("a"), Some("2"));
+        assert_eq!(
+            parse_line(&r#"s="\u00e9\\""#).unwrap().text("s"),
+            Some("é\\")
+        );
+        assert!(parse_line(&"").unwrap().fields.is_empty());
+    }
+
+    #[test]
+    fn typed() {
+        let line = parse_line(
+            &"status=200 big=300 neg=-4 dur=1.5ms pct=99% ratio=0.25 n=12x3 ok=false on",
+        )
+        .unwrap();
+        assert_eq!(line.unsigned::<u16>("status"), Some(200));
+        assert_eq!(line.unsigned::<u8>("big"), None);
+        assert_eq!(line.unsigned::<u32>("neg"), None);
+        assert_eq!(line.integer::<i32>("neg"), Some(-4));
+        assert_eq!(line.integer::<i32>("dur"), None);
+        assert_eq!(line.integer::<i32>("missing"), None);
+        assert_eq!(line.real::<f64>("ratio"), Some(0.25));
+        assert_eq!(line.real::<f64>("dur"), None);
+        assert_eq!(line.quantity::<f64>("dur"), Some((1.5, "ms")));
+        assert_eq!(line.quantity::<f32>("pct"), Some((99.0, "%")));
+        assert_eq!(line.quantity::<f64>("status"), Some((200.0, "")));
+        assert_eq!(line.quantity::<f64>("n"), None);
+        assert_eq!(line.get("ok").and_then(Field::as_bool), Some(false));
+        assert_eq!(line.get("on").and_then(Field::as_bool), Some(true));
+        assert_eq!(line.get("dur").and_then(Field::as_bool), None);
+    }
+
+    #[test]
+    fn errors() {
+        let span = |s| Span::new("app.log", s);
+
+        let e = parse_line(&span("a=1 msg=\"unclosed")).unwrap_err();
+        assert_eq!(e.to_string(), "unterminated quote");
+        assert_eq!(e.input().position(), (1, 9));
+
+        let e = parse_line(&span("msg=\"bad \\x\"")).unwrap_err();
+        assert_eq!(e.to_string(), "invalid escape `\\x`");
+        assert_eq!(e.input().position(), (1, 10));
+
+        let e = parse_line(&span("msg=\"a\"b")).unwrap_err();
+        assert!(e.to_string().starts_with("expected space"), "{e}");
+        assert_eq!(e.input().position(), (1, 8));
+
+        let e = parse_line(&span("a=1 =2")).unwrap_err();
+        assert!(e.to_string().starts_with("expected key"), "{e}");
+        let diagnostic = Diagnostic::from(e);
+        assert_eq!(diagnostic.primary().span().position(), (1, 5));
+    }
+
+    #[test]
+    fn streaming() {
+        let data = "level=info msg=\"first line\"\r\n\nlevel=warn msg=\"second\" n=2\nlast";
+        let lines = StreamDriver::new(data.as_bytes(), line_stream)
+            .with_chunk_size(5)
+            .map(|line: Result<Line<Span>, _>| line.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].text("msg"), Some("first line"));
+        assert!(lines[1].fields.is_empty());
+        assert_eq!(lines[2].integer::<i32>("n"), Some(2));
+        assert_eq!(lines[2].get("msg").unwrap().key.position(), (3, 12));
+        assert!(lines[3].get("last").unwrap().value.is_none());
+
+        let mut driver = StreamDriver::new("a=1\nb=\"x\nc=3\n".as_bytes(), line_stream::<Span>);
+        assert!(matches!(driver.next(), Some(Ok(_))));
+        assert!(matches!(
+            driver.next(),
+            Some(Err(DriverError::Failure(Error::UnterminatedQuote(_))))
+        ));
+
+        let mut driver = StreamDriver::new("a=1\nb=\"x".as_bytes(), line_stream::<Span>);
+        assert!(matches!(driver.next(), Some(Ok(_))));
+        assert!(matches!(
+            driver.next(),
+            Some(Err(DriverError::Incomplete(Error::UnterminatedQuote(_))))
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+/*!
+# Formats
+
+Parsers for complete data formats, written with the rest of the library.
+
+- [`csv`]: RFC 4180 CSV, with other delimiters such as TSV, and a streaming
+  record parser.
+- [`http1`]: HTTP/1.1 message heads and chunked bodies, parsed incrementally.
+- [`ini`]: INI files, git config and dotenv files, keeping the span of every
+  key & value.
+- [`json`]: RFC 8259 JSON, with optional JSONC & JSON5 extensions.
+- [`logfmt`]: `key=value` log lines, with typed access to their fields.
+- [`sexpr`]: S-expressions, with reader macros and datum comments.
+
+*/
+
+use crate::{
+    input::Input,
+    parse::{Expected, Never, Parser},
+    primitives::tag::tag,
+};
+
+/// Declares the `Error` of a format read from a [`Span`][crate::input::span::Span]:
+/// a `Syntax` variant for [`Expected`] errors, then the given variants, each
+/// holding the span where it was found. `span()`, the conversions from
+/// [`Expected`] & [`Never`] and into a [`Diagnostic`][crate::diagnostics::Diagnostic]
+/// are implemented, leaving `Display` to the format.
+macro_rules! span_error {
+    ($($(#[$doc:meta])* $variant:ident,)*) => {
+        #[derive(Clone, Debug)]
+        pub enum Error {
+            /// The input doesn't follow the grammar.
+            Syntax($crate::parse::Expected<$crate::input::span::Span>),
+            $($(#[$doc])* $variant($crate::input::span::Span),)*
+        }
+        impl Error {
+            /// Where the error was found. For syntax errors, this is the rest
+            /// of the input from that point.
+            pub fn span(&self) -> &$crate::input::span::Span {
+                match self {
+                    Error::Syntax(e) => e.input(),
+                    $(Error::$variant(span) => span,)*
+                }
+            }
+        }
+        impl std::error::Error for Error {}
+        impl From<$crate::parse::Expected<$crate::input::span::Span>> for Error {
+            fn from(value: $crate::parse::Expected<$crate::input::span::Span>) -> Self {
+                Error::Syntax(value)
+            }
+        }
+        impl From<$crate::parse::Never> for Error {
+            fn from(value: $crate::parse::Never) -> Self {
+                unreachable!()
+            }
+        }
+        impl From<Error> for $crate::diagnostics::Diagnostic {
+            fn from(value: Error) -> Self {
+                match value {
+                    Error::Syntax(e) => e.into(),
+                    e => $crate::diagnostics::Diagnostic::error(e.to_string(), e.span().clone()),
+                }
+            }
+        }
+    };
+}
+
+/// A tag, with the error & failure types of a format.
+fn symbol<I: Input, F: From<Never>>(s: &'static str) -> impl Parser<I, I, Expected<I>, F> {
+    tag(s).to_failure()
+}
+
+pub mod csv;
+pub mod http1;
+pub mod ini;
+pub mod json;
+pub mod logfmt;
+pub mod sexpr;
@@ -0,0 +1,624 @@
+/*!
+# S-expressions
+
+- [`parse`] reads every [`Datum`] in the input, and [`datum`] reads one for use
+  within a larger grammar. Every datum keeps its [`Span`], including those
+  nested in lists.
+- Atoms are symbols, [`Number`]s and strings in `"`, with escapes such as `\n`
+  and `\"` decoded. A token which isn't a whole number, such as `1+` or `-`,
+  is a symbol.
+- Lists are written `(a b c)`, dotted pairs `(a . b)` or `(a b . c)`, and
+  vectors `#(a b c)`.
+- The reader macros `'x`, `` `x ``, `,x` and `,@x` are kept as
+  [`Kind::Quoted`], and [`Quote::symbol`] gives the form each stands for, such
+  as `(quote x)`.
+- `;` comments to the end of the line, `#| |#` comments nest, and `#;`
+  comments out the datum after it.
+- [`Options`] limit how deeply data may nest, so that deeply nested input
+  fails rather than overflowing the stack.
+- Any [`Error`] converts to a [`Diagnostic`].
+
+```
+use parlance::{formats::sexpr::{self, Options}, input::span::Span};
+
+let input = Span::new("server.conf", "(server\n  (port 8080) ; the default\n  #;(host \"localhost\"))");
+let data = sexpr::parse(&input, &Options::default()).unwrap();
+let server = data[0].as_list().unwrap();
+assert_eq!(server.len(), 2);
+assert_eq!(server[1].head(), Some("port"));
+assert_eq!(server[1].span.position(), (2, 3));
+assert_eq!(data[0].to_string(), "(server (port 8080))");
+```
+
+[`Diagnostic`]: crate::diagnostics::Diagnostic
+
+*/
+
+use std::fmt;
+
+use crate::{
+    input::{span::Span, Input, Slice},
+    parse::{Expectation, Expected, NotFound, Parser, ParserError, ParserResult},
+    primitives::{
+        numbers::{Number, NumberToken},
+        quote::{double_quoted, UnterminatedQuote},
+    },
+};
+
+type SexprResult<O> = ParserResult<Span, O, Expected<Span>, Error>;
+
+#[derive(Clone, Debug)]
+pub struct Datum {
+    pub span: Span,
+    pub kind: Kind,
+}
+impl Datum {
+    pub fn as_symbol(&self) -> Option<&str> {
+        match self.kind {
+            Kind::Symbol => Some(self.span.as_str()),
+            _ => None,
+        }
+    }
+    pub fn as_number(&self) -> Option<&Number> {
+        match &self.kind {
+            Kind::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+    pub fn as_string(&self) -> Option<&str> {
+        match &self.kind {
+            Kind::String(s) => Some(s),
+            _ => None,
+        }
+    }
+    /// The items of a list which isn't dotted.
+    pub fn as_list(&self) -> Option<&[Datum]> {
+        match &self.kind {
+            Kind::List(items) => Some(items),
+            _ => None,
+        }
+    }
+    pub fn as_vector(&self) -> Option<&[Datum]> {
+        match &self.kind {
+            Kind::Vector(items) => Some(items),
+            _ => None,
+        }
+    }
+    /// The symbol at the start of a list, such as `define` in
+    /// `(define x 1)`.
+    pub fn head(&self) -> Option<&str> {
+        self.as_list()?.first()?.as_symbol()
+    }
+}
+/// Writes the datum on one line, with single spaces between items and
+/// without comments.
+impl fmt::Display for Datum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn items(f: &mut fmt::Formatter<'_>, items: &[Datum]) -> fmt::Result {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{item}")?;
+            }
+            Ok(())
+        }
+        match &self.kind {
+            Kind::Symbol => f.write_str(self.span.as_str()),
+            Kind::Number(Number::Unsigned(n)) => write!(f, "{n}"),
+            Kind::Number(Number::Signed(n)) => write!(f, "{n}"),
+            Kind::Number(Number::Real(n)) => write!(f, "{n:?}"),
+            Kind::String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        '\r' => f.write_str("\\r")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Kind::List(list) => {
+                f.write_str("(")?;
+                items(f, list)?;
+                f.write_str(")")
+            }
+            Kind::Dotted(list, tail) => {
+                f.write_str("(")?;
+                items(f, list)?;
+                write!(f, " . {tail})")
+            }
+            Kind::Vector(vector) => {
+                f.write_str("#(")?;
+                items(f, vector)?;
+                f.write_str(")")
+            }
+            Kind::Quoted(quote, datum) => write!(f, "{}{datum}", quote.prefix()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Kind {
+    /// A symbol, whose name is the text of the datum's span.
+    Symbol,
+    Number(Number),
+    /// A string, with its escapes decoded.
+    String(String),
+    List(Vec<Datum>),
+    /// A list ending in ` . tail)`, with at least one item before the dot.
+    Dotted(Vec<Datum>, Box<Datum>),
+    Vector(Vec<Datum>),
+    /// A datum after a reader macro such as `'`.
+    Quoted(Quote, Box<Datum>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Quote {
+    Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
+}
+impl Quote {
+    /// Every reader macro, with `,@` before `,` so that it is tried first.
+    const ALL: [Quote; 4] = [
+        Quote::UnquoteSplicing,
+        Quote::Quote,
+        Quote::Quasiquote,
+        Quote::Unquote,
+    ];
+
+    /// The reader macro, such as `'`.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Quote::Quote => "'",
+            Quote::Quasiquote => "`",
+            Quote::Unquote => ",",
+            Quote::UnquoteSplicing => ",@",
+        }
+    }
+    /// The symbol of the form the reader macro stands for, such as `quote`, as
+    /// `'x` stands for `(quote x)`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Quote::Quote => "quote",
+            Quote::Quasiquote => "quasiquote",
+            Quote::Unquote => "unquote",
+            Quote::UnquoteSplicing => "unquote-splicing",
+        }
+    }
+}
+
+/// Limits on the input accepted. The default allows data nested 128 deep.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    max_depth: usize,
+}
+impl Options {
+    pub fn new() -> Self {
+        Self { max_depth: 128 }
+    }
+    /// Each list, vector, reader macro or datum comment is one level. Reading
+    /// recurses once per level, so very large limits risk overflowing the
+    /// stack.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+span_error! {
+    /// A list or vector which is never closed, at its `(`.
+    Unclosed,
+    /// A string which is never closed, at its `"`.
+    UnterminatedString,
+    /// A `#|` comment which is never closed.
+    UnterminatedComment,
+    /// A backslash in a string which doesn't begin a valid escape.
+    InvalidEscape,
+    /// A number which doesn't fit in a [`Number`].
+    InvalidNumber,
+    /// A datum nested deeper than [`Options::with_max_depth`], at the syntax
+    /// which opens it.
+    TooDeep,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(e) => fmt::Display::fmt(e, f),
+            Error::Unclosed(_) => f.write_str("unclosed `(`"),
+            Error::UnterminatedString(_) => f.write_str("unterminated string"),
+            Error::UnterminatedComment(_) => f.write_str("unterminated comment"),
+            Error::InvalidEscape(span) => write!(f, "invalid escape `{}`", span.as_str()),
+            Error::InvalidNumber(span) => write!(f, "number `{}` is too large", span.as_str()),
+            Error::TooDeep(_) => f.write_str("data are nested too deeply"),
+        }
+    }
+}
+
+/// Every datum in the input, in order.
+pub fn parse(input: &Span, options: &Options) -> Result<Vec<Datum>, Error> {
+    let reader = Reader { options };
+    let mut data = Vec::new();
+    let mut remaining = input.clone();
+    loop {
+        let result = reader.atmosphere(&remaining, 0).and_then(|((), rest)| {
+            if rest.is_empty() {
+                Ok(None)
+            } else {
+                reader.datum_at(&rest, 0).map(Some)
+            }
+        });
+        match result {
+            Ok(None) => return Ok(data),
+            Ok(Some((datum, rest))) => {
+                data.push(datum);
+                remaining = rest;
+            }
+            Err(ParserError::Error(e)) => return Err(Error::Syntax(e)),
+            Err(ParserError::Failure(f)) => return Err(f),
+        }
+    }
+}
+
+/// A single datum, after any whitespace & comments before it, for use within
+/// a larger grammar.
+pub fn datum(options: Options) -> impl Parser<Span, Datum, Expected<Span>, Error> {
+    move |input: &Span| Reader { options: &options }.datum(input, 0)
+}
+
+fn expected<O>(input: &Span, expectation: Expectation) -> SexprResult<O> {
+    Err(ParserError::Error(Expected::new(
+        input.clone(),
+        expectation,
+    )))
+}
+
+struct Reader<'o> {
+    options: &'o Options,
+}
+impl Reader<'_> {
+    /// The depth within a datum opened by `open`, such as a list.
+    fn nest(&self, open: &Span, depth: usize) -> Result<usize, ParserError<Expected<Span>, Error>> {
+        if depth < self.options.max_depth {
+            Ok(depth + 1)
+        } else {
+            Err(ParserError::Failure(Error::TooDeep(open.clone())))
+        }
+    }
+
+    fn datum(&self, input: &Span, depth: usize) -> SexprResult<Datum> {
+        let ((), remaining) = self.atmosphere(input, depth)?;
+        self.datum_at(&remaining, depth)
+    }
+
+    /// A datum which must be present, such as within a list.
+    fn required(&self, input: &Span, depth: usize) -> SexprResult<Datum> {
+        self.datum(input, depth).map_err(|e| match e {
+            ParserError::Error(e) => ParserError::Failure(Error::Syntax(e)),
+            f => f,
+        })
+    }
+
+    /// Whitespace & comments, including datum comments.
+    fn atmosphere(&self, input: &Span, depth: usize) -> SexprResult<()> {
+        let mut remaining = input.clone();
+        loop {
+            if let Some((_, rest)) = remaining.take_while(char::is_whitespace) {
+                remaining = rest;
+            } else if let Some((_, rest)) = remaining.pop(&";") {
+                remaining = rest.skip(rest.as_str().find('\n').unwrap_or(rest.len()));
+            } else if let Some((open, rest)) = remaining.pop(&"#|") {
+                remaining = block_comment(&open, &rest)?;
+            } else if let Some((open, rest)) = remaining.pop(&"#;") {
+                let (_, rest) = self.required(&rest, self.nest(&open, depth)?)?;
+                remaining = rest;
+            } else {
+                break;
+            }
+        }
+        Ok(((), remaining))
+    }
+
+    /// A datum at the start of the input.
+    fn datum_at(&self, input: &Span, depth: usize) -> SexprResult<Datum> {
+        let s = input.as_str();
+        let (kind, remaining) = if let Some((open, rest)) = input.pop(&"(") {
+            let depth = self.nest(&open, depth)?;
+            let ((items, tail), rest) = self.items(&open, &rest, true, depth)?;
+            match tail {
+                Some(tail) => (Kind::Dotted(items, Box::new(tail)), rest),
+                None => (Kind::List(items), rest),
+            }
+        } else if let Some((open, rest)) = input.pop(&"#(") {
+            let depth = self.nest(&open, depth)?;
+            let ((items, _), rest) = self.items(&open, &rest, false, depth)?;
+            (Kind::Vector(items), rest)
+        } else if s.starts_with('"') {
+            string(input)?
+        } else if let Some(quote) = Quote::ALL.into_iter().find(|q| s.starts_with(q.prefix())) {
+            let (open, rest) = input.split_at(quote.prefix().len());
+            let (datum, rest) = self.required(&rest, self.nest(&open, depth)?)?;
+            (Kind::Quoted(quote, Box::new(datum)), rest)
+        } else {
+            atom(input)?
+        };
+        let datum = Datum {
+            span: input.take(input.len() - remaining.len()),
+            kind,
+        };
+        Ok((datum, remaining))
+    }
+
+    /// The items of a list or vector opened by `open`, up to & including its
+    /// `)`, and the tail of a dotted list. `depth` is the depth of the items.
+    fn items(
+        &self,
+        open: &Span,
+        input: &Span,
+        dotted: bool,
+        depth: usize,
+    ) -> SexprResult<(Vec<Datum>, Option<Datum>)> {
+        let unclosed = || ParserError::Failure(Error::Unclosed(open.clone()));
+        let mut items = Vec::new();
+        let mut remaining = input.clone();
+        loop {
+            let ((), rest) = self.atmosphere(&remaining, depth)?;
+            if let Some((_, rest)) = rest.pop(&")") {
+                return Ok(((items, None), rest));
+            } else if rest.is_empty() {
+                return Err(unclosed());
+            } else if dotted && !items.is_empty() && is_dot(rest.as_str()) {
+                let (tail, rest) = self.required(&rest.skip(1), depth)?;
+                let ((), rest) = self.atmosphere(&rest, depth)?;
+                return match rest.pop(&")") {
+                    Some((_, rest)) => Ok(((items, Some(tail)), rest)),
+                    None if rest.is_empty() => Err(unclosed()),
+                    None => Err(ParserError::Failure(Error::Syntax(Expected::new(
+                        rest,
+                        Expectation::tag(")"),
+                    )))),
+                };
+            }
+            let (item, rest) = self.required(&rest, depth)?;
+            items.push(item);
+            remaining = rest;
+        }
+    }
+}
+
+/// The rest of a `#|` comment, which may contain others.
+fn block_comment(open: &Span, input: &Span) -> Result<Span, ParserError<Expected<Span>, Error>> {
+    let s = input.as_str().as_bytes();
+    let mut depth = 1;
+    let mut idx = 0;
+    while idx < s.len() {
+        if s[idx..].starts_with(b"|#") {
+            depth -= 1;
+            idx += 2;
+            if depth == 0 {
+                return Ok(input.skip(idx));
+            }
+        } else if s[idx..].starts_with(b"#|") {
+            depth += 1;
+            idx += 2;
+        } else {
+            idx += 1;
+        }
+    }
+    Err(ParserError::Failure(Error::UnterminatedComment(
+        open.clone(),
+    )))
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '"' | ';' | '\'' | '`' | ',')
+}
+
+/// Whether the input starts with a `.` on its own.
+fn is_dot(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next() == Some('.') && chars.next().is_none_or(is_delimiter)
+}
+
+/// A symbol or number, up to the next delimiter.
+fn atom(input: &Span) -> SexprResult<Kind> {
+    let Some((token, remaining)) = input.take_while(|c| !is_delimiter(c)) else {
+        return expected(input, Expectation::label("datum"));
+    };
+    // `#` only begins the syntax above, and a lone `.` only appears in a
+    // dotted list.
+    if token.as_str().starts_with('#') || token.as_str() == "." {
+        return expected(input, Expectation::label("datum"));
+    }
+    match NumberToken::parse::<NotFound>(&token) {
+        Ok((_, rest)) if rest.is_empty() => match Number::parse::<_, NotFound>(&token) {
+            Ok((n, _)) => Ok((Kind::Number(n), remaining)),
+            Err(_) => Err(ParserError::Failure(Error::InvalidNumber(token))),
+        },
+        _ => Ok((Kind::Symbol, remaining)),
+    }
+}
+
+/// A string in `"`, with escapes decoded. Strings may span lines.
+fn string(input: &Span) -> SexprResult<Kind> {
+    let (raw, remaining) = double_quoted(input).map_err(|e| match e {
+        ParserError::Error(e) => ParserError::Error(e),
+        ParserError::Failure(UnterminatedQuote) => {
+            ParserError::Failure(Error::UnterminatedString(input.take(1)))
+        }
+    })?;
+    let mut text = String::new();
+    let mut chars = raw.as_str().char_indices();
+    while let Some((idx, c)) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        let decoded = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, c)) if matches!(c, '\\' | '"') => c,
+            Some((_, c)) => {
+                let escape = raw.slice(idx..idx + 1 + c.len_utf8());
+                return Err(ParserError::Failure(Error::InvalidEscape(escape)));
+            }
+            None => unreachable!("The closing quote can't be escaped"),
+        };
+        text.push(decoded);
+    }
+    Ok((Kind::String(text), remaining))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_str(s: &str) -> Result<Vec<Datum>, Error> {
+        parse(&Span::anonymous(s), &Options::default())
+    }
+
+    fn written(s: &str) -> Vec<String> {
+        parse_str(s).unwrap().iter().map(Datum::to_string).collect()
+    }
+
+    #[test]
+    fn atoms() {
+        let data = parse_str("foo -5 +5 7 1.5 2e3 1+ - ... \"a \\\"b\\\"\\n\" a#b").unwrap();
+        assert_eq!(data[0].as_symbol(), Some("foo"));
+        assert_eq!(data[1].as_number(), Some(&Number::Signed(-5)));
+        assert_eq!(data[2].as_number(), Some(&Number::Unsigned(5)));
+        assert_eq!(data[3].as_number(), Some(&Number::Unsigned(7)));
+        assert_eq!(data[4].as_number(), Some(&Number::Real(1.5)));
+        assert_eq!(data[5].as_number(), Some(&Number::Real(2000.0)));
+        for (datum, symbol) in data[6..9].iter().zip(["1+", "-", "..."]) {
+            assert_eq!(datum.as_symbol(), Some(symbol));
+        }
+        assert_eq!(data[9].as_string(), Some("a \"b\"\n"));
+        assert_eq!(data[9].span.as_str(), "\"a \\\"b\\\"\\n\"");
+        assert_eq!(data[10].as_symbol(), Some("a#b"));
+    }
+
+    #[test]
+    fn lists() {
+        let data = parse_str("(a (b c) () . d)\n#(1 \"two\" (3 . 4))\n(x.y 0.5)").unwrap();
+        assert_eq!(data.len(), 3);
+        let Kind::Dotted(items, tail) = &data[0].kind else {
+            panic!("Expected a dotted list")
+        };
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].span.as_str(), "(b c)");
+        assert_eq!(items[1].as_list().unwrap()[1].span.position(), (1, 7));
+        assert_eq!(items[2].as_list().map(<[_]>::len), Some(0));
+        assert_eq!(tail.as_symbol(), Some("d"));
+
+        let vector = data[1].as_vector().unwrap();
+        assert_eq!(data[1].span.position(), (2, 1));
+        assert_eq!(vector[2].span.position(), (2, 11));
+        assert!(matches!(vector[2].kind, Kind::Dotted(..)));
+        assert_eq!(data[2].head(), Some("x.y"));
+        assert_eq!(
+            data[2].as_list().unwrap()[1].as_number(),
+            Some(&Number::Real(0.5))
+        );
+
+        assert_eq!(
+            written("(a  (b c) () .  d) #( 1 \"two\" (3 . 4) )"),
+            ["(a (b c) () . d)", "#(1 \"two\" (3 . 4))"]
+        );
+    }
+
+    #[test]
+    fn reader_macros() {
+        let data = parse_str("'a `(b ,c ,@(d e)) ',x").unwrap();
+        let Kind::Quoted(Quote::Quote, quoted) = &data[0].kind else {
+            panic!("Expected a quote")
+        };
+        assert_eq!(quoted.as_symbol(), Some("a"));
+        let Kind::Quoted(Quote::Quasiquote, template) = &data[1].kind else {
+            panic!("Expected a quasiquote")
+        };
+        let template = template.as_list().unwrap();
+        assert!(matches!(template[1].kind, Kind::Quoted(Quote::Unquote, _)));
+        let Kind::Quoted(quote, spliced) = &template[2].kind else {
+            panic!("Expected an unquote")
+        };
+        assert_eq!(quote.symbol(), "unquote-splicing");
+        assert_eq!(spliced.span.as_str(), "(d e)");
+        assert_eq!(template[2].span.position(), (1, 11));
+        assert_eq!(
+            written("' a `(b , c ,@ (d e)) ',x"),
+            ["'a", "`(b ,c ,@(d e))", "',x"]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            written(
+                "; comment\na #| outer #| inner |# still |# b\n\
+                 #;(ignored (stuff)) c #; #; d e f\n(g #;h) (i ; j\n k)"
+            ),
+            ["a", "b", "c", "f", "(g)", "(i k)"]
+        );
+        assert!(parse_str("  ; only a comment").unwrap().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let position = |s: &str| parse_str(s).unwrap_err().span().position();
+
+        assert!(matches!(parse_str("(a (b c)"), Err(Error::Unclosed(_))));
+        assert_eq!(position("(a (b c)"), (1, 1));
+        assert!(matches!(parse_str("#(a . b)"), Err(Error::Syntax(_))));
+        assert_eq!(position("#(a . b)"), (1, 5));
+        assert_eq!(position("(. a)"), (1, 2));
+        assert_eq!(position("(a . b c)"), (1, 8));
+        assert_eq!(position("(a . )"), (1, 6));
+        assert_eq!(position("a )"), (1, 3));
+        assert_eq!(position("(a #;)"), (1, 6));
+        assert_eq!(position("#t"), (1, 1));
+
+        let e = parse_str("(a\n \"unterminated)").unwrap_err();
+        assert!(matches!(e, Error::UnterminatedString(_)));
+        assert_eq!(e.span().position(), (2, 2));
+        let e = parse_str("a #| #| |#").unwrap_err();
+        assert!(matches!(e, Error::UnterminatedComment(_)));
+        assert_eq!(e.span().position(), (1, 3));
+        let e = parse_str("\"a\\qb\"").unwrap_err();
+        assert_eq!(e.to_string(), "invalid escape `\\q`");
+        assert_eq!(e.span().position(), (1, 3));
+        let e = parse_str("(x 99999999999)").unwrap_err();
+        assert!(matches!(e, Error::InvalidNumber(_)));
+        assert_eq!(e.span().as_str(), "99999999999");
+    }
+
+    #[test]
+    fn depth() {
+        let options = Options::new().with_max_depth(3);
+        let parse_deep = |s: &str| parse(&Span::anonymous(s), &options);
+        assert!(parse_deep("(a #(b 'c))").is_ok());
+        for (input, column) in [("(a #(b ((c))))", 9), ("'''#;'x", 4), ("(#;#;#;a b c)", 6)] {
+            let e = parse_deep(input).unwrap_err();
+            assert!(matches!(e, Error::TooDeep(_)), "{input}");
+            assert_eq!(e.span().position(), (1, column), "{input}");
+        }
+
+        // Fails rather than overflowing the stack.
+        let e = parse_str(&"(".repeat(100_000)).unwrap_err();
+        assert_eq!(e.span().position(), (1, 129));
+        let e = parse_str(&"'".repeat(100_000)).unwrap_err();
+        assert!(matches!(e, Error::TooDeep(_)));
+    }
+}
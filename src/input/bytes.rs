@@ -0,0 +1,265 @@
+use std::{fmt, ops::Range, str::Utf8Error, sync::Arc};
+
+use super::{string::SharedString, Addressable, Input, Slice};
+
+/// Binary input. This is the counterpart to [`Input`] for data which may not be
+/// valid UTF-8.
+pub trait ByteInput: Slice {
+    /// The text input produced by [`to_text`][ByteInput::to_text].
+    type Text: Input;
+
+    fn as_bytes(&self) -> &[u8];
+    /// View this input as text, if it is valid UTF-8.
+    fn to_text(&self) -> Result<Self::Text, Utf8Error>;
+    fn take_while<P: Fn(u8) -> bool>(&self, predicate: P) -> Option<(Self, Self)>
+    where
+        Self: Sized,
+    {
+        match self.as_bytes().iter().position(|b| !predicate(*b)) {
+            Some(0) => None,
+            Some(idx) => Some(self.split_at(idx)),
+            None if self.is_empty() => None,
+            None => Some(self.take_all()),
+        }
+    }
+    fn take_until<P: Fn(u8) -> bool>(&self, predicate: P) -> Option<(Self, Self)>
+    where
+        Self: Sized,
+    {
+        self.as_bytes()
+            .iter()
+            .position(|b| predicate(*b))
+            .map(|idx| self.split_at(idx))
+    }
+    /// The offset of the first occurrence of `needle`.
+    fn find<T: AsRef<[u8]>>(&self, needle: &T) -> Option<usize> {
+        let needle = needle.as_ref();
+        if needle.is_empty() {
+            return Some(0);
+        }
+        self.as_bytes()
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
+    fn pop<T: AsRef<[u8]>>(&self, tag: &T) -> Option<(Self, Self)>
+    where
+        Self: Sized,
+    {
+        let tag = tag.as_ref();
+        if self.as_bytes().starts_with(tag) {
+            Some(self.split_at(tag.len()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Describe the first byte of some data, for [`Slice::describe_next`].
+fn describe_next_byte(b: &[u8]) -> Option<String> {
+    b.first().map(|b| format!("{:#04x}", b))
+}
+
+impl Slice for &[u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+    fn empty(&self) -> Self {
+        &[]
+    }
+    fn slice(&self, range: Range<usize>) -> Self {
+        &self[range]
+    }
+    fn split_at(&self, mid: usize) -> (Self, Self) {
+        <[u8]>::split_at(self, mid)
+    }
+    fn split_at_checked(&self, mid: usize) -> Option<(Self, Self)> {
+        <[u8]>::split_at_checked(self, mid)
+    }
+    fn describe_next(&self) -> Option<String> {
+        describe_next_byte(self)
+    }
+}
+impl<'a> ByteInput for &'a [u8] {
+    type Text = &'a str;
+
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+    fn to_text(&self) -> Result<&'a str, Utf8Error> {
+        std::str::from_utf8(self)
+    }
+}
+
+/// A cheaply cloneable view into a shared buffer of bytes.
+#[derive(Clone, PartialEq)]
+pub struct SharedBytes {
+    content: Arc<Vec<u8>>,
+    range: Range<usize>,
+    /// The offset of the buffer from the start of its stream.
+    start: usize,
+}
+impl SharedBytes {
+    pub fn new(b: Vec<u8>) -> Self {
+        Self::new_continued(b, 0)
+    }
+    /// As [`new`][Self::new], for a buffer which begins `offset` bytes into
+    /// a larger stream.
+    pub fn new_continued(b: Vec<u8>, offset: usize) -> Self {
+        let range = 0..b.len();
+        Self {
+            content: Arc::new(b),
+            range,
+            start: offset,
+        }
+    }
+    /// The byte offset of this view from the start of the stream.
+    pub fn offset(&self) -> usize {
+        self.start + self.range.start
+    }
+}
+impl Slice for SharedBytes {
+    fn len(&self) -> usize {
+        self.range.len()
+    }
+    fn empty(&self) -> Self {
+        Self {
+            content: self.content.clone(),
+            range: self.range.start..self.range.start,
+            start: self.start,
+        }
+    }
+    fn slice(&self, range: Range<usize>) -> Self {
+        let start = self.range.start + range.start;
+        assert!(start <= self.range.end);
+        let end = start + range.len();
+        assert!(end <= self.range.end);
+
+        Self {
+            content: self.content.clone(),
+            range: start..end,
+            start: self.start,
+        }
+    }
+    fn split_at(&self, mid: usize) -> (Self, Self) {
+        let idx = self.range.start + mid;
+        assert!(idx <= self.range.end);
+
+        (
+            Self {
+                content: self.content.clone(),
+                range: self.range.start..idx,
+                start: self.start,
+            },
+            Self {
+                content: self.content.clone(),
+                range: idx..self.range.end,
+                start: self.start,
+            },
+        )
+    }
+    fn split_at_checked(&self, mid: usize) -> Option<(Self, Self)> {
+        if self.range.start + mid <= self.range.end {
+            Some(self.split_at(mid))
+        } else {
+            None
+        }
+    }
+    fn describe_next(&self) -> Option<String> {
+        describe_next_byte(self.as_bytes())
+    }
+}
+impl Addressable for SharedBytes {
+    fn offset(&self) -> usize {
+        SharedBytes::offset(self)
+    }
+    fn same_source(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.content, &other.content)
+    }
+}
+impl ByteInput for SharedBytes {
+    type Text = SharedString;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.content[self.range.clone()]
+    }
+    /// Copies the view into a new string, which keeps the view's offset.
+    fn to_text(&self) -> Result<SharedString, Utf8Error> {
+        let text = std::str::from_utf8(self.as_bytes())?;
+        Ok(SharedString::new_continued(text.to_string(), self.offset()))
+    }
+}
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+impl fmt::Debug for SharedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let content = self.as_bytes().escape_ascii();
+        if self.range.len() == self.content.len() {
+            f.write_fmt(format_args!("SharedBytes [ b\"{}\" ]", content))
+        } else if self.range.start == 0 {
+            f.write_fmt(format_args!("SharedBytes [ b\"{}\" .. ]", content))
+        } else if self.range.end == self.content.len() {
+            f.write_fmt(format_args!("SharedBytes [ .. b\"{}\" ]", content))
+        } else {
+            f.write_fmt(format_args!("SharedBytes [ .. b\"{}\" .. ]", content))
+        }
+    }
+}
+impl Default for SharedBytes {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+impl From<Vec<u8>> for SharedBytes {
+    fn from(value: Vec<u8>) -> Self {
+        Self::new(value)
+    }
+}
+impl From<&[u8]> for SharedBytes {
+    fn from(value: &[u8]) -> Self {
+        Self::new(value.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_while_is_inclusive() {
+        let b: &[u8] = b"xxxyyy";
+        assert_eq!(
+            ByteInput::take_while(&b, |b| b == b'x'),
+            Some((&b"xxx"[..], &b"yyy"[..]))
+        );
+        assert_eq!(ByteInput::take_while(&b, |b| b == b'y'), None);
+    }
+
+    #[test]
+    fn shared_bytes_views() {
+        let b = SharedBytes::new(b"GET /\r\n\xff\xfe".to_vec());
+        let (line, rest) = b.split_at(b.find(b"\r\n").unwrap());
+        assert_eq!(line.as_bytes(), b"GET /");
+        assert_eq!(line.to_text().unwrap().as_str(), "GET /");
+
+        let (_, body) = rest.pop(b"\r\n").unwrap();
+        assert_eq!(body.offset(), 7);
+        assert!(body.to_text().is_err());
+    }
+
+    #[test]
+    fn text_offsets() {
+        let b = SharedBytes::new(b"key=value\n\xff".to_vec());
+        let key = b.take(3).to_text().unwrap();
+        let value = b.slice(4..9).to_text().unwrap();
+        assert_eq!((key.as_str(), value.as_str()), ("key", "value"));
+        assert_eq!((key.offset(), value.offset()), (0, 4));
+
+        let b = SharedBytes::new_continued(b"\xffabc".to_vec(), 100);
+        let text = b.skip(2).to_text().unwrap();
+        assert_eq!((text.as_str(), text.offset()), ("bc", 102));
+        assert_eq!(text.skip(1).offset(), 103);
+    }
+}
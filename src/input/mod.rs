@@ -1,19 +1,22 @@
 use std::{fmt, ops::Range};
 
+pub mod bytes;
 pub mod span;
 pub mod string;
+pub mod tokens;
 pub mod transform;
 
-pub trait Input: Clone + fmt::Debug {
-    fn as_str(&self) -> &str;
-    fn len(&self) -> usize {
-        self.as_str().len()
-    }
+/// Input which can be measured & divided. This is the common ground between
+/// text ([`Input`]) and binary ([`ByteInput`][bytes::ByteInput]) input.
+pub trait Slice: Clone + fmt::Debug {
+    fn len(&self) -> usize;
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
     fn empty(&self) -> Self;
     fn slice(&self, range: Range<usize>) -> Self;
+    /// Split the input in two at `mid`, which may be its length to leave
+    /// nothing after the split, as with [`str::split_at`].
     fn split_at(&self, mid: usize) -> (Self, Self)
     where
         Self: Sized;
@@ -30,12 +33,25 @@ pub trait Input: Clone + fmt::Debug {
     where
         Self: Sized,
     {
-        if self.len() <= count {
+        if count <= self.len() {
             Some(self.take(count))
         } else {
             None
         }
     }
+    fn take_all(&self) -> (Self, Self) {
+        self.split_at(self.len())
+    }
+    fn skip(&self, count: usize) -> Self {
+        self.slice(count..self.len())
+    }
+    /// Describe the next item of input, for use in error messages.
+    /// Returns `None` at the end of input.
+    fn describe_next(&self) -> Option<String>;
+}
+
+pub trait Input: Slice {
+    fn as_str(&self) -> &str;
     fn take_while<P: Fn(char) -> bool>(&self, predicate: P) -> Option<(Self, Self)>
     where
         Self: Sized,
@@ -55,12 +71,6 @@ pub trait Input: Clone + fmt::Debug {
             .find(|(_, c)| predicate(*c))
             .map(|(i, _)| self.split_at(i))
     }
-    fn take_all(&self) -> (Self, Self) {
-        self.split_at(self.len())
-    }
-    fn skip(&self, count: usize) -> Self {
-        self.slice(count..self.len())
-    }
     fn pop<T: AsRef<str>>(&self, tag: &T) -> Option<(Self, Self)>
     where
         Self: Sized,
@@ -80,11 +90,7 @@ pub trait Input: Clone + fmt::Debug {
     {
         let tag = tag.as_ref();
         if let Some((s, remaining)) = self.split_at_checked(tag.len()) {
-            if tag
-                .chars()
-                .zip(s.as_str().chars())
-                .all(|(a, b)| a.to_ascii_lowercase() == b.to_ascii_lowercase())
-            {
+            if tag.eq_ignore_ascii_case(s.as_str()) {
                 return Some((s, remaining));
             }
         }
@@ -93,10 +99,21 @@ pub trait Input: Clone + fmt::Debug {
     }
 }
 
-impl Input for &str {
-    fn as_str(&self) -> &str {
-        self
-    }
+/// Input which is a view into a larger shared source, and knows where in that
+/// source it starts. Results for such input can be cached by position, see
+/// [`memoize`][fn@crate::combinators::memoize].
+pub trait Addressable: Slice {
+    /// The byte offset of this input from the start of its source.
+    fn offset(&self) -> usize;
+    fn same_source(&self, other: &Self) -> bool;
+}
+
+/// Describe the first character of some text, for [`Slice::describe_next`].
+pub(crate) fn describe_next_char(s: &str) -> Option<String> {
+    s.chars().next().map(|c| format!("{:?}", c))
+}
+
+impl Slice for &str {
     fn len(&self) -> usize {
         str::len(self)
     }
@@ -124,12 +141,17 @@ impl Input for &str {
     {
         &self[0..count]
     }
+    fn describe_next(&self) -> Option<String> {
+        describe_next_char(self)
+    }
 }
-
-impl Input for String {
+impl Input for &str {
     fn as_str(&self) -> &str {
-        self.as_str()
+        self
     }
+}
+
+impl Slice for String {
     fn len(&self) -> usize {
         String::len(self)
     }
@@ -162,6 +184,14 @@ impl Input for String {
     {
         self.as_str().take(count).to_string()
     }
+    fn describe_next(&self) -> Option<String> {
+        describe_next_char(self)
+    }
+}
+impl Input for String {
+    fn as_str(&self) -> &str {
+        self.as_str()
+    }
 }
 
 #[cfg(test)]
@@ -179,4 +209,13 @@ mod tests {
         assert_eq!("xxxyyy".take_until(|c| c == 'y'), Some(("xxx", "yyy")));
         assert_eq!("xxxyyy".take_until(|c| c == 'z'), None);
     }
+
+    #[test]
+    fn take_checked_is_bounded_by_len() {
+        assert_eq!("xxxyyy".take_checked(3), Some("xxx"));
+        assert_eq!("xxxyyy".take_checked(6), Some("xxxyyy"));
+        assert_eq!("xxxyyy".take_checked(7), None);
+        assert_eq!((&b"xy"[..]).take_checked(0), Some(&b""[..]));
+        assert_eq!((&b"xy"[..]).take_checked(3), None);
+    }
 }
@@ -6,7 +6,11 @@ use std::{
 };
 
 use super::{string::SharedString, transform::TransformContent};
-use crate::{input::Input, parse::Parser, primitives::line::line};
+use crate::{
+    input::{describe_next_char, Addressable, Input, Slice},
+    parse::{NotFound, Parser},
+    primitives::line::line,
+};
 
 #[derive(Clone, Default)]
 pub struct Span {
@@ -28,12 +32,27 @@ impl Span {
         id: impl Into<Id>,
         content: impl Into<String>,
         starting_line: usize,
+    ) -> Self {
+        Self::new_continued_at(id, content, starting_line, 0)
+    }
+    /// As [`new_continued`][Span::new_continued], for content which also
+    /// begins part way through a line, after `starting_column` bytes.
+    pub fn new_continued_at(
+        id: impl Into<Id>,
+        content: impl Into<String>,
+        starting_line: usize,
+        starting_column: usize,
     ) -> Self {
         let id = id.into();
         let content = content.into();
         let end = content.len();
         Self {
-            source: Arc::new(Source::new_continued(id, content, starting_line)),
+            source: Arc::new(Source::new_continued(
+                id,
+                content,
+                starting_line,
+                starting_column,
+            )),
             range: 0..end,
         }
     }
@@ -44,32 +63,46 @@ impl Span {
         &self.source.id
     }
     pub fn position(&self) -> (usize, usize) {
-        match self.source.lines.binary_search(&self.range.start) {
-            Ok(idx) => {
-                let line = idx + 1 + self.source.starting_line;
-                let column = 1;
-                (line, column)
-            }
-            Err(idx) => {
-                let line = idx + self.source.starting_line;
-                let column = self.range.start - self.source.lines[idx - 1] + 1;
-                (line, column)
-            }
-        }
+        self.source.position_of(self.range.start)
+    }
+    /// The line & column just past the end of the span.
+    pub fn end_position(&self) -> (usize, usize) {
+        self.source.position_of(self.range.end)
     }
     pub fn location(&self) -> (&Id, (usize, usize)) {
         (self.id(), self.position())
     }
+    /// The byte offset of the span from the start of its source.
+    pub fn offset(&self) -> usize {
+        self.range.start
+    }
+    /// The byte range of the span within its source.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+    pub fn same_source(&self, other: &Span) -> bool {
+        Arc::ptr_eq(&self.source, &other.source)
+    }
+    /// The index of the line containing `offset`, counted from the start of
+    /// this source (ignoring `starting_line`).
+    pub(crate) fn line_index(&self, offset: usize) -> usize {
+        self.source.line_index(offset)
+    }
+    /// The line number of a line index, as displayed to a user.
+    pub(crate) fn line_number(&self, idx: usize) -> usize {
+        idx + 1 + self.source.starting_line
+    }
+    /// The byte offset & content of a line, without its line break.
+    pub(crate) fn line_text(&self, idx: usize) -> (usize, &str) {
+        self.source.line_text(idx)
+    }
     pub fn detatch(self) -> TransformedSpan {
         let (line, column) = self.position();
         TransformedSpan::new(self.id(), line, column, self.as_str().to_string())
     }
 }
 
-impl Input for Span {
-    fn as_str(&self) -> &str {
-        self.as_ref()
-    }
+impl Slice for Span {
     fn len(&self) -> usize {
         self.range.len()
     }
@@ -81,7 +114,7 @@ impl Input for Span {
     }
     fn slice(&self, subrange: Range<usize>) -> Self {
         let start = self.range.start + subrange.start;
-        assert!(self.range.contains(&start));
+        assert!(start <= self.range.end);
         let end = start + subrange.len();
         assert!(end <= self.range.end);
 
@@ -92,7 +125,7 @@ impl Input for Span {
     }
     fn split_at(&self, mid: usize) -> (Self, Self) {
         let idx = self.range.start + mid;
-        assert!(idx < self.range.end);
+        assert!(idx <= self.range.end);
 
         (
             Self {
@@ -107,12 +140,10 @@ impl Input for Span {
     }
     fn split_at_checked(&self, mid: usize) -> Option<(Self, Self)> {
         let abs_mid = self.range.start + mid;
-        if abs_mid >= self.range.end {
-            return None;
-        }
-        if self.as_str().split_at_checked(mid).is_none() {
+        if abs_mid > self.range.end {
             return None;
         }
+        self.as_str().split_at_checked(mid)?;
 
         Some((
             Self {
@@ -137,6 +168,22 @@ impl Input for Span {
             range: self.range.start..end,
         }
     }
+    fn describe_next(&self) -> Option<String> {
+        describe_next_char(self.as_str())
+    }
+}
+impl Addressable for Span {
+    fn offset(&self) -> usize {
+        Span::offset(self)
+    }
+    fn same_source(&self, other: &Self) -> bool {
+        Span::same_source(self, other)
+    }
+}
+impl Input for Span {
+    fn as_str(&self) -> &str {
+        self.as_ref()
+    }
 }
 impl AsRef<str> for Span {
     fn as_ref(&self) -> &str {
@@ -182,12 +229,13 @@ impl Debug for Span {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct Source {
     pub id: Id,
     pub content: String,
     pub lines: Box<[usize]>,
     pub starting_line: usize,
+    pub starting_column: usize,
 }
 impl Source {
     pub fn new(id: Id, content: String) -> Self {
@@ -197,29 +245,57 @@ impl Source {
             content,
             lines,
             starting_line: 0,
+            starting_column: 0,
         }
     }
-    pub fn new_continued(id: Id, content: String, starting_line: usize) -> Self {
+    pub fn new_continued(
+        id: Id,
+        content: String,
+        starting_line: usize,
+        starting_column: usize,
+    ) -> Self {
         let lines = line_indexes(&content);
         Self {
             id,
             content,
             lines,
             starting_line,
+            starting_column,
         }
     }
-}
-impl Default for Source {
-    fn default() -> Self {
-        Self {
-            id: Default::default(),
-            content: Default::default(),
-            lines: Default::default(),
-            starting_line: 0,
+    fn line_index(&self, offset: usize) -> usize {
+        match self.lines.binary_search(&offset) {
+            // The final entry is the end of the content, which only begins a
+            // line if the content ends in a line break.
+            Ok(idx) if idx > 0 && idx + 1 == self.lines.len() && !self.content.ends_with('\n') => {
+                idx - 1
+            }
+            Ok(idx) => idx,
+            Err(idx) => idx - 1,
         }
     }
+    fn position_of(&self, offset: usize) -> (usize, usize) {
+        let idx = self.line_index(offset);
+        let column = offset - self.lines[idx] + 1;
+        if idx == 0 {
+            (1 + self.starting_line, column + self.starting_column)
+        } else {
+            (idx + 1 + self.starting_line, column)
+        }
+    }
+    fn line_text(&self, idx: usize) -> (usize, &str) {
+        let start = self.lines[idx];
+        let end = self
+            .lines
+            .get(idx + 1)
+            .copied()
+            .unwrap_or(self.content.len());
+        let line = &self.content[start..end];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        (start, line)
+    }
 }
-
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Id {
     Static(&'static str),
@@ -229,6 +305,9 @@ impl Id {
     pub fn len(&self) -> usize {
         self.as_ref().len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 impl AsRef<str> for Id {
     fn as_ref(&self) -> &str {
@@ -263,7 +342,7 @@ fn line_indexes(s: &str) -> Box<[usize]> {
     let mut lines = vec![0];
     let mut remaining = s;
     while !remaining.is_empty() {
-        let (l, r) = line.parse(&remaining).unwrap();
+        let (l, r) = line::<_, NotFound>.parse(&remaining).unwrap();
         lines.push(lines.last().unwrap() + l.len());
         remaining = r;
     }
@@ -296,9 +375,9 @@ impl TransformedSpan {
         }
     }
 }
-impl Input for TransformedSpan {
-    fn as_str(&self) -> &str {
-        self.content.as_str()
+impl Slice for TransformedSpan {
+    fn len(&self) -> usize {
+        self.content.len()
     }
     fn empty(&self) -> Self {
         self.with_content(self.content.empty())
@@ -321,6 +400,14 @@ impl Input for TransformedSpan {
             .split_at_checked(mid)
             .map(|(a, b)| (self.with_content(a), self.with_content(b)))
     }
+    fn describe_next(&self) -> Option<String> {
+        self.content.describe_next()
+    }
+}
+impl Input for TransformedSpan {
+    fn as_str(&self) -> &str {
+        self.content.as_str()
+    }
 }
 impl AsRef<str> for TransformedSpan {
     fn as_ref(&self) -> &str {
@@ -393,7 +480,7 @@ mod test {
     #[test]
     fn start_of_line_locations() {
         let s = Span::anonymous("foo\nbar");
-        let (first_line, second_line) = line.parse(&s).unwrap();
+        let (first_line, second_line) = line::<_, NotFound>.parse(&s).unwrap();
 
         assert_eq!(first_line.position(), (1, 1));
         assert_eq!(second_line.position(), (2, 1));
@@ -402,11 +489,44 @@ mod test {
     #[test]
     fn column_2_locations() {
         let s = Span::anonymous("foo\nbar");
-        let (first_line, second_line) = line.parse(&s).unwrap();
+        let (first_line, second_line) = line::<_, NotFound>.parse(&s).unwrap();
         let first_line_col1 = first_line.skip(1);
         let second_line_col1 = second_line.skip(1);
 
         assert_eq!(first_line_col1.position(), (1, 2));
         assert_eq!(second_line_col1.position(), (2, 2));
     }
+
+    #[test]
+    fn end_of_input_locations() {
+        let s = Span::anonymous("foo\nbar");
+        assert_eq!(s.end_position(), (2, 4));
+
+        let s = Span::anonymous("foo\n");
+        assert_eq!(s.end_position(), (2, 1));
+
+        let s = Span::anonymous("");
+        assert_eq!(s.position(), (1, 1));
+        assert_eq!(s.end_position(), (1, 1));
+    }
+
+    #[test]
+    fn continued_locations() {
+        let s = Span::new_continued_at("", "o\nbar", 4, 2);
+        assert_eq!(s.position(), (5, 3));
+        assert_eq!(s.skip(2).position(), (6, 1));
+        assert_eq!(s.end_position(), (6, 4));
+    }
+
+    #[test]
+    fn split_at_end() {
+        let s = Span::anonymous("foo");
+        let (all, end) = s.split_at(3);
+        assert_eq!((all.as_str(), end.as_str()), ("foo", ""));
+        assert_eq!(end.position(), (1, 4));
+        assert!(s.split_at_checked(3).is_some());
+        assert!(s.split_at_checked(4).is_none());
+        assert_eq!(s.slice(3..3).offset(), 3);
+        assert_eq!(s.pop(&"foo").map(|(_, rest)| rest.is_empty()), Some(true));
+    }
 }
@@ -1,16 +1,15 @@
 use std::{fmt, ops::Range, sync::Arc};
 
-use super::{transform::TransformContent, Input};
+use super::{describe_next_char, transform::TransformContent, Addressable, Input, Slice};
 
 #[derive(Clone, PartialEq)]
 pub struct SharedString {
     content: Arc<String>,
     range: Range<usize>,
+    /// The offset of the content from the start of its stream.
+    start: usize,
 }
-impl Input for SharedString {
-    fn as_str(&self) -> &str {
-        self.as_ref()
-    }
+impl Slice for SharedString {
     fn len(&self) -> usize {
         self.range.len()
     }
@@ -19,13 +18,14 @@ impl Input for SharedString {
     }
     fn slice(&self, range: Range<usize>) -> Self {
         let start = self.range.start + range.start;
-        assert!(self.range.contains(&start));
+        assert!(start <= self.range.end);
         let end = start + range.len();
         assert!(end <= self.range.end);
 
         Self {
             content: self.content.clone(),
             range: start..end,
+            start: self.start,
         }
     }
     fn split_at(&self, mid: usize) -> (Self, Self)
@@ -33,16 +33,18 @@ impl Input for SharedString {
         Self: Sized,
     {
         let idx = self.range.start + mid;
-        assert!(idx < self.range.end);
+        assert!(idx <= self.range.end);
 
         (
             Self {
                 content: self.content.clone(),
                 range: self.range.start..idx,
+                start: self.start,
             },
             Self {
                 content: self.content.clone(),
                 range: idx..self.range.end,
+                start: self.start,
             },
         )
     }
@@ -51,15 +53,17 @@ impl Input for SharedString {
         Self: Sized,
     {
         let idx = self.range.start + mid;
-        if idx < self.range.end {
+        if idx <= self.range.end {
             Some((
                 Self {
                     content: self.content.clone(),
                     range: self.range.start..idx,
+                    start: self.start,
                 },
                 Self {
                     content: self.content.clone(),
                     range: idx..self.range.end,
+                    start: self.start,
                 },
             ))
         } else {
@@ -76,15 +80,38 @@ impl Input for SharedString {
         Self {
             content: self.content.clone(),
             range: self.range.start..end,
+            start: self.start,
         }
     }
+    fn describe_next(&self) -> Option<String> {
+        describe_next_char(self.as_str())
+    }
+}
+impl Addressable for SharedString {
+    fn offset(&self) -> usize {
+        self.start + self.range.start
+    }
+    fn same_source(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.content, &other.content)
+    }
+}
+impl Input for SharedString {
+    fn as_str(&self) -> &str {
+        self.as_ref()
+    }
 }
 impl SharedString {
     pub fn new(s: String) -> Self {
+        Self::new_continued(s, 0)
+    }
+    /// As [`new`][Self::new], for text which begins `offset` bytes into a
+    /// larger stream.
+    pub fn new_continued(s: String, offset: usize) -> Self {
         let range = 0..s.len();
         Self {
             content: Arc::new(s),
             range,
+            start: offset,
         }
     }
 }
@@ -116,6 +143,7 @@ impl Default for SharedString {
         Self {
             content: Arc::new(Default::default()),
             range: 0..0,
+            start: 0,
         }
     }
 }
@@ -134,3 +162,19 @@ impl TransformContent for SharedString {
         Self::new(s)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_at_end() {
+        let s = SharedString::new("foo".to_string());
+        let (all, end) = s.split_at(3);
+        assert_eq!((all.as_str(), end.as_str()), ("foo", ""));
+        assert!(s.split_at_checked(3).is_some());
+        assert!(s.split_at_checked(4).is_none());
+        assert_eq!(s.take_all().0.as_str(), "foo");
+        assert_eq!(s.slice(3..3).offset(), 3);
+    }
+}
@@ -0,0 +1,177 @@
+use std::{fmt, ops::Range};
+
+use super::{span::Span, Addressable, Slice};
+
+/// An item of [`TokenInput`], which can be matched by its kind with
+/// [`token`][crate::primitives::tokens::token].
+pub trait Token {
+    type Kind: PartialEq + fmt::Debug;
+    fn kind(&self) -> &Self::Kind;
+}
+
+/// A token, and the text it was lexed from.
+#[derive(Debug, Clone)]
+pub struct Spanned<K> {
+    pub kind: K,
+    pub span: Span,
+}
+impl<K> Spanned<K> {
+    pub fn new(kind: K, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+impl<K: PartialEq + fmt::Debug> Token for Spanned<K> {
+    type Kind = K;
+    fn kind(&self) -> &K {
+        &self.kind
+    }
+}
+
+/// A view into a slice of tokens, such as the output of a lexer. Unlike text,
+/// positions & lengths are counted in tokens.
+pub struct TokenInput<'a, T> {
+    tokens: &'a [T],
+    range: Range<usize>,
+}
+impl<'a, T> TokenInput<'a, T> {
+    pub fn new(tokens: &'a [T]) -> Self {
+        Self {
+            tokens,
+            range: 0..tokens.len(),
+        }
+    }
+    pub fn as_slice(&self) -> &'a [T] {
+        &self.tokens[self.range.clone()]
+    }
+    pub fn first(&self) -> Option<&'a T> {
+        self.as_slice().first()
+    }
+    /// The first token, and the input following it.
+    pub fn split_first(&self) -> Option<(&'a T, Self)> {
+        let first = self.first()?;
+        let remaining = Self {
+            tokens: self.tokens,
+            range: self.range.start + 1..self.range.end,
+        };
+        Some((first, remaining))
+    }
+}
+impl<K> TokenInput<'_, Spanned<K>> {
+    /// The span of the next token. At the end of input, this is an empty span
+    /// following the last token.
+    pub fn span(&self) -> Option<Span> {
+        match self.first() {
+            Some(token) => Some(token.span.clone()),
+            None => self.tokens[..self.range.start]
+                .last()
+                .map(|token| token.span.skip(token.span.len())),
+        }
+    }
+}
+impl<T> Clone for TokenInput<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            tokens: self.tokens,
+            range: self.range.clone(),
+        }
+    }
+}
+impl<T: PartialEq> PartialEq for TokenInput<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.range == other.range && self.as_slice() == other.as_slice()
+    }
+}
+impl<T: fmt::Debug> fmt::Debug for TokenInput<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenInput ")?;
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+impl<T: fmt::Debug> Slice for TokenInput<'_, T> {
+    fn len(&self) -> usize {
+        self.range.len()
+    }
+    fn empty(&self) -> Self {
+        Self {
+            tokens: self.tokens,
+            range: self.range.start..self.range.start,
+        }
+    }
+    fn slice(&self, range: Range<usize>) -> Self {
+        let start = self.range.start + range.start;
+        assert!(start <= self.range.end);
+        let end = start + range.len();
+        assert!(end <= self.range.end);
+
+        Self {
+            tokens: self.tokens,
+            range: start..end,
+        }
+    }
+    fn split_at(&self, mid: usize) -> (Self, Self)
+    where
+        Self: Sized,
+    {
+        self.split_at_checked(mid)
+            .expect("Split point is beyond the end of the tokens")
+    }
+    fn split_at_checked(&self, mid: usize) -> Option<(Self, Self)>
+    where
+        Self: Sized,
+    {
+        let idx = self.range.start + mid;
+        if idx <= self.range.end {
+            Some((
+                Self {
+                    tokens: self.tokens,
+                    range: self.range.start..idx,
+                },
+                Self {
+                    tokens: self.tokens,
+                    range: idx..self.range.end,
+                },
+            ))
+        } else {
+            None
+        }
+    }
+    fn describe_next(&self) -> Option<String> {
+        self.first().map(|token| format!("{:?}", token))
+    }
+}
+impl<T: fmt::Debug> Addressable for TokenInput<'_, T> {
+    fn offset(&self) -> usize {
+        self.range.start
+    }
+    fn same_source(&self, other: &Self) -> bool {
+        std::ptr::eq(self.tokens, other.tokens)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::input::Input;
+
+    #[test]
+    fn spans() {
+        let text = Span::anonymous("a = 1");
+        let tokens = vec![
+            Spanned::new('a', text.slice(0..1)),
+            Spanned::new('=', text.slice(2..3)),
+            Spanned::new('1', text.slice(4..5)),
+        ];
+        let input = TokenInput::new(&tokens);
+
+        let (head, tail) = input.split_at(1);
+        assert_eq!(head.as_slice().len(), 1);
+        assert_eq!(tail.first().map(Token::kind), Some(&'='));
+        assert_eq!(tail.span().unwrap().position(), (1, 3));
+
+        let end = input.skip(3);
+        assert!(end.is_empty());
+        assert_eq!(end.span().unwrap().position(), (1, 6));
+        assert_eq!(end.span().unwrap().as_str(), "");
+        assert!(end.same_source(&input));
+    }
+}
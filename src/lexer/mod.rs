@@ -0,0 +1,403 @@
+/*!
+# Lexer
+
+- A [`Lexer`] splits a [`Span`] into [`Spanned`] tokens, which can then be
+  parsed as a [`TokenInput`][crate::input::tokens::TokenInput].
+- Each [`Rule`] matches a tag, a class of characters, or anything a [`Parser`]
+  accepts. At each position the longest match wins; ties go to the rule with
+  the highest priority, then to the rule added first.
+- Trivia, such as whitespace & comments, is kept apart from the tokens.
+- Rules belong to modes. A rule may push a mode onto the lexer's stack or pop
+  back to the previous one, switching rule sets for string interpolation,
+  heredocs and the like.
+- Input which no rule matches is reported as a [`LexError`], and lexing carries
+  on after it.
+
+*/
+
+use std::fmt;
+
+use crate::{
+    diagnostics::Diagnostic,
+    input::{span::Span, tokens::Spanned, Input, Slice},
+    parse::{NotFound, Parser},
+};
+
+/// Gives the length of the match at the start of some input.
+type Matcher = Box<dyn Fn(&Span) -> Option<usize>>;
+
+enum Transition<M> {
+    Push(M),
+    Pop,
+}
+
+/// A way of matching a token of kind `K`, in a lexer with modes `M`.
+pub struct Rule<K, M = ()> {
+    kind: K,
+    matcher: Matcher,
+    priority: i32,
+    trivia: bool,
+    transition: Option<Transition<M>>,
+}
+impl<K, M> Rule<K, M> {
+    fn new(kind: K, matcher: Matcher) -> Self {
+        Self {
+            kind,
+            matcher,
+            priority: 0,
+            trivia: false,
+            transition: None,
+        }
+    }
+    pub fn tag(kind: K, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        Self::new(
+            kind,
+            Box::new(move |input| input.as_str().starts_with(&tag).then_some(tag.len())),
+        )
+    }
+    /// One or more characters matching `predicate`.
+    pub fn chars(kind: K, predicate: impl Fn(char) -> bool + 'static) -> Self {
+        Self::new(
+            kind,
+            Box::new(move |input| {
+                let (matched, _) = input.take_while(&predicate)?;
+                Some(matched.len())
+            }),
+        )
+    }
+    /// The input consumed by a parser. Its output & errors are discarded.
+    pub fn parser<O, F>(kind: K, p: impl Parser<Span, O, NotFound, F> + 'static) -> Self {
+        Self::new(
+            kind,
+            Box::new(move |input| {
+                let (_, remaining) = p.parse(input).ok()?;
+                Some(input.len() - remaining.len())
+            }),
+        )
+    }
+    /// Prefer this rule to others matching the same length. The default
+    /// priority is 0.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+    /// Keep matches out of the token stream.
+    pub fn trivia(mut self) -> Self {
+        self.trivia = true;
+        self
+    }
+    /// Switch to `mode` after a match, until a rule pops it.
+    pub fn push(mut self, mode: M) -> Self {
+        self.transition = Some(Transition::Push(mode));
+        self
+    }
+    /// Return to the previous mode after a match.
+    pub fn pop(mut self) -> Self {
+        self.transition = Some(Transition::Pop);
+        self
+    }
+}
+
+/// Splits text into tokens of kind `K`, with rules grouped into modes `M`.
+/// Lexing begins in `M::default()`.
+pub struct Lexer<K, M = ()> {
+    modes: Vec<(M, Vec<Rule<K, M>>)>,
+}
+impl<K: Clone, M: Clone + PartialEq + Default> Lexer<K, M> {
+    pub fn new() -> Self {
+        Self { modes: Vec::new() }
+    }
+    /// Add a rule to the initial mode.
+    pub fn with_rule(self, rule: Rule<K, M>) -> Self {
+        self.with_mode_rule(M::default(), rule)
+    }
+    pub fn with_mode_rule(mut self, mode: M, rule: Rule<K, M>) -> Self {
+        match self.modes.iter_mut().find(|(m, _)| m == &mode) {
+            Some((_, rules)) => rules.push(rule),
+            None => self.modes.push((mode, vec![rule])),
+        }
+        self
+    }
+    fn rules(&self, mode: &M) -> &[Rule<K, M>] {
+        self.modes
+            .iter()
+            .find(|(m, _)| m == mode)
+            .map_or(&[], |(_, rules)| rules)
+    }
+    /// The rule with the best match at the start of `input`, and its length.
+    fn best_match(&self, mode: &M, input: &Span) -> Option<(&Rule<K, M>, usize)> {
+        let mut best: Option<(&Rule<K, M>, usize)> = None;
+        for rule in self.rules(mode) {
+            let Some(len) = (rule.matcher)(input).filter(|&len| len > 0) else {
+                continue;
+            };
+            let better = match best {
+                None => true,
+                Some((best_rule, best_len)) => {
+                    len > best_len || (len == best_len && rule.priority > best_rule.priority)
+                }
+            };
+            if better {
+                best = Some((rule, len));
+            }
+        }
+        best
+    }
+
+    pub fn lex(&self, input: &Span) -> Lexed<K, M> {
+        let initial = M::default();
+        let mut lexed = Lexed {
+            tokens: Vec::new(),
+            trivia: Vec::new(),
+            errors: Vec::new(),
+        };
+        // Each mode pushed, with the token which pushed it.
+        let mut stack: Vec<(M, Span)> = Vec::new();
+        // The start of a run of input which no rule matched.
+        let mut unexpected: Option<Span> = None;
+
+        let mut remaining = input.clone();
+        while !remaining.is_empty() {
+            let mode = stack.last().map_or(&initial, |(mode, _)| mode);
+            let Some((rule, len)) = self.best_match(mode, &remaining) else {
+                unexpected.get_or_insert_with(|| remaining.clone());
+                let c = remaining
+                    .as_str()
+                    .chars()
+                    .next()
+                    .expect("Input isn't empty");
+                remaining = remaining.skip(c.len_utf8());
+                continue;
+            };
+            if let Some(start) = unexpected.take() {
+                lexed.errors.push(LexError::Unexpected {
+                    span: start.take(start.len() - remaining.len()),
+                    mode: mode.clone(),
+                });
+            }
+
+            let (span, rest) = remaining.split_at(len);
+            let token = Spanned::new(rule.kind.clone(), span.clone());
+            if rule.trivia {
+                lexed.trivia.push(token);
+            } else {
+                lexed.tokens.push(token);
+            }
+            match &rule.transition {
+                Some(Transition::Push(mode)) => stack.push((mode.clone(), span)),
+                Some(Transition::Pop) => match stack.pop() {
+                    Some(_) => {}
+                    None => lexed.errors.push(LexError::UnmatchedPop { span }),
+                },
+                None => {}
+            }
+            remaining = rest;
+        }
+
+        if let Some(start) = unexpected {
+            let mode = stack.last().map_or(&initial, |(mode, _)| mode).clone();
+            lexed
+                .errors
+                .push(LexError::Unexpected { span: start, mode });
+        }
+        for (mode, span) in stack {
+            lexed.errors.push(LexError::Unclosed { span, mode });
+        }
+        lexed
+    }
+}
+impl<K: Clone, M: Clone + PartialEq + Default> Default for Lexer<K, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The output of [`Lexer::lex`].
+#[derive(Clone, Debug)]
+pub struct Lexed<K, M = ()> {
+    pub tokens: Vec<Spanned<K>>,
+    pub trivia: Vec<Spanned<K>>,
+    pub errors: Vec<LexError<M>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum LexError<M = ()> {
+    /// Input which no rule of `mode` matched.
+    Unexpected { span: Span, mode: M },
+    /// A mode still on the stack at the end of input, with the token which
+    /// pushed it.
+    Unclosed { span: Span, mode: M },
+    /// A token which popped the initial mode.
+    UnmatchedPop { span: Span },
+}
+impl<M> LexError<M> {
+    pub fn span(&self) -> &Span {
+        match self {
+            LexError::Unexpected { span, .. }
+            | LexError::Unclosed { span, .. }
+            | LexError::UnmatchedPop { span } => span,
+        }
+    }
+}
+impl<M: fmt::Debug> fmt::Display for LexError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexError::Unexpected { span, .. } => write!(f, "unexpected {:?}", span.as_str()),
+            LexError::Unclosed { mode, .. } => write!(f, "unclosed {:?}", mode),
+            LexError::UnmatchedPop { span } => write!(f, "unmatched {:?}", span.as_str()),
+        }
+    }
+}
+impl<M: fmt::Debug> From<LexError<M>> for Diagnostic {
+    fn from(value: LexError<M>) -> Self {
+        Diagnostic::error(value.to_string(), value.span().clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::tokens::TokenInput,
+        parse::Sequence,
+        primitives::{line::line, tokens::token},
+    };
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Kind {
+        Let,
+        Ident,
+        Number,
+        Equals,
+        Plus,
+        Space,
+        Comment,
+        StringStart,
+        StringText,
+        StringEnd,
+        InterpolationStart,
+        InterpolationEnd,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    enum Mode {
+        #[default]
+        Code,
+        String,
+    }
+
+    fn lexer() -> Lexer<Kind, Mode> {
+        Lexer::new()
+            .with_rule(Rule::tag(Kind::Let, "let").with_priority(1))
+            .with_rule(Rule::chars(Kind::Ident, |c| c.is_ascii_alphabetic()))
+            .with_rule(Rule::chars(Kind::Number, |c| c.is_ascii_digit()))
+            .with_rule(Rule::tag(Kind::Equals, "="))
+            .with_rule(Rule::tag(Kind::Plus, "+"))
+            .with_rule(Rule::chars(Kind::Space, char::is_whitespace).trivia())
+            .with_rule(Rule::parser(Kind::Comment, ("#", line).and()).trivia())
+            .with_rule(Rule::tag(Kind::StringStart, "\"").push(Mode::String))
+            .with_rule(Rule::tag(Kind::InterpolationEnd, "}").pop())
+            .with_mode_rule(
+                Mode::String,
+                Rule::chars(Kind::StringText, |c| c != '"' && c != '$'),
+            )
+            .with_mode_rule(Mode::String, Rule::tag(Kind::StringEnd, "\"").pop())
+            .with_mode_rule(
+                Mode::String,
+                Rule::tag(Kind::InterpolationStart, "${").push(Mode::Code),
+            )
+    }
+
+    fn kinds(tokens: &[Spanned<Kind>]) -> Vec<(Kind, &str)> {
+        tokens
+            .iter()
+            .map(|t| (t.kind.clone(), t.span.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn longest_match() {
+        let lexed = lexer().lex(&Span::anonymous("let letter = 12 # note\n"));
+        assert_eq!(
+            kinds(&lexed.tokens),
+            vec![
+                (Kind::Let, "let"),
+                (Kind::Ident, "letter"),
+                (Kind::Equals, "="),
+                (Kind::Number, "12"),
+            ]
+        );
+        assert_eq!(
+            kinds(&lexed.trivia)[3..],
+            [(Kind::Space, " "), (Kind::Comment, "# note\n")]
+        );
+        assert!(lexed.errors.is_empty());
+
+        let input = TokenInput::new(&lexed.tokens);
+        let (_, remaining) = (token::<_, NotFound>(Kind::Let), token(Kind::Ident))
+            .and()
+            .parse(&input)
+            .unwrap();
+        assert_eq!(remaining.span().unwrap().as_str(), "=");
+    }
+
+    #[test]
+    fn modes() {
+        let lexed = lexer().lex(&Span::anonymous("\"a${b + \"c\"}d\""));
+        assert_eq!(
+            kinds(&lexed.tokens),
+            vec![
+                (Kind::StringStart, "\""),
+                (Kind::StringText, "a"),
+                (Kind::InterpolationStart, "${"),
+                (Kind::Ident, "b"),
+                (Kind::Plus, "+"),
+                (Kind::StringStart, "\""),
+                (Kind::StringText, "c"),
+                (Kind::StringEnd, "\""),
+                (Kind::InterpolationEnd, "}"),
+                (Kind::StringText, "d"),
+                (Kind::StringEnd, "\""),
+            ]
+        );
+        assert!(lexed.errors.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let lexed = lexer().lex(&Span::anonymous("a @@ b }\n\"x$"));
+        assert_eq!(
+            kinds(&lexed.tokens),
+            vec![
+                (Kind::Ident, "a"),
+                (Kind::Ident, "b"),
+                (Kind::InterpolationEnd, "}"),
+                (Kind::StringStart, "\""),
+                (Kind::StringText, "x"),
+            ]
+        );
+
+        let errors: Vec<_> = lexed
+            .errors
+            .iter()
+            .map(|e| (e.to_string(), e.span().position()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("unexpected \"@@\"".to_string(), (1, 3)),
+                ("unmatched \"}\"".to_string(), (1, 8)),
+                ("unexpected \"$\"".to_string(), (2, 3)),
+                ("unclosed String".to_string(), (2, 1)),
+            ]
+        );
+        assert!(matches!(
+            lexed.errors[2],
+            LexError::Unexpected {
+                mode: Mode::String,
+                ..
+            }
+        ));
+    }
+}
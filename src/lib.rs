@@ -1,8 +1,16 @@
 #![allow(unused)]
 #![deny(unused_must_use, clippy::dbg_macro)]
 
+// Lets `#[derive(Parse)]` refer to `::parlance` from within this crate.
+#[cfg(feature = "derive")]
+extern crate self as parlance;
+
 pub mod combinators;
+pub mod diagnostics;
+pub mod driver;
+pub mod formats;
 pub mod input;
+pub mod lexer;
 pub mod parse;
 pub mod prelude;
 pub mod primitives;
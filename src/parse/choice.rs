@@ -1,15 +1,28 @@
 use crate::util::tuples::implement_for_tuples;
 
-use super::{Never, NotFound, Parser, StreamingParser, StreamingError, ParserError};
+use super::{ExpectError, Never, NotFound, Parser, ParserError, StreamingError, StreamingParser};
 
 /// A tuple of parsers. Returns the first to succeed.
+///
+/// If every alternative fails, their errors are merged, keeping the one which
+/// got furthest into the input.
 pub trait Choice<Input, Output, Error = NotFound, Failure = Never> {
-    fn or(self) -> impl Parser<Input, Output, NotFound, Failure>;
+    fn or(self) -> impl Parser<Input, Output, Error, Failure>;
 }
 
 /// A tuple of streaming parsers. Returns the first to succeed.
+///
+/// If every alternative fails, their errors are merged, keeping the one which
+/// got furthest into the input.
 pub trait StreamingChoice<Input, Output, Error = NotFound, Failure = Never> {
-    fn or(self) -> impl StreamingParser<Input, Output, NotFound, Failure>;
+    fn or(self) -> impl StreamingParser<Input, Output, Error, Failure>;
+}
+
+fn merge<I, E: ExpectError<I>>(previous: Option<E>, current: E) -> E {
+    match previous {
+        Some(previous) => previous.merge(current),
+        None => current,
+    }
 }
 
 macro_rules! choice_impl (
@@ -18,25 +31,26 @@ macro_rules! choice_impl (
             impl<
                 Input,
                 Output,
-                Error,
+                Error: ExpectError<Input>,
                 Failure,
                 $([<P $idx>]: Parser<Input, Output, Error, Failure>, )*
                 [<P $last>]: Parser<Input, Output, Error, Failure>,
                 > Choice<Input, Output, Error, Failure> for ($([<P $idx>], )* [<P $last>])
             {
-                fn or(self) -> impl Parser<Input, Output, NotFound, Failure> {
+                fn or(self) -> impl Parser<Input, Output, Error, Failure> {
                     move |input: &Input| {
+                        let mut error = None;
                         $(
                             match self.$idx.parse(input) {
                                 Ok(x) => return Ok(x),
-                                Err(ParserError::Error(_)) => (),
+                                Err(ParserError::Error(e)) => error = Some(merge(error, e)),
                                 Err(ParserError::Failure(e)) => return Err(ParserError::Failure(e)),
                             }
                         )*
 
                         match self.$last.parse(input) {
                             Ok(x) => Ok(x),
-                            Err(ParserError::Error(_)) => Err(ParserError::Error(NotFound)),
+                            Err(ParserError::Error(e)) => Err(ParserError::Error(merge(error, e))),
                             Err(ParserError::Failure(e)) => Err(ParserError::Failure(e)),
                         }
                     }
@@ -46,18 +60,19 @@ macro_rules! choice_impl (
             impl<
                 Input,
                 Output,
-                Error,
+                Error: ExpectError<Input>,
                 Failure,
                 $([<P $idx>]: StreamingParser<Input, Output, Error, Failure>, )*
                 [<P $last>]: StreamingParser<Input, Output, Error, Failure>,
                 > StreamingChoice<Input, Output, Error, Failure> for ($([<P $idx>], )* [<P $last>])
             {
-                fn or(self) -> impl StreamingParser<Input, Output, NotFound, Failure> {
+                fn or(self) -> impl StreamingParser<Input, Output, Error, Failure> {
                     move |input: &Input| {
+                        let mut error = None;
                         $(
                             match self.$idx.parse_stream(input) {
                                 Ok(x) => return Ok(x),
-                                Err(StreamingError::Error(_)) => (),
+                                Err(StreamingError::Error(e)) => error = Some(merge(error, e)),
                                 Err(StreamingError::Incomplete(e)) => return Err(StreamingError::Incomplete(e)),
                                 Err(StreamingError::Failure(e)) => return Err(StreamingError::Failure(e)),
                             }
@@ -65,8 +80,8 @@ macro_rules! choice_impl (
 
                         match self.$last.parse_stream(input) {
                             Ok(x) => Ok(x),
-                            Err(StreamingError::Error(_)) => Err(StreamingError::Error(NotFound)),
-                            Err(StreamingError::Incomplete(e)) => return Err(StreamingError::Incomplete(e)),
+                            Err(StreamingError::Error(e)) => Err(StreamingError::Error(merge(error, e))),
+                            Err(StreamingError::Incomplete(e)) => Err(StreamingError::Incomplete(e)),
                             Err(StreamingError::Failure(e)) => Err(StreamingError::Failure(e)),
                         }
                     }
@@ -0,0 +1,137 @@
+use crate::input::Slice;
+
+use super::{Expected, Incomplete, Never, NotFound};
+
+/// An error which can record the [`context`][super::Parser::context] it was
+/// raised in.
+pub trait AddContext<I>: Sized {
+    /// Record that this error occurred within a parser labelled `label`, which
+    /// began at `input`.
+    fn add_context(self, input: &I, label: &'static str) -> Self;
+}
+
+impl<I> AddContext<I> for NotFound {
+    fn add_context(self, input: &I, label: &'static str) -> Self {
+        self
+    }
+}
+impl<I> AddContext<I> for Incomplete {
+    fn add_context(self, input: &I, label: &'static str) -> Self {
+        self
+    }
+}
+impl<I> AddContext<I> for Never {
+    fn add_context(self, input: &I, label: &'static str) -> Self {
+        unreachable!()
+    }
+}
+impl<I: Slice> AddContext<I> for Expected<I> {
+    fn add_context(mut self, input: &I, label: &'static str) -> Self {
+        self.push_frame(Frame {
+            label,
+            input: input.clone(),
+        });
+        self
+    }
+}
+
+/// A labelled parser which was running when an error occurred.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame<I> {
+    label: &'static str,
+    input: I,
+}
+impl<I> Frame<I> {
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+    /// The input at the point the labelled parser began.
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+    pub fn map_input<J>(self, f: impl FnOnce(I) -> J) -> Frame<J> {
+        Frame {
+            label: self.label,
+            input: f(self.input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        combinators::many::delimited,
+        input::span::Span,
+        parse::{Parser, ParserError, Sequence},
+        primitives::{numbers::digits, tag::tag, whitespace::whitespace},
+    };
+
+    fn array(
+        s: &Span,
+    ) -> crate::parse::ParserResult<Span, Vec<Span>, Expected<Span>, Expected<Span>> {
+        let element = digits.to_failure().context("array element");
+        (
+            "[".to_failure(),
+            delimited(element, ",".to_failure()),
+            "]".to_failure().or_fail(),
+        )
+            .and()
+            .map(|(_, elements, _)| elements)
+            .context("array")
+            .parse(s)
+    }
+
+    #[test]
+    fn frames_are_recorded_innermost_first() {
+        let input = Span::anonymous("[1,2;");
+        let parser = ("[", digits.context("array element"), "]")
+            .and()
+            .context("array")
+            .context("config file");
+        let Err(ParserError::Error(e)) = parser.parse(&input) else {
+            panic!("Parser should have failed")
+        };
+        let e: Expected<Span> = e;
+
+        let frames = e
+            .context()
+            .map(|frame| (frame.label(), frame.input().position()))
+            .collect::<Vec<_>>();
+        assert_eq!(e.input().position(), (1, 3));
+        assert_eq!(frames, vec![("array", (1, 1)), ("config file", (1, 1))]);
+    }
+
+    #[test]
+    fn failures_keep_their_frames() {
+        let input = Span::anonymous("x = [1,2;");
+        let parser = (
+            "x".to_failure(),
+            whitespace.to_failure(),
+            "=".to_failure(),
+            whitespace.to_failure(),
+            array,
+        )
+            .and()
+            .map(|_| ())
+            .context("assignment");
+        let Err(ParserError::Failure(e)) = parser.parse(&input) else {
+            panic!("Parser should have failed permanently")
+        };
+
+        let frames = e
+            .context()
+            .map(|frame| (frame.label(), frame.input().position()))
+            .collect::<Vec<_>>();
+        assert_eq!(e.input().position(), (1, 9));
+        assert_eq!(frames, vec![("array", (1, 5)), ("assignment", (1, 1))]);
+    }
+
+    #[test]
+    fn conversion_drops_frames() {
+        let parser = tag::<_, _, Expected<_>>("x")
+            .context("letter")
+            .to_error::<NotFound>();
+        assert_eq!(parser.parse(&"y"), Err(ParserError::Error(NotFound)));
+    }
+}
@@ -0,0 +1,94 @@
+/*!
+# Deriving parsers
+
+With the `derive` feature, `#[derive(Parse)]` implements [`Parser`] for a type
+which parses itself, in the same way as
+[`Number`][crate::primitives::numbers::Number]. See [`Parse`] for the supported
+attributes.
+
+*/
+
+pub use parlance_derive::Parse;
+
+#[cfg(test)]
+mod test {
+    use super::Parse;
+    use crate::{
+        input::{span::Span, Input},
+        parse::{Expectation, Expected, NotFound, Parser, ParserError},
+        primitives::{numbers::digits, whitespace::whitespace},
+    };
+
+    #[derive(Debug, PartialEq, Parse)]
+    enum Op {
+        #[parse(tag = "+")]
+        Add,
+        #[parse(tag = "-")]
+        Sub,
+    }
+
+    #[derive(Debug, Parse)]
+    #[parse(input = Span)]
+    struct Number(#[parse(with = digits)] Span);
+
+    #[derive(Debug, Parse)]
+    #[parse(input = Span, sep = whitespace)]
+    struct Binary {
+        left: Number,
+        op: Op,
+        right: Number,
+    }
+
+    #[derive(Debug, Parse)]
+    #[parse(input = Span)]
+    enum Statement {
+        #[parse(tag = "let ")]
+        Let {
+            #[parse(with = digits)]
+            name: Span,
+            #[parse(tag = "=")]
+            value: Number,
+        },
+        #[parse(with = Binary::parse)]
+        Expr(Binary),
+    }
+
+    #[test]
+    fn structs() {
+        let (binary, remaining) = Binary::parse::<NotFound>(&Span::anonymous("1 + 23;")).unwrap();
+        assert_eq!(binary.left.0.as_str(), "1");
+        assert_eq!(binary.op, Op::Add);
+        assert_eq!(binary.right.0.as_str(), "23");
+        assert_eq!(remaining.as_str(), ";");
+
+        assert!(matches!(
+            Binary::parse::<NotFound>(&Span::anonymous("1 * 2")),
+            Err(ParserError::Error(NotFound))
+        ));
+        assert_eq!(Op::parse::<_, NotFound>(&"-1"), Ok((Op::Sub, "1")));
+    }
+
+    #[test]
+    fn enums() {
+        let parser = |s: &Span| Statement::parse::<Expected<Span>>(s);
+        let Ok((Statement::Let { name, value }, _)) = parser.parse(&Span::anonymous("let 1=2"))
+        else {
+            panic!("Expected a let statement")
+        };
+        assert_eq!((name.as_str(), value.0.as_str()), ("1", "2"));
+
+        assert!(matches!(
+            parser.parse(&Span::anonymous("3 - 4")),
+            Ok((Statement::Expr(Binary { op: Op::Sub, .. }), _))
+        ));
+
+        let Err(ParserError::Error(e)) = parser.parse(&Span::anonymous("5 / 6")) else {
+            panic!("Expected an error")
+        };
+        assert_eq!(e.input().position(), (1, 3));
+        assert_eq!(
+            e.expectations().collect::<Vec<_>>(),
+            vec![&Expectation::tag("+"), &Expectation::tag("-")]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::{collections::BTreeSet, fmt};
+
+use crate::input::Slice;
+
+use super::{Frame, Never, NotFound};
+
+/// A recoverable error which can be built by primitive parsers and merged by
+/// [`Choice`][super::Choice].
+pub trait ExpectError<I>: Sized {
+    /// Build an error at `input`, where `expectation` could not be parsed.
+    fn expected(input: &I, expectation: Expectation) -> Self;
+    /// Combine the errors of two alternatives.
+    fn merge(self, other: Self) -> Self;
+}
+
+impl<I> ExpectError<I> for NotFound {
+    fn expected(input: &I, expectation: Expectation) -> Self {
+        NotFound
+    }
+    fn merge(self, other: Self) -> Self {
+        NotFound
+    }
+}
+
+/// Something a parser was looking for.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Expectation {
+    /// A literal string, as matched by [`tag`][crate::primitives::tag::tag].
+    Tag(String),
+    /// A literal sequence of bytes.
+    Bytes(Vec<u8>),
+    /// A description of a class of input, such as `"digit"`.
+    Label(&'static str),
+    /// A kind of token, as matched by
+    /// [`token`][crate::primitives::tokens::token].
+    Token(String),
+}
+impl Expectation {
+    pub fn tag(s: impl AsRef<str>) -> Self {
+        Self::Tag(s.as_ref().to_string())
+    }
+    pub fn bytes(b: impl AsRef<[u8]>) -> Self {
+        Self::Bytes(b.as_ref().to_vec())
+    }
+    pub fn label(s: &'static str) -> Self {
+        Self::Label(s)
+    }
+    pub fn token(kind: &impl fmt::Debug) -> Self {
+        Self::Token(format!("{:?}", kind))
+    }
+}
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expectation::Tag(s) => f.write_fmt(format_args!("`{}`", s.escape_debug())),
+            Expectation::Bytes(b) => f.write_fmt(format_args!("b\"{}\"", b.escape_ascii())),
+            Expectation::Label(s) => f.write_str(s),
+            Expectation::Token(s) => f.write_str(s),
+        }
+    }
+}
+
+/// A positioned error recording everything that was expected at the point
+/// parsing stopped.
+///
+/// When two alternatives fail, the error which got furthest into the input is
+/// kept. If both stopped at the same point, their expectations are combined,
+/// and the longer of their [`context`][Expected::context]s is kept.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Expected<I> {
+    input: I,
+    expected: BTreeSet<Expectation>,
+    context: Vec<Frame<I>>,
+}
+impl<I> Expected<I> {
+    pub fn new(input: I, expectation: Expectation) -> Self {
+        Self {
+            input,
+            expected: BTreeSet::from([expectation]),
+            context: Vec::new(),
+        }
+    }
+    /// The input remaining where parsing stopped.
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+    pub fn expectations(&self) -> impl Iterator<Item = &Expectation> {
+        self.expected.iter()
+    }
+    /// The labelled parsers this error occurred within, innermost first.
+    pub fn context(&self) -> impl Iterator<Item = &Frame<I>> {
+        self.context.iter()
+    }
+    pub fn into_input(self) -> I {
+        self.input
+    }
+    /// Convert the input this error (and its context) refers to, e.g. from a
+    /// text view back to the underlying bytes.
+    pub fn map_input<J>(self, f: impl Fn(I) -> J) -> Expected<J> {
+        Expected {
+            input: f(self.input),
+            expected: self.expected,
+            context: self
+                .context
+                .into_iter()
+                .map(|frame| frame.map_input(&f))
+                .collect(),
+        }
+    }
+    pub(crate) fn push_frame(&mut self, frame: Frame<I>) {
+        self.context.push(frame);
+    }
+}
+impl<I: Slice> ExpectError<I> for Expected<I> {
+    fn expected(input: &I, expectation: Expectation) -> Self {
+        Self::new(input.clone(), expectation)
+    }
+    fn merge(mut self, mut other: Self) -> Self {
+        // Inputs are suffixes of the same original, so less remaining input
+        // means the parser got further.
+        match self.input.len().cmp(&other.input.len()) {
+            std::cmp::Ordering::Less => self,
+            std::cmp::Ordering::Greater => other,
+            std::cmp::Ordering::Equal => {
+                self.expected.append(&mut other.expected);
+                if other.context.len() > self.context.len() {
+                    self.context = other.context;
+                }
+                self
+            }
+        }
+    }
+}
+impl<I: Slice> fmt::Debug for Expected<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Expected")
+            .field("input", &self.input)
+            .field("expected", &self.expected)
+            .field("context", &self.context)
+            .finish()
+    }
+}
+impl<I: Slice> fmt::Display for Expected<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected ")?;
+        let count = self.expected.len();
+        for (idx, expectation) in self.expected.iter().enumerate() {
+            if idx > 0 && idx + 1 == count {
+                f.write_str(" or ")?;
+            } else if idx > 0 {
+                f.write_str(", ")?;
+            }
+            fmt::Display::fmt(expectation, f)?;
+        }
+        match self.input.describe_next() {
+            Some(next) => f.write_fmt(format_args!(", found {}", next)),
+            None => f.write_str(", found end of input"),
+        }
+    }
+}
+impl<I: Slice> std::error::Error for Expected<I> {}
+
+impl<I> From<Expected<I>> for NotFound {
+    fn from(value: Expected<I>) -> Self {
+        NotFound
+    }
+}
+impl<I> From<Never> for Expected<I> {
+    fn from(value: Never) -> Self {
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::{span::Span, Input},
+        parse::{Choice, Parser, ParserError, Sequence},
+        primitives::{numbers::digits, tag::tag},
+    };
+
+    #[test]
+    fn furthest_error_wins() {
+        let near = Expected::new("abc", Expectation::tag("x"));
+        let far = Expected::new("c", Expectation::label("digit"));
+        assert_eq!(near.clone().merge(far.clone()), far);
+        assert_eq!(far.clone().merge(near), far);
+    }
+
+    #[test]
+    fn equal_errors_are_combined() {
+        let parser = ("true", "false", digits).or();
+        let Err(ParserError::Error(e)) = parser.parse(&"nil") else {
+            panic!("Parser should have failed")
+        };
+        let e: Expected<&str> = e;
+        assert_eq!(e.input(), &"nil");
+        assert_eq!(
+            e.to_string(),
+            "expected `false`, `true` or digit, found 'n'"
+        );
+    }
+
+    #[test]
+    fn equal_errors_keep_context() {
+        let parser = ("none", digits.context("count")).or();
+        let Err(ParserError::Error(e)) = parser.parse(&"x") else {
+            panic!("Parser should have failed")
+        };
+        let e: Expected<&str> = e;
+        assert_eq!(e.to_string(), "expected `none` or digit, found 'x'");
+        let labels = e.context().map(Frame::label).collect::<Vec<_>>();
+        assert_eq!(labels, ["count"]);
+    }
+
+    #[test]
+    fn choice_reports_furthest_alternative() {
+        let parser = (("[", digits, "]").and().map(|_| ()), "[]".map(|_| ())).or();
+        let input = Span::anonymous("\n[1;");
+        let input = input.skip(1);
+        let Err(ParserError::Error(e)) = parser.parse(&input) else {
+            panic!("Parser should have failed")
+        };
+        let e: Expected<Span> = e;
+        assert_eq!(e.input().as_str(), ";");
+        assert_eq!(e.input().position(), (2, 3));
+        assert_eq!(e.to_string(), "expected `]`, found ';'");
+    }
+
+    #[test]
+    fn end_of_input() {
+        let Err(ParserError::Error(e)) = tag::<_, _, Expected<_>>("foo").parse(&"fo") else {
+            panic!("Parser should have failed")
+        };
+        assert_eq!(e.to_string(), "expected `foo`, found 'f'");
+
+        let Err(ParserError::Error(e)) = tag::<_, _, Expected<_>>("foo").parse(&"") else {
+            panic!("Parser should have failed")
+        };
+        assert_eq!(e.to_string(), "expected `foo`, found end of input");
+    }
+}
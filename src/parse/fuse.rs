@@ -1,4 +1,4 @@
-use crate::{input::Input, util::tuples::implement_for_tuples};
+use crate::{input::Slice, util::tuples::implement_for_tuples};
 
 use super::{Parser, Sequence};
 
@@ -7,7 +7,7 @@ pub trait FuseSequence<Input, Output, Error, Failure> {
     fn fuse(self) -> impl Parser<Input, Input, Error, Failure>;
 }
 
-impl<I: Input, O: Fusable, E, F, T: Sequence<I, O, E, F>> FuseSequence<I, O, E, F> for T {
+impl<I: Slice, O: Fusable, E, F, T: Sequence<I, O, E, F>> FuseSequence<I, O, E, F> for T {
     fn output_len(self) -> impl Parser<I, usize, E, F> {
         self.and().map(|o| o.len())
     }
@@ -20,18 +20,19 @@ impl<I: Input, O: Fusable, E, F, T: Sequence<I, O, E, F>> FuseSequence<I, O, E,
     }
 }
 
+#[allow(clippy::len_without_is_empty)]
 pub trait Fusable {
     fn len(&self) -> usize;
 }
 
-impl<I: crate::input::Input> Fusable for I {
+impl<I: Slice> Fusable for I {
     fn len(&self) -> usize {
-        crate::input::Input::len(self)
+        Slice::len(self)
     }
 }
-impl<I: crate::input::Input> Fusable for Option<I> {
+impl<I: Slice> Fusable for Option<I> {
     fn len(&self) -> usize {
-        self.as_ref().map(crate::input::Input::len).unwrap_or(0)
+        self.as_ref().map(Slice::len).unwrap_or(0)
     }
 }
 
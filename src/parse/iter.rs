@@ -0,0 +1,117 @@
+use std::marker::PhantomData;
+
+use crate::input::Slice;
+
+use super::{Parser, ParserError};
+
+/// An iterator applying a parser repeatedly, created by [`Parser::iter`].
+///
+/// Iteration stops at the end of input, at the first error or failure, or
+/// when the parser succeeds without consuming any input.
+pub struct ParserIter<'p, P, I, O, E, F> {
+    parser: &'p P,
+    remaining: I,
+    stopped: Option<ParserError<E, F>>,
+    done: bool,
+    phantom: PhantomData<fn() -> O>,
+}
+impl<'p, P, I, O, E, F> ParserIter<'p, P, I, O, E, F> {
+    pub(super) fn new(parser: &'p P, input: I) -> Self {
+        Self {
+            parser,
+            remaining: input,
+            stopped: None,
+            done: false,
+            phantom: PhantomData,
+        }
+    }
+    /// The input following the last output.
+    pub fn remaining(&self) -> &I {
+        &self.remaining
+    }
+    /// The error or failure which ended iteration, if any.
+    pub fn stopped(&self) -> Option<&ParserError<E, F>> {
+        self.stopped.as_ref()
+    }
+    /// The remaining input, and the error or failure which ended iteration.
+    pub fn finish(self) -> (I, Option<ParserError<E, F>>) {
+        (self.remaining, self.stopped)
+    }
+}
+impl<P: Parser<I, O, E, F>, I: Slice, O, E, F> Iterator for ParserIter<'_, P, I, O, E, F> {
+    type Item = O;
+
+    fn next(&mut self) -> Option<O> {
+        if self.done || self.remaining.is_empty() {
+            self.done = true;
+            return None;
+        }
+        match self.parser.parse(&self.remaining) {
+            // No progress was made, so we would loop forever.
+            Ok((_, r)) if r.len() == self.remaining.len() => {
+                self.done = true;
+                None
+            }
+            Ok((o, r)) => {
+                self.remaining = r;
+                Some(o)
+            }
+            Err(e) => {
+                self.done = true;
+                self.stopped = Some(e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::{span::Span, Input},
+        parse::{Expected, NotFound, Sequence},
+        primitives::{line::line, numbers::digits},
+    };
+
+    #[test]
+    fn lazy_lines() {
+        let input = "ok\nERROR a\nok\nERROR b\n";
+        let errors = line::<_, NotFound>
+            .iter(&input)
+            .filter(|l: &&str| l.starts_with("ERROR"))
+            .count();
+        assert_eq!(errors, 2);
+    }
+
+    #[test]
+    fn exposes_remainder_and_error() {
+        let parser = (digits, ",").and().map(|(n, _): (Span, _)| n);
+        let input = Span::anonymous("1,2,x,3,");
+        let mut iter = parser.iter(&input);
+
+        let numbers = iter
+            .by_ref()
+            .map(|n| n.as_str().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec!["1", "2"]);
+        assert_eq!(iter.remaining().as_str(), "x,3,");
+
+        let (remaining, stopped) = iter.finish();
+        let Some(ParserError::Error(e)) = stopped else {
+            panic!("Iteration should have stopped on an error")
+        };
+        let e: Expected<Span> = e;
+        assert_eq!(e.input().position(), (1, 5));
+        assert_eq!(remaining.offset(), 4);
+    }
+
+    #[test]
+    fn clean_end() {
+        let parser = (digits::<_, NotFound>, ";").and();
+        let mut iter = parser.iter(&"1;2;");
+        assert_eq!(iter.by_ref().count(), 2);
+        assert!(iter.stopped().is_none());
+        assert_eq!(iter.remaining(), &"");
+    }
+}
@@ -4,15 +4,25 @@ use crate::primitives::whitespace::whitespace;
 
 mod choice;
 mod compose;
+mod context;
+#[cfg(feature = "derive")]
+mod derive;
 mod err;
+mod expected;
 mod fuse;
+mod iter;
 mod sequence;
 mod streaming;
 
 pub use choice::{Choice, StreamingChoice};
 pub use compose::{Compose, StreamingCompose};
+pub use context::{AddContext, Frame};
+#[cfg(feature = "derive")]
+pub use derive::Parse;
 pub use err::{Incomplete, Never, NotFound};
+pub use expected::{ExpectError, Expectation, Expected};
 pub use fuse::{Fusable, FuseSequence};
+pub use iter::ParserIter;
 pub use sequence::{SeparatedSequence, Sequence, StreamingSequence};
 pub use streaming::{
     ErrorWasIncomplete, IntoStreamingParser, IntoStreamingResult, StreamingError, StreamingOk,
@@ -178,6 +188,21 @@ pub trait Parser<Input, Output, Error = NotFound, Failure = Never> {
             })
         }
     }
+    /// Label errors & failures from this parser, recording where it began.
+    /// Nested contexts build a stack of frames, innermost first.
+    fn context(self, label: &'static str) -> impl Parser<Input, Output, Error, Failure>
+    where
+        Self: Sized,
+        Error: AddContext<Input>,
+        Failure: AddContext<Input>,
+    {
+        move |input: &Input| {
+            self.parse(input).map_err(|e| match e {
+                ParserError::Error(e) => ParserError::Error(e.add_context(input, label)),
+                ParserError::Failure(e) => ParserError::Failure(e.add_context(input, label)),
+            })
+        }
+    }
     /// Returns None on a recoverable error.
     fn opt(self) -> impl Parser<Input, Option<Output>, Error, Failure>
     where
@@ -202,9 +227,10 @@ pub trait Parser<Input, Output, Error = NotFound, Failure = Never> {
     fn or<Other: Parser<Input, Output, Error, Failure>>(
         self,
         other: Other,
-    ) -> impl Parser<Input, Output, NotFound, Failure>
+    ) -> impl Parser<Input, Output, Error, Failure>
     where
         Self: Sized,
+        Error: ExpectError<Input>,
     {
         (self, other).or()
     }
@@ -217,12 +243,20 @@ pub trait Parser<Input, Output, Error = NotFound, Failure = Never> {
     {
         Compose::map((self, other))
     }
+    /// Apply this parser repeatedly, lazily yielding each output.
+    fn iter(&self, input: &Input) -> ParserIter<'_, Self, Input, Output, Error, Failure>
+    where
+        Self: Sized,
+        Input: Clone,
+    {
+        ParserIter::new(self, input.clone())
+    }
 }
 
 pub type ParserResult<Input, Output, Error = NotFound, Failure = Never> =
     Result<(Output, Input), ParserError<Error, Failure>>;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ParserError<Error, Failure> {
     Error(Error),
     Failure(Failure),
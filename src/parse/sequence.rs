@@ -152,11 +152,11 @@ macro_rules! sequence_impl (
                             unreachable!()
                         };
                         $(
-                            let StreamingOk::Complete([<output_ $mid>], remaining) = self.$mid.parse_stream(input).no_partial()? else {
+                            let StreamingOk::Complete([<output_ $mid>], remaining) = self.$mid.parse_stream(&remaining).no_partial()? else {
                                 unreachable!()
                             };
                         )*
-                        let StreamingOk::Complete([<output_ $last>], remaining) = self.$last.parse_stream(input).no_partial()? else {
+                        let StreamingOk::Complete([<output_ $last>], remaining) = self.$last.parse_stream(&remaining).no_partial()? else {
                             unreachable!()
                         };
 
@@ -177,3 +177,23 @@ macro_rules! sequence_impl (
 );
 
 implement_for_tuples!(sequence_impl);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::primitives::binary::{be_u16_stream, be_u32_stream};
+
+    #[test]
+    fn streaming_threads_remaining_input() {
+        let input: &[u8] = b"\x00\x01\x00\x00\x00\x02\x00\x03rest";
+        let parser = (be_u16_stream::<_, NotFound>, be_u32_stream, be_u16_stream).and();
+        assert_eq!(
+            parser.parse_stream(&input),
+            Ok(StreamingOk::Complete((1, 2, 3), &b"rest"[..]))
+        );
+        assert_eq!(
+            parser.parse_stream(&&input[..7]),
+            Err(StreamingError::Incomplete(Incomplete))
+        );
+    }
+}
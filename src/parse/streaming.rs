@@ -1,8 +1,8 @@
 use std::fmt;
 
 use super::{
-    Fusable, Incomplete, Never, NotFound, Parser, ParserError, StreamingChoice, StreamingCompose,
-    StreamingSequence,
+    AddContext, ExpectError, Fusable, Incomplete, Never, NotFound, Parser, ParserError,
+    StreamingChoice, StreamingCompose, StreamingSequence,
 };
 
 pub trait StreamingParser<Input, Output, Error = NotFound, Failure = Never> {
@@ -209,6 +209,23 @@ pub trait StreamingParser<Input, Output, Error = NotFound, Failure = Never> {
             })
         }
     }
+    /// Label errors & failures from this parser, recording where it began.
+    /// Nested contexts build a stack of frames, innermost first.
+    /// NB: Failures inside of an `Incomplete` variant are untouched.
+    fn context(self, label: &'static str) -> impl StreamingParser<Input, Output, Error, Failure>
+    where
+        Self: Sized,
+        Error: AddContext<Input>,
+        Failure: AddContext<Input>,
+    {
+        move |input: &Input| {
+            self.parse_stream(input).map_err(|e| match e {
+                StreamingError::Error(e) => StreamingError::Error(e.add_context(input, label)),
+                StreamingError::Failure(e) => StreamingError::Failure(e.add_context(input, label)),
+                StreamingError::Incomplete(e) => StreamingError::Incomplete(e),
+            })
+        }
+    }
     /// Returns None on a recoverable error.
     fn opt(self) -> impl StreamingParser<Input, Option<Output>, Error, Failure>
     where
@@ -238,9 +255,10 @@ pub trait StreamingParser<Input, Output, Error = NotFound, Failure = Never> {
     fn or<Other: StreamingParser<Input, Output, Error, Failure>>(
         self,
         other: Other,
-    ) -> impl StreamingParser<Input, Output, NotFound, Failure>
+    ) -> impl StreamingParser<Input, Output, Error, Failure>
     where
         Self: Sized,
+        Error: ExpectError<Input>,
         Failure: From<Incomplete>,
     {
         (self, other).or()
@@ -260,22 +278,19 @@ pub trait StreamingParser<Input, Output, Error = NotFound, Failure = Never> {
 pub type StreamingResult<Input, Output, Error = NotFound, Failure = Never> =
     Result<StreamingOk<Input, Output>, StreamingError<Error, Failure>>;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum StreamingOk<Input, Output> {
     Complete(Output, Input),
     Partial(Output, Input),
 }
 impl<I, O> StreamingOk<I, O> {
     pub fn is_partial(&self) -> bool {
-        match self {
-            StreamingOk::Partial(_, _) => true,
-            _ => false,
-        }
+        matches!(self, StreamingOk::Partial(_, _))
     }
     pub fn peek(&self) -> (&O, &I) {
         match self {
-            StreamingOk::Complete(o, r) => (&o, &r),
-            StreamingOk::Partial(o, r) => (&o, &r),
+            StreamingOk::Complete(o, r) => (o, r),
+            StreamingOk::Partial(o, r) => (o, r),
         }
     }
 }
@@ -285,7 +300,7 @@ impl<I, O: Fusable> Fusable for StreamingOk<I, O> {
     }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum StreamingError<Error, Failure> {
     Incomplete(Failure),
     Error(Error),
@@ -350,6 +365,7 @@ impl<I, O, E, F, T: Parser<I, O, E, F>> IntoStreamingParser<I, O, E, F> for T {
     }
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait IntoStreamingResult<Input, Output, Error, Failure> {
     fn as_streaming(self) -> StreamingResult<Input, Output, Error, Failure>;
 }
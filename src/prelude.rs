@@ -1,12 +1,14 @@
 // Concrete types & aliases
 pub use crate::parse::{
-    Incomplete, Never, NotFound, ParserError, ParserResult, StreamingError, StreamingOk,
-    StreamingResult,
+    Expectation, Expected, Frame, Incomplete, Never, NotFound, ParserError, ParserResult,
+    StreamingError, StreamingOk, StreamingResult,
 };
 
 // Implementable traits
-pub use crate::input::Input;
-pub use crate::parse::{Parser, StreamingParser};
+pub use crate::input::{bytes::ByteInput, tokens::Token, Addressable, Input, Slice};
+#[cfg(feature = "derive")]
+pub use crate::parse::Parse;
+pub use crate::parse::{AddContext, ExpectError, Parser, StreamingParser};
 
 // Automatic traits - these occupy common names, so bind anonymously to
 // avoid polluting namespace.
@@ -16,7 +18,7 @@ pub use crate::{
         StreamingCompose as _,
     },
     util::conditional_transforms::{
-        EitherCompleteIf as _, MaybeCompleteIf as _, NoPartial as _, OrFail as _,
-        OrIncomplete as _, OrNotFound as _, StreamingOrNotFound as _,
+        EitherCompleteIf as _, MaybeCompleteIf as _, NoPartial as _, OrExpected as _, OrFail as _,
+        OrIncomplete as _, OrNotFound as _, StreamingOrExpected as _, StreamingOrNotFound as _,
     },
 };
@@ -0,0 +1,326 @@
+/*!
+# Binary primitives
+
+- Fixed-width numbers in big-endian (`be_u32`) and little-endian (`le_u32`)
+  byte order, for `u8`-`u128`, `i8`-`i128`, `f32` & `f64`.
+- [`uleb128`] & [`zigzag_leb128`] variable-length integers.
+- [`length_prefixed`] fields.
+- Each has a `_stream` form which returns [`StreamingError::Incomplete`] when
+  the input ends part way through.
+
+*/
+
+use crate::{
+    input::bytes::ByteInput,
+    parse::{
+        ExpectError, Expectation, Incomplete, Never, Parser, ParserError, ParserResult,
+        StreamingError, StreamingOk, StreamingParser, StreamingResult,
+    },
+    util::conditional_transforms::OrExpected,
+};
+
+/// Split the first `N` bytes off the input.
+fn array<I: ByteInput, const N: usize>(s: &I) -> Option<([u8; N], I)> {
+    let (head, remaining) = s.split_at_checked(N)?;
+    Some((head.as_bytes().try_into().ok()?, remaining))
+}
+
+macro_rules! fixed_width {
+    ($($ty:ident,)*) => {
+        paste::paste! {
+            $(
+                pub fn [<be_ $ty>]<I: ByteInput, E: ExpectError<I>>(s: &I) -> ParserResult<I, $ty, E> {
+                    array(s)
+                        .map(|(b, remaining)| ($ty::from_be_bytes(b), remaining))
+                        .ok_or_expected(s, Expectation::label(stringify!($ty)))
+                }
+
+                pub fn [<be_ $ty _stream>]<I: ByteInput, E: ExpectError<I>>(
+                    s: &I,
+                ) -> StreamingResult<I, $ty, E, Incomplete> {
+                    array(s)
+                        .map(|(b, remaining)| StreamingOk::Complete($ty::from_be_bytes(b), remaining))
+                        .ok_or(StreamingError::Incomplete(Incomplete))
+                }
+
+                pub fn [<le_ $ty>]<I: ByteInput, E: ExpectError<I>>(s: &I) -> ParserResult<I, $ty, E> {
+                    array(s)
+                        .map(|(b, remaining)| ($ty::from_le_bytes(b), remaining))
+                        .ok_or_expected(s, Expectation::label(stringify!($ty)))
+                }
+
+                pub fn [<le_ $ty _stream>]<I: ByteInput, E: ExpectError<I>>(
+                    s: &I,
+                ) -> StreamingResult<I, $ty, E, Incomplete> {
+                    array(s)
+                        .map(|(b, remaining)| StreamingOk::Complete($ty::from_le_bytes(b), remaining))
+                        .ok_or(StreamingError::Incomplete(Incomplete))
+                }
+            )*
+        }
+    };
+}
+fixed_width!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64,);
+
+/// A failure while reading a variable-length integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VarintFailure {
+    /// The input ended before the final byte. Only returned by the streaming
+    /// forms.
+    Incomplete,
+    /// The value does not fit in 64 bits.
+    Overflow,
+}
+impl From<Never> for VarintFailure {
+    fn from(value: Never) -> Self {
+        unreachable!()
+    }
+}
+impl From<Incomplete> for VarintFailure {
+    fn from(value: Incomplete) -> Self {
+        Self::Incomplete
+    }
+}
+
+/// Decode an unsigned LEB128 value. Returns `Ok(None)` if the input ends
+/// before the final byte.
+fn decode_leb128(b: &[u8]) -> Result<Option<(u64, usize)>, VarintFailure> {
+    let mut value = 0u64;
+    for (idx, byte) in b.iter().enumerate() {
+        let bits = (byte & 0x7f) as u64;
+        let shift = 7 * idx as u32;
+        if shift >= 64 || (shift > 0 && bits >> (64 - shift) != 0) {
+            return Err(VarintFailure::Overflow);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(Some((value, idx + 1)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn zigzag(n: u64) -> i64 {
+    (n >> 1) as i64 ^ -((n & 1) as i64)
+}
+
+/// An unsigned LEB128 variable-length integer.
+pub fn uleb128<I: ByteInput, E: ExpectError<I>>(s: &I) -> ParserResult<I, u64, E, VarintFailure> {
+    match decode_leb128(s.as_bytes()) {
+        Ok(Some((n, len))) => Ok((n, s.skip(len))),
+        Ok(None) => Err(ParserError::Error(E::expected(
+            s,
+            Expectation::label("varint"),
+        ))),
+        Err(e) => Err(ParserError::Failure(e)),
+    }
+}
+
+pub fn uleb128_stream<I: ByteInput, E: ExpectError<I>>(
+    s: &I,
+) -> StreamingResult<I, u64, E, VarintFailure> {
+    match decode_leb128(s.as_bytes()) {
+        Ok(Some((n, len))) => Ok(StreamingOk::Complete(n, s.skip(len))),
+        Ok(None) => Err(StreamingError::Incomplete(VarintFailure::Incomplete)),
+        Err(e) => Err(StreamingError::Failure(e)),
+    }
+}
+
+/// A signed LEB128 integer in zigzag encoding, as used by protobuf's `sint64`.
+pub fn zigzag_leb128<I: ByteInput, E: ExpectError<I>>(
+    s: &I,
+) -> ParserResult<I, i64, E, VarintFailure> {
+    uleb128.map(zigzag).parse(s)
+}
+
+pub fn zigzag_leb128_stream<I: ByteInput, E: ExpectError<I>>(
+    s: &I,
+) -> StreamingResult<I, i64, E, VarintFailure> {
+    uleb128_stream.map(zigzag).parse_stream(s)
+}
+
+/// Read a length with `len`, then run `body` over exactly that many bytes.
+/// `body` must consume the entire field.
+pub fn length_prefixed<I: ByteInput, L: TryInto<usize>, O, E: ExpectError<I>, F>(
+    len: impl Parser<I, L, E, F>,
+    body: impl Parser<I, O, E, F>,
+) -> impl Parser<I, O, E, F> {
+    move |input: &I| {
+        let (n, remaining) = len.parse(input)?;
+        let Some((field, remaining)) = n
+            .try_into()
+            .ok()
+            .and_then(|n| remaining.split_at_checked(n))
+        else {
+            return Err(ParserError::Error(E::expected(
+                &remaining,
+                Expectation::label("length-prefixed field"),
+            )));
+        };
+
+        let (output, rest) = body.parse(&field)?;
+        if !rest.is_empty() {
+            return Err(ParserError::Error(E::expected(
+                &rest,
+                Expectation::label("end of field"),
+            )));
+        }
+        Ok((output, remaining))
+    }
+}
+
+/// As [`length_prefixed`], returning [`StreamingError::Incomplete`] if the
+/// input ends within the length or the field. A length over `max_len` is an
+/// error at the start of the input, rather than waiting for a field which
+/// may never fit in memory.
+pub fn length_prefixed_stream<
+    I: ByteInput,
+    L: TryInto<usize>,
+    O,
+    E: ExpectError<I>,
+    F: From<Incomplete>,
+>(
+    len: impl StreamingParser<I, L, E, F>,
+    body: impl Parser<I, O, E, F>,
+    max_len: usize,
+) -> impl StreamingParser<I, O, E, F> {
+    move |input: &I| {
+        let (n, remaining) = match len.parse_stream(input)? {
+            StreamingOk::Complete(n, remaining) | StreamingOk::Partial(n, remaining) => {
+                (n, remaining)
+            }
+        };
+        let Some(n) = n.try_into().ok().filter(|n| *n <= max_len) else {
+            return Err(StreamingError::Error(E::expected(
+                input,
+                Expectation::label("field within the length limit"),
+            )));
+        };
+        let Some((field, remaining)) = remaining.split_at_checked(n) else {
+            return Err(StreamingError::Incomplete(Incomplete.into()));
+        };
+
+        let (output, rest) = body.parse(&field)?;
+        if !rest.is_empty() {
+            return Err(StreamingError::Error(E::expected(
+                &rest,
+                Expectation::label("end of field"),
+            )));
+        }
+        Ok(StreamingOk::Complete(output, remaining))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parse::{NotFound, Sequence},
+        primitives::{
+            bytes::{take, text},
+            numbers::digits,
+        },
+    };
+
+    type Bytes<'a> = &'a [u8];
+
+    #[test]
+    fn fixed_width() {
+        let input: Bytes = &[0x01, 0x02, 0x03, 0x04, 0xff];
+        assert_eq!(be_u16::<_, NotFound>(&input), Ok((0x0102, &input[2..])));
+        assert_eq!(le_u32::<_, NotFound>(&input), Ok((0x04030201, &input[4..])));
+        assert_eq!(be_i8::<_, NotFound>(&&input[4..]), Ok((-1, &input[5..])));
+        assert_eq!(
+            be_f32::<_, NotFound>(&&1.5f32.to_be_bytes()[..]),
+            Ok((1.5, &[][..]))
+        );
+        assert!(be_u64::<_, NotFound>(&input).is_err());
+
+        assert_eq!(
+            be_u64_stream::<_, NotFound>(&input),
+            Err(StreamingError::Incomplete(Incomplete))
+        );
+        assert_eq!(
+            le_u16_stream::<_, NotFound>(&input),
+            Ok(StreamingOk::Complete(0x0201, &input[2..]))
+        );
+    }
+
+    #[test]
+    fn varints() {
+        let input: Bytes = &[0xe5, 0x8e, 0x26, 0x00];
+        assert_eq!(uleb128::<_, NotFound>(&input), Ok((624485, &input[3..])));
+        assert_eq!(
+            uleb128_stream::<_, NotFound>(&&input[..2]),
+            Err(StreamingError::Incomplete(VarintFailure::Incomplete))
+        );
+        assert!(matches!(
+            uleb128::<_, NotFound>(&&input[..2]),
+            Err(ParserError::Error(_))
+        ));
+
+        let max: Bytes = &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(uleb128::<_, NotFound>(&max), Ok((u64::MAX, &[][..])));
+        let overflow: Bytes = &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02];
+        assert_eq!(
+            uleb128::<_, NotFound>(&overflow),
+            Err(ParserError::Failure(VarintFailure::Overflow))
+        );
+
+        for (encoded, n) in [(0u8, 0i64), (1, -1), (2, 1), (3, -2), (0x7f, -64)] {
+            assert_eq!(
+                zigzag_leb128::<_, NotFound>(&&[encoded][..]),
+                Ok((n, &[][..]))
+            );
+        }
+    }
+
+    #[test]
+    fn length_prefixed_fields() {
+        let parser = (length_prefixed(be_u8, text(digits)), be_u16).and();
+        let input: Bytes = b"\x0242\x00\x2a";
+        assert_eq!(parser.parse(&input), Ok((("42", 42), &[][..])));
+
+        let short: Bytes = b"\x0542";
+        let Err(ParserError::Error(e)) = parser.parse(&short) else {
+            panic!("Parser should have failed")
+        };
+        assert_eq!(e.input(), &&b"42"[..]);
+
+        let unconsumed: Bytes = b"\x024x\x00\x2a";
+        let Err(ParserError::Error(e)) = parser.parse(&unconsumed) else {
+            panic!("Parser should have failed")
+        };
+        assert_eq!(e.to_string(), "expected end of field, found 0x78");
+    }
+
+    #[test]
+    fn length_prefixed_streams() {
+        let field = length_prefixed_stream(be_u16_stream, take::<_, NotFound>(3).to_failure(), 8);
+        assert_eq!(
+            field.parse_stream(&&b"\x00"[..]),
+            Err(StreamingError::Incomplete(Incomplete))
+        );
+        assert_eq!(
+            field.parse_stream(&&b"\x00\x03ab"[..]),
+            Err(StreamingError::Incomplete(Incomplete))
+        );
+        assert_eq!(
+            field.parse_stream(&&b"\x00\x03abcd"[..]),
+            Ok(StreamingOk::Complete(&b"abc"[..], &b"d"[..]))
+        );
+        assert_eq!(
+            field.parse_stream(&&b"\x00\x09abc"[..]),
+            Err(StreamingError::Error(NotFound))
+        );
+
+        // A length which doesn't fit in a usize.
+        let input = [[0xff; 16].as_slice(), b"abc"].concat();
+        let field = length_prefixed_stream(be_u128_stream, take::<_, NotFound>(3).to_failure(), 8);
+        assert_eq!(
+            field.parse_stream(&input.as_slice()),
+            Err(StreamingError::Error(NotFound))
+        );
+    }
+}
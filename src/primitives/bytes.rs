@@ -0,0 +1,160 @@
+/*!
+# Byte primitives
+
+- Counterparts to [`tag`][fn@super::tag], [`take`][super::take] etc. for
+  [`ByteInput`]. These share names with the text primitives, so they are not
+  re-exported from [`primitives`][super].
+- A byte string literal such as `b"GET"` can be used as shorthand for
+  `tag(b"GET")`.
+- [`text`] runs a text parser over the valid UTF-8 prefix of some bytes.
+
+*/
+
+use crate::{
+    input::{bytes::ByteInput, Slice},
+    parse::{ExpectError, Expectation, Expected, Parser, ParserError, ParserResult},
+    util::conditional_transforms::OrExpected,
+};
+
+pub fn tag<T: AsRef<[u8]>, I: ByteInput, E: ExpectError<I>>(b: T) -> impl Parser<I, I, E> {
+    move |input: &I| input.pop(&b).ok_or_expected(input, Expectation::bytes(&b))
+}
+
+/// Exactly `count` bytes.
+pub fn take<I: ByteInput, E: ExpectError<I>>(count: usize) -> impl Parser<I, I, E> {
+    move |input: &I| {
+        input
+            .split_at_checked(count)
+            .ok_or_expected(input, Expectation::label("more bytes"))
+    }
+}
+
+/// One or more bytes matching `predicate`.
+pub fn take_while<I: ByteInput, E: ExpectError<I>, Predicate: Fn(u8) -> bool>(
+    predicate: Predicate,
+) -> impl Parser<I, I, E> {
+    move |input: &I| {
+        input
+            .take_while(&predicate)
+            .ok_or_expected(input, Expectation::label("matching byte"))
+    }
+}
+
+/// Bytes up to (but not including) the first which matches `predicate`.
+pub fn take_until<I: ByteInput, E: ExpectError<I>, Predicate: Fn(u8) -> bool>(
+    predicate: Predicate,
+) -> impl Parser<I, I, E> {
+    move |input: &I| {
+        input
+            .take_until(&predicate)
+            .ok_or_expected(input, Expectation::label("matching byte"))
+    }
+}
+
+/// Bytes up to (but not including) the first occurrence of `needle`.
+pub fn take_until_tag<T: AsRef<[u8]>, I: ByteInput, E: ExpectError<I>>(
+    needle: T,
+) -> impl Parser<I, I, E> {
+    move |input: &I| {
+        input
+            .find(&needle)
+            .map(|idx| input.split_at(idx))
+            .ok_or_expected(input, Expectation::bytes(&needle))
+    }
+}
+
+/// Run a text parser over the longest prefix of the input which is valid
+/// UTF-8. The remaining input & any error position are mapped back onto the
+/// bytes.
+pub fn text<I: ByteInput, O, F, P: Parser<I::Text, O, Expected<I::Text>, F>>(
+    parser: P,
+) -> impl Parser<I, O, Expected<I>, F> {
+    move |input: &I| {
+        let valid = match std::str::from_utf8(input.as_bytes()) {
+            Ok(_) => input.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let text = input
+            .take(valid)
+            .to_text()
+            .expect("Prefix should be valid UTF-8");
+        let rebase = |remaining: I::Text| input.skip(valid - remaining.len());
+
+        match parser.parse(&text) {
+            Ok((output, remaining)) => Ok((output, rebase(remaining))),
+            Err(ParserError::Error(e)) => Err(ParserError::Error(e.map_input(rebase))),
+            Err(ParserError::Failure(e)) => Err(ParserError::Failure(e)),
+        }
+    }
+}
+
+impl<const N: usize, I: ByteInput, E: ExpectError<I>> Parser<I, I, E> for &[u8; N] {
+    fn parse(&self, input: &I) -> ParserResult<I, I, E> {
+        tag(self).parse(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::bytes::SharedBytes,
+        parse::{Choice, NotFound, Sequence},
+        primitives::numbers::digits,
+    };
+
+    #[test]
+    fn simple() {
+        let input: &[u8] = b"\x00\x01GET /";
+        assert_eq!(
+            tag::<_, _, NotFound>(b"\x00\x01").parse(&input),
+            Ok((&b"\x00\x01"[..], &b"GET /"[..]))
+        );
+        assert_eq!(
+            take::<_, NotFound>(3).parse(&input),
+            Ok((&b"\x00\x01G"[..], &b"ET /"[..]))
+        );
+        assert!(take::<_, NotFound>(8).parse(&input).is_err());
+        assert_eq!(
+            take_while::<_, NotFound, _>(|b| b < 0x10).parse(&input),
+            Ok((&b"\x00\x01"[..], &b"GET /"[..]))
+        );
+        assert_eq!(
+            take_until::<_, NotFound, _>(|b| b == b' ').parse(&input),
+            Ok((&b"\x00\x01GET"[..], &b" /"[..]))
+        );
+    }
+
+    #[test]
+    fn sequence_and_choice() {
+        let method = (b"GET", b"POST").or();
+        let parser = (method, b" ", take_until_tag(b"\r\n"), b"\r\n").and();
+
+        let input = SharedBytes::new(b"POST /index\r\n\xff".to_vec());
+        let result: ParserResult<_, _, Expected<_>> = parser.parse(&input);
+        let ((method, _, path, _), remaining) = result.unwrap();
+        assert_eq!(method.as_bytes(), b"POST");
+        assert_eq!(path.as_bytes(), b"/index");
+        assert_eq!(remaining.as_bytes(), b"\xff");
+
+        let Err(ParserError::Error(e)) = parser.parse(&SharedBytes::from(&b"PUT /"[..])) else {
+            panic!("Parser should have failed")
+        };
+        assert_eq!(e.to_string(), "expected b\"GET\" or b\"POST\", found 0x50");
+    }
+
+    #[test]
+    fn text_view() {
+        let parser = (b"len=", text(digits), b"\xff").and();
+        let input: &[u8] = b"len=42\xff";
+        let ((_, n, _), remaining) = parser.parse(&input).unwrap();
+        assert_eq!(n, "42");
+        assert!(remaining.is_empty());
+
+        let Err(ParserError::Error(e)) = parser.parse(&&b"len=x"[..]) else {
+            panic!("Parser should have failed")
+        };
+        assert_eq!(e.input(), &&b"x"[..]);
+        assert_eq!(e.to_string(), "expected digit, found 0x78");
+    }
+}
@@ -1,13 +1,13 @@
 use crate::{
     input::Input,
-    parse::{Choice, NotFound, Parser, ParserError, ParserResult, StreamingResult},
+    parse::{Choice, ExpectError, Expectation, Parser, ParserError, ParserResult, StreamingResult},
     util::conditional_transforms::MaybeCompleteIf,
-    util::conditional_transforms::StreamingOrNotFound,
+    util::conditional_transforms::StreamingOrExpected,
 };
 
 use super::tag::tag;
 
-pub fn line<I: Input>(s: &I) -> ParserResult<I, I> {
+pub fn line<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
     if let Some((line, remaining)) = s.pop(&"\n") {
         Ok((line, remaining))
     } else if let Some((line, remaining)) = s.take_while(|c| c != '\n') {
@@ -22,58 +22,68 @@ pub fn line<I: Input>(s: &I) -> ParserResult<I, I> {
     }
 }
 
-pub fn line_stream<I: Input>(s: &I) -> StreamingResult<I, I> {
+pub fn line_stream<I: Input, E: ExpectError<I>>(s: &I) -> StreamingResult<I, I, E> {
     s.take_while(|c| c != '\n')
         .as_complete_if(|_, remaining| !remaining.is_empty())
-        .ok_or_not_found()
+        .ok_or_expected(s, Expectation::label("line"))
 }
 
-pub fn eol<I: Input>(s: &I) -> ParserResult<I, I> {
+pub fn eol<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
     ("\n", "\r\n").or().parse(s)
 }
 
-pub fn eof<I: Input, F>(s: &I) -> ParserResult<I, ()> {
-    if s.len() == 0 {
+pub fn eof<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, (), E> {
+    if s.is_empty() {
         Ok(((), s.clone()))
     } else {
-        Err(ParserError::Error(NotFound))
+        Err(ParserError::Error(E::expected(
+            s,
+            Expectation::label("end of input"),
+        )))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parse::NotFound;
 
     #[test]
     fn empty() {
-        assert_eq!(line.parse(&""), Ok(("", "")));
+        assert_eq!(line::<_, NotFound>.parse(&""), Ok(("", "")));
     }
 
     #[test]
     fn blank_line() {
-        assert_eq!(line.parse(&"\n\n"), Ok(("\n", "\n")));
-        assert_eq!(line.parse(&"\r\n\r\n"), Ok(("\r\n", "\r\n")));
+        assert_eq!(line::<_, NotFound>.parse(&"\n\n"), Ok(("\n", "\n")));
+        assert_eq!(line::<_, NotFound>.parse(&"\r\n\r\n"), Ok(("\r\n", "\r\n")));
     }
 
     #[test]
     fn no_newline() {
-        assert_eq!(line.parse(&"foo"), Ok(("foo", "")));
+        assert_eq!(line::<_, NotFound>.parse(&"foo"), Ok(("foo", "")));
     }
 
     #[test]
     fn linebreak_is_present() {
-        assert_eq!(line.parse(&"foo\n"), Ok(("foo\n", "")));
-        assert_eq!(line.parse(&"foo\nbar"), Ok(("foo\n", "bar")));
+        assert_eq!(line::<_, NotFound>.parse(&"foo\n"), Ok(("foo\n", "")));
+        assert_eq!(line::<_, NotFound>.parse(&"foo\nbar"), Ok(("foo\n", "bar")));
 
-        assert_eq!(line.parse(&"foo\r\n"), Ok(("foo\r\n", "")));
-        assert_eq!(line.parse(&"foo\r\nbar"), Ok(("foo\r\n", "bar")));
+        assert_eq!(line::<_, NotFound>.parse(&"foo\r\n"), Ok(("foo\r\n", "")));
+        assert_eq!(
+            line::<_, NotFound>.parse(&"foo\r\nbar"),
+            Ok(("foo\r\n", "bar"))
+        );
     }
 
     #[test]
     fn multiple_linebreaks() {
-        assert_eq!(line.parse(&"foo\nbar\nbaz"), Ok(("foo\n", "bar\nbaz")));
         assert_eq!(
-            line.parse(&"foo\r\nbar\r\nbaz"),
+            line::<_, NotFound>.parse(&"foo\nbar\nbaz"),
+            Ok(("foo\n", "bar\nbaz"))
+        );
+        assert_eq!(
+            line::<_, NotFound>.parse(&"foo\r\nbar\r\nbaz"),
             Ok(("foo\r\n", "bar\r\nbaz"))
         );
     }
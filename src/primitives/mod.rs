@@ -2,18 +2,23 @@
 # Primitive parsers
 
 - Primitive parsers are single-order functions generic over an [`Input`][input::Input] argument and
-    returning a [`ParserResult`][parse::ParserResult] of the form `Fn(&Input) -> ParserResult<Input, Output, ...>`
+  returning a [`ParserResult`][parse::ParserResult] of the form `Fn(&Input) -> ParserResult<Input, Output, ...>`
 - Most of them return a `ParserResult<Input, Input>`.
 
 */
 
+pub mod binary;
+pub mod bytes;
 pub mod line;
 pub mod numbers;
 pub mod quote;
+pub mod shell;
 pub mod tag;
 pub mod take;
+pub mod tokens;
 pub mod whitespace;
 
+pub use binary::*;
 pub use line::*;
 pub use numbers::*;
 pub use quote::*;
@@ -4,58 +4,61 @@ use std::{
 };
 
 use crate::{
-    fuse,
     input::Input,
-    parse::{Choice, Fusable, NotFound, Parser, ParserError, ParserResult, Sequence},
+    parse::{
+        Choice, ExpectError, Expectation, FuseSequence, Parser, ParserError, ParserResult, Sequence,
+    },
     primitives::tag::tag_no_case,
-    util::conditional_transforms::OrNotFound,
+    util::conditional_transforms::OrExpected,
 };
 
-pub fn sign<I: Input>(s: &I) -> ParserResult<I, I> {
+pub fn sign<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
     ("+", "-").or().parse(s)
 }
 
-pub fn digits<I: Input>(s: &I) -> ParserResult<I, I> {
-    s.take_while(|c| c.is_ascii_digit()).ok_or_not_found()
+pub fn digits<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    s.take_while(|c| c.is_ascii_digit())
+        .ok_or_expected(s, Expectation::label("digit"))
 }
 
-pub fn digits_with_decimal<I: Input>(s: &I) -> ParserResult<I, I> {
-    fuse!((digits, ".", digits)).parse(s)
+pub fn digits_with_decimal<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    (digits, ".", digits).fuse().parse(s)
 }
 
-pub fn plain_number<I: Input>(s: &I) -> ParserResult<I, I> {
-    fuse!((sign.opt(), digits)).parse(s)
+pub fn plain_number<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    (sign.opt(), digits).fuse().parse(s)
 }
 
-pub fn positive_number<I: Input>(s: &I) -> ParserResult<I, I> {
-    fuse!(("+".opt(), digits)).parse(s)
+pub fn positive_number<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    ("+".opt(), digits).fuse().parse(s)
 }
 
-pub fn negative_number<I: Input>(s: &I) -> ParserResult<I, I> {
-    fuse!(("-", digits)).parse(s)
+pub fn negative_number<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    ("-", digits).fuse().parse(s)
 }
 
-pub fn number_with_decimal<I: Input>(s: &I) -> ParserResult<I, I> {
-    fuse!((sign.opt(), digits_with_decimal)).parse(s)
+pub fn number_with_decimal<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    (sign.opt(), digits_with_decimal).fuse().parse(s)
 }
 
-pub fn scientific_number<I: Input>(s: &I) -> ParserResult<I, I> {
-    fuse!((
+pub fn scientific_number<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    (
         sign.opt(),
         digits,
         ".".opt(),
         digits.opt(),
         tag_no_case("e"),
         sign.opt(),
-        digits
-    ))
-    .parse(s)
+        digits,
+    )
+        .fuse()
+        .parse(s)
 }
 
-pub fn special<I: Input>(s: &I) -> ParserResult<I, I> {
+pub fn special<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
     (
-        fuse!((sign.opt(), "infinity")),
-        fuse!((sign.opt(), "inf")),
+        (sign.opt(), "infinity").fuse(),
+        (sign.opt(), "inf").fuse(),
         "NaN",
         "nan",
     )
@@ -80,7 +83,7 @@ impl<I> NumberToken<I> {
     }
 }
 impl<I: Input> NumberToken<I> {
-    pub fn parse(s: &I) -> ParserResult<I, Self> {
+    pub fn parse<E: ExpectError<I>>(s: &I) -> ParserResult<I, Self, E> {
         (
             scientific_number.map(NumberToken::Scientific),
             number_with_decimal.map(NumberToken::WithDecimal),
@@ -91,38 +94,51 @@ impl<I: Input> NumberToken<I> {
     }
 }
 
-pub fn integer<I: Input, O: Integer>(s: &I) -> ParserResult<I, O, NotFound, <O as FromStr>::Err> {
-    if let Ok((n, remaining)) = plain_number.parse(s) {
+pub fn integer<I: Input, O: Integer, E: ExpectError<I>>(
+    s: &I,
+) -> ParserResult<I, O, E, <O as FromStr>::Err> {
+    if let Ok((n, remaining)) = plain_number::<_, E>(s) {
         match O::from_str(n.as_str()) {
             Ok(output) => Ok((output, remaining)),
             Err(e) => Err(ParserError::Failure(e)),
         }
     } else {
-        Err(ParserError::Error(NotFound))
+        Err(ParserError::Error(E::expected(
+            s,
+            Expectation::label("integer"),
+        )))
     }
 }
 
-pub fn unsigned_integer<I: Input, O: UnsignedInteger>(
+pub fn unsigned_integer<I: Input, O: UnsignedInteger, E: ExpectError<I>>(
     s: &I,
-) -> ParserResult<I, O, NotFound, <O as FromStr>::Err> {
-    if let Ok((n, remaining)) = positive_number.parse(s) {
+) -> ParserResult<I, O, E, <O as FromStr>::Err> {
+    if let Ok((n, remaining)) = positive_number::<_, E>(s) {
         match O::from_str(n.as_str()) {
             Ok(output) => Ok((output, remaining)),
             Err(e) => Err(ParserError::Failure(e)),
         }
     } else {
-        Err(ParserError::Error(NotFound))
+        Err(ParserError::Error(E::expected(
+            s,
+            Expectation::label("unsigned integer"),
+        )))
     }
 }
 
-pub fn real<I: Input, O: Real>(s: &I) -> ParserResult<I, O, NotFound, <O as FromStr>::Err> {
-    if let Ok((n, remaining)) = NumberToken::parse(s) {
+pub fn real<I: Input, O: Real, E: ExpectError<I>>(
+    s: &I,
+) -> ParserResult<I, O, E, <O as FromStr>::Err> {
+    if let Ok((n, remaining)) = NumberToken::parse::<E>(s) {
         match O::from_str(n.unwrap().as_str()) {
             Ok(output) => Ok((output, remaining)),
             Err(e) => Err(ParserError::Failure(e)),
         }
     } else {
-        Err(ParserError::Error(NotFound))
+        Err(ParserError::Error(E::expected(
+            s,
+            Expectation::label("number"),
+        )))
     }
 }
 
@@ -133,8 +149,8 @@ pub enum Number {
     Real(f32),
 }
 impl Number {
-    pub fn parse<I: Input>(s: &I) -> ParserResult<I, Self, NotFound, NumberFailure> {
-        match NumberToken::parse(s) {
+    pub fn parse<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, Self, E, NumberFailure> {
+        match NumberToken::parse::<E>(s) {
             Ok((NumberToken::Plain(n), remaining)) => {
                 if n.as_str().starts_with("-") {
                     i32::from_str(n.as_str())
@@ -150,7 +166,10 @@ impl Number {
             | Ok((NumberToken::Scientific(n), remaining)) => f32::from_str(n.as_str())
                 .map(|n| (n.into(), remaining))
                 .map_err(|e| ParserError::Failure(e.into())),
-            Err(_) => Err(ParserError::Error(NotFound)),
+            Err(_) => Err(ParserError::Error(E::expected(
+                s,
+                Expectation::label("number"),
+            ))),
         }
     }
 }
@@ -169,8 +188,8 @@ impl From<f32> for Number {
         Self::Real(v)
     }
 }
-impl<I: Input> Parser<I, Number, NotFound, NumberFailure> for Number {
-    fn parse(&self, input: &I) -> ParserResult<I, Number, NotFound, NumberFailure> {
+impl<I: Input, E: ExpectError<I>> Parser<I, Number, E, NumberFailure> for Number {
+    fn parse(&self, input: &I) -> ParserResult<I, Number, E, NumberFailure> {
         Self::parse(input)
     }
 }
@@ -226,55 +245,110 @@ float_impl!(f32, f64,);
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        input::{span::Span, Slice},
+        parse::{Expected, NotFound},
+    };
 
     #[test]
     fn plain() {
-        assert_eq!(plain_number.parse(&"123"), Ok(("123", "")));
+        assert_eq!(plain_number::<_, NotFound>.parse(&"123"), Ok(("123", "")));
     }
 
     #[test]
     fn with_decimal() {
-        assert_eq!(number_with_decimal.parse(&"123.45"), Ok(("123.45", "")));
+        assert_eq!(
+            number_with_decimal::<_, NotFound>.parse(&"123.45"),
+            Ok(("123.45", ""))
+        );
     }
 
     #[test]
     fn scientific() {
-        assert_eq!(scientific_number.parse(&"1e6"), Ok(("1e6", "")));
-        assert_eq!(scientific_number.parse(&"1.0e6"), Ok(("1.0e6", "")));
+        assert_eq!(
+            scientific_number::<_, NotFound>.parse(&"1e6"),
+            Ok(("1e6", ""))
+        );
+        assert_eq!(
+            scientific_number::<_, NotFound>.parse(&"1.0e6"),
+            Ok(("1.0e6", ""))
+        );
 
-        assert_eq!(scientific_number.parse(&"1E6"), Ok(("1E6", "")));
-        assert_eq!(scientific_number.parse(&"1.0E6"), Ok(("1.0E6", "")));
+        assert_eq!(
+            scientific_number::<_, NotFound>.parse(&"1E6"),
+            Ok(("1E6", ""))
+        );
+        assert_eq!(
+            scientific_number::<_, NotFound>.parse(&"1.0E6"),
+            Ok(("1.0E6", ""))
+        );
     }
 
     #[test]
     fn one() {
-        assert_eq!(Number::parse.parse(&"1"), Ok((Number::Unsigned(1), "")));
-        assert_eq!(Number::parse.parse(&"-1"), Ok((Number::Signed(-1), "")));
-        assert_eq!(Number::parse.parse(&"1.0"), Ok((Number::Real(1.0), "")));
-        assert_eq!(Number::parse.parse(&"-1.0"), Ok((Number::Real(-1.0), "")));
+        assert_eq!(
+            Number::parse::<_, NotFound>.parse(&"1"),
+            Ok((Number::Unsigned(1), ""))
+        );
+        assert_eq!(
+            Number::parse::<_, NotFound>.parse(&"-1"),
+            Ok((Number::Signed(-1), ""))
+        );
+        assert_eq!(
+            Number::parse::<_, NotFound>.parse(&"1.0"),
+            Ok((Number::Real(1.0), ""))
+        );
+        assert_eq!(
+            Number::parse::<_, NotFound>.parse(&"-1.0"),
+            Ok((Number::Real(-1.0), ""))
+        );
     }
 
     #[test]
     fn zeroes() {
-        assert_eq!(Number::parse.parse(&"0"), Ok((Number::Unsigned(0), "")));
-        assert_eq!(Number::parse.parse(&"0.0"), Ok((Number::Real(0.0), "")));
-        assert_eq!(Number::parse.parse(&"-0.0"), Ok((Number::Real(0.0), "")));
+        assert_eq!(
+            Number::parse::<_, NotFound>.parse(&"0"),
+            Ok((Number::Unsigned(0), ""))
+        );
+        assert_eq!(
+            Number::parse::<_, NotFound>.parse(&"0.0"),
+            Ok((Number::Real(0.0), ""))
+        );
+        assert_eq!(
+            Number::parse::<_, NotFound>.parse(&"-0.0"),
+            Ok((Number::Real(0.0), ""))
+        );
+    }
+
+    #[test]
+    fn expected() {
+        let input = Span::anonymous("12.x");
+        let Err(ParserError::Error(e)) = number_with_decimal::<_, Expected<Span>>(&input) else {
+            panic!("Expected an error")
+        };
+        assert_eq!(e.input().position(), (1, 4));
+
+        let Err(ParserError::Error(e)) = Number::parse::<_, Expected<Span>>(&input.skip(3)) else {
+            panic!("Expected an error")
+        };
+        assert!(e.to_string().starts_with("expected number"), "{e}");
     }
 }
 
 #[cfg(test)]
 mod property_tests {
     use super::*;
+    use crate::parse::NotFound;
     use proptest::prelude::*;
 
     proptest! {
         #[test]
         fn uinteger_dogfood(n in u32::MIN..u32::MAX) {
-            assert_eq!(Number::parse(&n.to_string().as_str()), Ok((Number::Unsigned(n), "")))
+            assert_eq!(Number::parse::<_, NotFound>(&n.to_string().as_str()), Ok((Number::Unsigned(n), "")))
         }
         #[test]
         fn integer_dogfood(n in i32::MIN..i32::MAX) {
-            match Number::parse(&n.to_string()) {
+            match Number::parse::<_, NotFound>(&n.to_string()) {
                 Ok((Number::Signed(x), _)) => assert_eq!(n, x),
                 Ok((Number::Unsigned(x), _)) => assert_eq!(n as u32, x),
                 Ok((Number::Real(x), _)) => panic!("Integer should not be real (parsed as {})", x),
@@ -285,7 +359,7 @@ mod property_tests {
         fn float_dogfood(a in i32::MIN..i32::MAX, b in 0f32..1f32) {
             let n = (a as f32) * b;
             let s = format!("{:.4}", n);
-            match Number::parse(&s) {
+            match Number::parse::<_, NotFound>(&s) {
                 Ok((Number::Real(actual), _)) => assert!((n - actual).abs() < 0.1),
                 Ok(x) => panic!("Wrong number kind {:?}", x),
                 Err(e) => panic!("Error: {:?}", e),
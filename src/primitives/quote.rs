@@ -2,8 +2,8 @@ use crate::{
     combinators::sandwich::sandwich,
     input::Input,
     parse::{
-        Choice, Never, NotFound, Parser, ParserError, ParserResult, StreamingChoice,
-        StreamingError, StreamingOk, StreamingParser, StreamingResult,
+        Choice, ExpectError, Expectation, Never, NotFound, Parser, ParserError, ParserResult,
+        StreamingChoice, StreamingError, StreamingOk, StreamingParser, StreamingResult,
     },
     primitives::tag::tag,
 };
@@ -17,9 +17,17 @@ pub const SINGLE_QUOTE_STR: &str = "'";
 pub const ESCAPE: char = '\\';
 pub const ESCAPE_STR: &str = "\\";
 
-pub fn single_quoted_stream<I: Input>(s: &I) -> StreamingResult<I, I, NotFound, UnterminatedQuote> {
-    let Ok((_, remaining)) = tag(SINGLE_QUOTE_STR).parse(s) else {
-        return Err(StreamingError::Error(NotFound));
+/// Text between `'` marks. A backslash escapes whatever follows it, so `\'`
+/// doesn't close the quote but `\\'` does. The output is the text between the
+/// quotes, with any escapes as written.
+pub fn single_quoted_stream<I: Input, E: ExpectError<I>>(
+    s: &I,
+) -> StreamingResult<I, I, E, UnterminatedQuote> {
+    let Some((_, remaining)) = s.pop(&SINGLE_QUOTE_STR) else {
+        return Err(StreamingError::Error(E::expected(
+            s,
+            Expectation::tag(SINGLE_QUOTE_STR),
+        )));
     };
 
     if let Some(idx) = find_quote_mark(SINGLE_QUOTE, remaining.as_str()) {
@@ -31,13 +39,21 @@ pub fn single_quoted_stream<I: Input>(s: &I) -> StreamingResult<I, I, NotFound,
     }
 }
 
-pub fn single_quoted<I: Input>(s: &I) -> ParserResult<I, I, NotFound, UnterminatedQuote> {
+pub fn single_quoted<I: Input, E: ExpectError<I>>(
+    s: &I,
+) -> ParserResult<I, I, E, UnterminatedQuote> {
     single_quoted_stream.complete().parse(s)
 }
 
-pub fn double_quoted_stream<I: Input>(s: &I) -> StreamingResult<I, I, NotFound, UnterminatedQuote> {
-    let Ok((_, remaining)) = tag(DOUBLE_QUOTE_STR).parse(s) else {
-        return Err(StreamingError::Error(NotFound));
+/// As [`single_quoted_stream`], for text between `"` marks.
+pub fn double_quoted_stream<I: Input, E: ExpectError<I>>(
+    s: &I,
+) -> StreamingResult<I, I, E, UnterminatedQuote> {
+    let Some((_, remaining)) = s.pop(&DOUBLE_QUOTE_STR) else {
+        return Err(StreamingError::Error(E::expected(
+            s,
+            Expectation::tag(DOUBLE_QUOTE_STR),
+        )));
     };
 
     if let Some(idx) = find_quote_mark(DOUBLE_QUOTE, remaining.as_str()) {
@@ -49,32 +65,89 @@ pub fn double_quoted_stream<I: Input>(s: &I) -> StreamingResult<I, I, NotFound,
     }
 }
 
-pub fn double_quoted<I: Input>(s: &I) -> ParserResult<I, I, NotFound, UnterminatedQuote> {
+pub fn double_quoted<I: Input, E: ExpectError<I>>(
+    s: &I,
+) -> ParserResult<I, I, E, UnterminatedQuote> {
     double_quoted_stream.complete().parse(s)
 }
 
-pub fn quoted_stream<I: Input>(s: &I) -> StreamingResult<I, I, NotFound, UnterminatedQuote> {
+pub fn quoted_stream<I: Input, E: ExpectError<I>>(
+    s: &I,
+) -> StreamingResult<I, I, E, UnterminatedQuote> {
     (single_quoted_stream, double_quoted_stream)
         .or()
         .parse_stream(s)
 }
 
-pub fn quoted<I: Input>(s: &I) -> ParserResult<I, I, NotFound, UnterminatedQuote> {
+pub fn quoted<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E, UnterminatedQuote> {
     (single_quoted, double_quoted).or().parse(s)
 }
 
+/// Text between two `quote` marks, in which a doubled quote mark stands for a
+/// single one, as in CSV & SQL. Unlike [`double_quoted`], backslashes have no
+/// special meaning. The output is the text between the outer quotes, with any
+/// quote marks still doubled.
+///
+/// A closing quote at the end of the input could be the first of a doubled
+/// pair, so the result is then [`StreamingOk::Partial`].
+pub fn doubled_quoted_stream<I: Input, E: ExpectError<I>>(
+    quote: char,
+) -> impl StreamingParser<I, I, E, UnterminatedQuote> {
+    let quote_str = quote.to_string();
+    move |s: &I| {
+        let Some((_, remaining)) = s.pop(&quote_str) else {
+            return Err(StreamingError::Error(E::expected(
+                s,
+                Expectation::tag(&quote_str),
+            )));
+        };
+
+        let Some(idx) = find_doubled_quote_mark(quote, remaining.as_str()) else {
+            return Err(StreamingError::Incomplete(UnterminatedQuote));
+        };
+        let (output, r) = remaining.split_at(idx);
+        let remaining = r.skip(quote.len_utf8());
+        if remaining.is_empty() {
+            Ok(StreamingOk::Partial(output, remaining))
+        } else {
+            Ok(StreamingOk::Complete(output, remaining))
+        }
+    }
+}
+
+pub fn doubled_quoted<I: Input, E: ExpectError<I>>(
+    quote: char,
+) -> impl Parser<I, I, E, UnterminatedQuote> {
+    doubled_quoted_stream(quote).complete()
+}
+
+/// The index of the first quote mark which isn't doubled.
+fn find_doubled_quote_mark(quote: char, s: &str) -> Option<usize> {
+    let mut iter = s.char_indices().peekable();
+    while let Some((idx, c)) = iter.next() {
+        if c == quote {
+            match iter.peek() {
+                Some((_, next)) if *next == quote => {
+                    iter.next();
+                }
+                _ => return Some(idx),
+            }
+        }
+    }
+
+    None
+}
+
+/// The index of the first quote mark which isn't escaped. A backslash escapes
+/// whatever follows it, including another backslash.
 fn find_quote_mark(quote: char, s: &str) -> Option<usize> {
     let mut iter = s.char_indices();
-    let mut prev = match iter.next() {
-        Some((idx, c)) if c == quote => return Some(idx),
-        Some((_, c)) => c,
-        None => return None,
-    };
-    while let Some((idx, current)) = iter.next() {
-        if current == quote && prev != ESCAPE {
+    while let Some((idx, c)) = iter.next() {
+        if c == quote {
             return Some(idx);
+        } else if c == ESCAPE {
+            iter.next();
         }
-        prev = current;
     }
 
     None
@@ -100,36 +173,89 @@ mod test {
     #[test]
     fn simple() {
         assert_eq!(
-            single_quoted.parse(&"'foo' bar"),
+            single_quoted::<_, NotFound>.parse(&"'foo' bar"),
+            Ok(("foo", " bar"))
+        );
+        assert_eq!(
+            quoted::<_, NotFound>.parse(&"'foo' bar"),
             Ok(("foo", " bar"))
         );
-        assert_eq!(quoted.parse(&"'foo' bar"), Ok(("foo", " bar")));
 
         assert_eq!(
-            double_quoted.parse(&"\"foo\" bar"),
+            double_quoted::<_, NotFound>.parse(&"\"foo\" bar"),
+            Ok(("foo", " bar"))
+        );
+        assert_eq!(
+            quoted::<_, NotFound>.parse(&"\"foo\" bar"),
             Ok(("foo", " bar"))
         );
-        assert_eq!(quoted.parse(&"\"foo\" bar"), Ok(("foo", " bar")));
     }
 
     #[test]
     fn escaped() {
         assert_eq!(
-            single_quoted.parse(&"'foo\\' bar' baz"),
+            single_quoted::<_, NotFound>.parse(&"'foo\\' bar' baz"),
             Ok(("foo\\' bar", " baz"))
         );
         assert_eq!(
-            quoted.parse(&"'foo\\' bar' baz"),
+            quoted::<_, NotFound>.parse(&"'foo\\' bar' baz"),
             Ok(("foo\\' bar", " baz"))
         );
 
         assert_eq!(
-            double_quoted.parse(&"\"foo\\\" bar\" baz"),
+            double_quoted::<_, NotFound>.parse(&"\"foo\\\" bar\" baz"),
             Ok(("foo\\\" bar", " baz"))
         );
         assert_eq!(
-            quoted.parse(&"\"foo\\\" bar\" baz"),
+            quoted::<_, NotFound>.parse(&"\"foo\\\" bar\" baz"),
             Ok(("foo\\\" bar", " baz"))
         );
+        assert_eq!(
+            double_quoted::<_, NotFound>.parse(&"\"foo\\\\\" bar"),
+            Ok(("foo\\\\", " bar"))
+        );
+    }
+
+    #[test]
+    fn escaped_backslash() {
+        // An escaped backslash doesn't escape the quote which follows it.
+        assert_eq!(
+            double_quoted::<_, NotFound>.parse(&r#""a\\" b"#),
+            Ok((r"a\\", " b"))
+        );
+        assert_eq!(
+            single_quoted::<_, NotFound>.parse(&r"'a\\\'' b"),
+            Ok((r"a\\\'", " b"))
+        );
+        assert_eq!(
+            double_quoted::<_, NotFound>.parse(&r#""a\""#),
+            Err(ParserError::Failure(UnterminatedQuote))
+        );
+    }
+
+    #[test]
+    fn doubled() {
+        assert_eq!(
+            doubled_quoted::<_, NotFound>('"').parse(&"\"say \"\"hi\"\"\", x"),
+            Ok(("say \"\"hi\"\"", ", x"))
+        );
+        assert_eq!(
+            doubled_quoted::<_, NotFound>('\'').parse(&"'a\\' b"),
+            Ok(("a\\", " b"))
+        );
+        assert_eq!(
+            doubled_quoted::<_, NotFound>('"').parse(&"\"a\"\"b"),
+            Err(ParserError::Failure(UnterminatedQuote))
+        );
+
+        let parser = doubled_quoted_stream::<_, NotFound>('"');
+        assert_eq!(
+            parser.parse_stream(&"\"a\""),
+            Ok(StreamingOk::Partial("a", ""))
+        );
+        assert_eq!(
+            parser.parse_stream(&"\"a\"\""),
+            Err(StreamingError::Incomplete(UnterminatedQuote))
+        );
     }
 }
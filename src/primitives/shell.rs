@@ -0,0 +1,317 @@
+/*!
+# Shell words
+
+- [`words`] splits a command line into words as `sh` does, removing quotes &
+  backslashes and skipping comments, but making no expansions.
+- [`word`] parses a single word. Each [`Word`] keeps the input it was written
+  as, and can map its text back to that input.
+- [`quote_word`] quotes text so that it reads back as one word.
+
+*/
+
+use std::{borrow::Cow, fmt, ops::Range};
+
+use crate::{
+    input::Input,
+    parse::{ExpectError, Expectation, Never, ParserError, ParserResult},
+};
+
+/// A word of a command line, with its quotes & backslashes removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Word<I> {
+    /// The word as written.
+    pub raw: I,
+    pub text: String,
+    /// The runs of `raw` copied into `text`, which joined are the text.
+    pieces: Vec<I>,
+}
+impl<I: Input> Word<I> {
+    /// The runs of input which make up the text, without the quotes &
+    /// backslashes between them.
+    pub fn pieces(&self) -> &[I] {
+        &self.pieces
+    }
+    /// The input which the character at byte `idx` of the text was read
+    /// from, or `None` if `idx` isn't the start of a character.
+    pub fn source(&self, idx: usize) -> Option<I> {
+        let mut start = 0;
+        for piece in &self.pieces {
+            let end = start + piece.len();
+            if idx < end {
+                let offset = idx - start;
+                let c = piece.as_str().get(offset..)?.chars().next()?;
+                return Some(piece.slice(offset..offset + c.len_utf8()));
+            }
+            start = end;
+        }
+        None
+    }
+}
+
+/// A failure while splitting words, at the character which caused it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WordFailure<I> {
+    /// A `'` or `"` which is never closed.
+    UnterminatedQuote(I),
+    /// A `\` at the end of the input, with nothing to escape.
+    TrailingBackslash(I),
+}
+impl<I: Input> WordFailure<I> {
+    pub fn input(&self) -> &I {
+        match self {
+            WordFailure::UnterminatedQuote(i) | WordFailure::TrailingBackslash(i) => i,
+        }
+    }
+}
+impl<I> fmt::Display for WordFailure<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordFailure::UnterminatedQuote(_) => f.write_str("unterminated quote"),
+            WordFailure::TrailingBackslash(_) => f.write_str("trailing backslash"),
+        }
+    }
+}
+impl<I: fmt::Debug> std::error::Error for WordFailure<I> {}
+impl<I> From<Never> for WordFailure<I> {
+    fn from(value: Never) -> Self {
+        unreachable!()
+    }
+}
+
+/// Blanks separate words, as do unquoted newlines.
+fn is_blank(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n')
+}
+
+/// Split a command line into words as `sh` does, skipping blanks & comments.
+/// Newlines separate words like blanks, and operators such as `|` & `;` are
+/// not special. No expansions are made, so `$` and `*` are kept as written.
+pub fn words<I: Input, E: ExpectError<I>>(
+    s: &I,
+) -> ParserResult<I, Vec<Word<I>>, E, WordFailure<I>> {
+    let mut words = Vec::new();
+    let mut remaining = s.clone();
+    loop {
+        if let Some((_, rest)) = remaining.take_while(is_blank) {
+            remaining = rest;
+        } else if let Some((_, rest)) = remaining.pop(&"\\\n") {
+            // A line continuation between words joins nothing.
+            remaining = rest;
+        } else if let Some((_, rest)) = remaining.pop(&"#") {
+            remaining = rest.skip(rest.as_str().find('\n').unwrap_or(rest.len()));
+        } else if remaining.is_empty() {
+            return Ok((words, remaining));
+        } else {
+            let (w, rest) = word(&remaining)?;
+            words.push(w);
+            remaining = rest;
+        }
+    }
+}
+
+/// A single word, up to an unquoted blank or the end of the input.
+///
+/// - Text in `'` is taken as written.
+/// - In `"`, a backslash escapes `$`, `` ` ``, `"`, `\` and a newline, and is
+///   otherwise kept.
+/// - Elsewhere, a backslash escapes any character.
+/// - A backslash before a newline joins lines, and is removed with the
+///   newline.
+///
+/// Quoted & unquoted text next to each other form one word, so `a'b c'"d"` is
+/// `ab cd`. A word can't begin with `#`, which begins a comment.
+pub fn word<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, Word<I>, E, WordFailure<I>> {
+    let input = s.as_str();
+    if input.is_empty() || input.starts_with(is_blank) || input.starts_with('#') {
+        return Err(ParserError::Error(E::expected(
+            s,
+            Expectation::label("word"),
+        )));
+    }
+    let failure = |f: fn(I) -> WordFailure<I>, idx: usize| {
+        Err(ParserError::Failure(f(s.slice(idx..idx + 1))))
+    };
+
+    let mut text = String::new();
+    let mut pieces: Vec<Range<usize>> = Vec::new();
+    let mut copy = |range: Range<usize>| {
+        text.push_str(&input[range.clone()]);
+        match pieces.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => pieces.push(range),
+        }
+    };
+    let mut chars = input.char_indices().peekable();
+    let mut end = input.len();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            c if is_blank(c) => {
+                end = idx;
+                break;
+            }
+            '\\' => match chars.next() {
+                Some((_, '\n')) => {}
+                Some((i, c)) => copy(i..i + c.len_utf8()),
+                None => return failure(WordFailure::TrailingBackslash, idx),
+            },
+            '\'' => {
+                let Some(len) = input[idx + 1..].find('\'') else {
+                    return failure(WordFailure::UnterminatedQuote, idx);
+                };
+                let close = idx + 1 + len;
+                copy(idx + 1..close);
+                while chars.next_if(|(i, _)| *i <= close).is_some() {}
+            }
+            '"' => loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((i, '\\')) => match chars.peek() {
+                        Some((_, '\n')) => {
+                            chars.next();
+                        }
+                        Some(&(j, '$' | '`' | '"' | '\\')) => {
+                            copy(j..j + 1);
+                            chars.next();
+                        }
+                        _ => copy(i..i + 1),
+                    },
+                    Some((i, c)) => copy(i..i + c.len_utf8()),
+                    None => return failure(WordFailure::UnterminatedQuote, idx),
+                }
+            },
+            c => copy(idx..idx + c.len_utf8()),
+        }
+    }
+
+    let word = Word {
+        raw: s.slice(0..end),
+        text,
+        pieces: pieces.into_iter().map(|range| s.slice(range)).collect(),
+    };
+    Ok((word, s.skip(end)))
+}
+
+/// Characters which never need quoting.
+fn is_safe(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c)
+}
+
+/// Quote text so that [`words`], or `sh`, reads it back as a single word with
+/// the same text. Text which needs no quoting is returned as it is, and other
+/// text is put in `'`, with any `'` written as `'\''`.
+pub fn quote_word(s: &str) -> Cow<'_, str> {
+    if !s.is_empty() && s.chars().all(is_safe) {
+        return Cow::Borrowed(s);
+    }
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('\'');
+    for c in s.chars() {
+        if c == '\'' {
+            quoted.push_str("'\\''");
+        } else {
+            quoted.push(c);
+        }
+    }
+    quoted.push('\'');
+    Cow::Owned(quoted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::span::Span,
+        parse::{NotFound, Parser},
+    };
+
+    fn split(s: &str) -> Vec<String> {
+        let (words, _) = words::<_, NotFound>(&s).unwrap();
+        words.into_iter().map(|w| w.text).collect()
+    }
+
+    #[test]
+    fn splitting() {
+        assert_eq!(
+            split("  cargo  build\t--release \n"),
+            ["cargo", "build", "--release"]
+        );
+        assert_eq!(
+            split(r#"echo 'a b'"c d"e\ f 'it'\''s'"#),
+            ["echo", "a bc de f", "it's"]
+        );
+        assert_eq!(
+            split(r#""\$HOME \"x\" \\ \n" '\n' \n"#),
+            ["$HOME \"x\" \\ \\n", "\\n", "n"]
+        );
+        assert_eq!(split("a\\\nb \"c\\\nd\" 'e\nf'"), ["ab", "cd", "e\nf"]);
+        assert_eq!(split("'' \"\" a''"), ["", "", "a"]);
+        assert_eq!(split("a#b # comment 'x\nc #d"), ["a#b", "c"]);
+        assert!(split("  # only a comment").is_empty());
+        assert_eq!(split("x $y * a|b;c"), ["x", "$y", "*", "a|b;c"]);
+    }
+
+    #[test]
+    fn word_remaining() {
+        let (w, remaining) = word::<_, NotFound>.parse(&"a'b c' d").unwrap();
+        assert_eq!(
+            (w.raw, w.text.as_str(), remaining),
+            ("a'b c'", "ab c", " d")
+        );
+        assert_eq!(w.pieces(), ["a", "b c"]);
+        assert!(word::<_, NotFound>.parse(&" a").is_err());
+        assert!(word::<_, NotFound>.parse(&"#a").is_err());
+    }
+
+    #[test]
+    fn failures() {
+        let input = Span::anonymous("echo \"a b\nc");
+        let Err(ParserError::Failure(f)) = words::<_, NotFound>(&input) else {
+            panic!("Expected a failure")
+        };
+        assert!(matches!(f, WordFailure::UnterminatedQuote(_)));
+        assert_eq!(f.input().position(), (1, 6));
+
+        let input = Span::anonymous("a 'b");
+        let Err(ParserError::Failure(f)) = words::<_, NotFound>(&input) else {
+            panic!("Expected a failure")
+        };
+        assert_eq!(f.input().position(), (1, 3));
+
+        let input = Span::anonymous("a b\\");
+        let Err(ParserError::Failure(f)) = words::<_, NotFound>(&input) else {
+            panic!("Expected a failure")
+        };
+        assert!(matches!(f, WordFailure::TrailingBackslash(_)));
+        assert_eq!(f.input().position(), (1, 4));
+    }
+
+    #[test]
+    fn positions() {
+        let input = Span::anonymous("run \\\n  --name='a \\'b\"c\\\"\"");
+        let (words, _) = words::<_, NotFound>(&input).unwrap();
+        let name = &words[1];
+        assert_eq!(name.text, "--name=a \\bc\"");
+        assert_eq!(name.raw.position(), (2, 3));
+        assert_eq!(name.source(0).unwrap().position(), (2, 3));
+        // The `a` after the quote.
+        assert_eq!(name.source(7).unwrap().position(), (2, 11));
+        // The escaped `"`.
+        let quote = name.source(12).unwrap();
+        assert_eq!((quote.as_str(), quote.position()), ("\"", (2, 19)));
+        assert!(name.source(13).is_none());
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote_word("src/main.rs"), "src/main.rs");
+        assert!(matches!(quote_word("--flag=1"), Cow::Borrowed(_)));
+        assert_eq!(quote_word(""), "''");
+        assert_eq!(quote_word("a b"), "'a b'");
+        assert_eq!(quote_word("it's"), "'it'\\''s'");
+
+        for text in ["", "a b", "it's", "$HOME", "\"\\\n", "#x", "tab\there", "'"] {
+            let quoted = quote_word(text);
+            assert_eq!(split(&quoted), [text], "{quoted}");
+        }
+    }
+}
@@ -7,20 +7,24 @@
 
 use crate::{
     input::Input,
-    parse::{NotFound, Parser, ParserError, ParserResult},
-    util::conditional_transforms::OrNotFound,
+    parse::{ExpectError, Expectation, NotFound, Parser, ParserError, ParserResult},
+    util::conditional_transforms::OrExpected,
 };
 
-pub fn tag<T: AsRef<str>, I: Input>(s: T) -> impl Parser<I, I> {
-    move |input: &I| input.pop(&s).ok_or_not_found()
+pub fn tag<T: AsRef<str>, I: Input, E: ExpectError<I>>(s: T) -> impl Parser<I, I, E> {
+    move |input: &I| input.pop(&s).ok_or_expected(input, Expectation::tag(&s))
 }
 
-pub fn tag_no_case<T: AsRef<str>, I: Input>(s: T) -> impl Parser<I, I> {
-    move |input: &I| input.pop_no_case(&s).ok_or_not_found()
+pub fn tag_no_case<T: AsRef<str>, I: Input, E: ExpectError<I>>(s: T) -> impl Parser<I, I, E> {
+    move |input: &I| {
+        input
+            .pop_no_case(&s)
+            .ok_or_expected(input, Expectation::tag(&s))
+    }
 }
 
-impl<I: Input> Parser<I, I> for &str {
-    fn parse(&self, input: &I) -> ParserResult<I, I> {
+impl<I: Input, E: ExpectError<I>> Parser<I, I, E> for &str {
+    fn parse(&self, input: &I) -> ParserResult<I, I, E> {
         tag(self).parse(input)
     }
 }
@@ -31,18 +35,21 @@ mod test {
 
     #[test]
     fn simple() {
-        assert_eq!(tag::<_, _>("foo").parse(&"foobar"), Ok(("foo", "bar")));
+        assert_eq!(
+            tag::<_, _, NotFound>("foo").parse(&"foobar"),
+            Ok(("foo", "bar"))
+        );
 
         assert_eq!(
-            tag_no_case::<_, _>("foo").parse(&"foobar"),
+            tag_no_case::<_, _, NotFound>("foo").parse(&"foobar"),
             Ok(("foo", "bar"))
         );
         assert_eq!(
-            tag_no_case::<_, _>("foo").parse(&"FOObar"),
+            tag_no_case::<_, _, NotFound>("foo").parse(&"FOObar"),
             Ok(("FOO", "bar"))
         );
         assert_eq!(
-            tag_no_case::<_, _>("foo").parse(&"fOobar"),
+            tag_no_case::<_, _, NotFound>("foo").parse(&"fOobar"),
             Ok(("fOo", "bar"))
         );
     }
@@ -1,52 +1,90 @@
 use crate::{
     input::Input,
-    parse::{NotFound, Parser, ParserError},
+    parse::{ExpectError, Expectation, Parser},
+    util::conditional_transforms::OrExpected,
 };
 
-pub fn take_while0<I: Input, Predicate: Fn(char) -> bool>(
+pub fn take_while0<I: Input, E: ExpectError<I>, Predicate: Fn(char) -> bool>(
     predicate: Predicate,
-) -> impl Parser<I, I> {
+) -> impl Parser<I, I, E> {
     move |input: &I| {
         if let Some((s, remaining)) = input.take_while(&predicate) {
-            Ok((remaining, s))
+            Ok((s, remaining))
         } else {
-            Ok((input.clone(), input.empty()))
+            Ok((input.empty(), input.clone()))
         }
     }
 }
 
-pub fn take_while<I: Input, Predicate: Fn(char) -> bool>(
+pub fn take_while<I: Input, E: ExpectError<I>, Predicate: Fn(char) -> bool>(
     predicate: Predicate,
-) -> impl Parser<I, I> {
+) -> impl Parser<I, I, E> {
     move |input: &I| {
-        if let Some((s, remaining)) = input.take_while(&predicate) {
-            Ok((remaining, s))
-        } else {
-            Err(ParserError::Error(NotFound))
-        }
+        input
+            .take_while(&predicate)
+            .ok_or_expected(input, Expectation::label("matching character"))
     }
 }
 
-pub fn take_until0<I: Input, F, Predicate: Fn(char) -> bool>(
+pub fn take_until0<I: Input, E: ExpectError<I>, Predicate: Fn(char) -> bool>(
     predicate: Predicate,
-) -> impl Parser<I, I> {
+) -> impl Parser<I, I, E> {
     move |input: &I| {
         if let Some((s, remaining)) = input.take_until(&predicate) {
-            Ok((remaining, s))
+            Ok((s, remaining))
         } else {
-            Ok((input.clone(), input.empty()))
+            Ok((input.empty(), input.clone()))
         }
     }
 }
 
-pub fn take_until<I: Input, Predicate: Fn(char) -> bool>(
+pub fn take_until<I: Input, E: ExpectError<I>, Predicate: Fn(char) -> bool>(
     predicate: Predicate,
-) -> impl Parser<I, I> {
+) -> impl Parser<I, I, E> {
     move |input: &I| {
-        if let Some((s, remaining)) = input.take_until(&predicate) {
-            Ok((remaining, s))
-        } else {
-            Err(ParserError::Error(NotFound))
-        }
+        input
+            .take_until(&predicate)
+            .ok_or_expected(input, Expectation::label("matching character"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::{Expected, NotFound, ParserError};
+
+    #[test]
+    fn output_then_remaining() {
+        let digit = |c: char| c.is_ascii_digit();
+        assert_eq!(
+            take_while::<_, NotFound, _>(digit).parse(&"12ab"),
+            Ok(("12", "ab"))
+        );
+        assert_eq!(
+            take_while0::<_, NotFound, _>(digit).parse(&"ab"),
+            Ok(("", "ab"))
+        );
+        assert_eq!(
+            take_until::<_, NotFound, _>(digit).parse(&"ab12"),
+            Ok(("ab", "12"))
+        );
+        assert_eq!(
+            take_until0::<_, NotFound, _>(digit).parse(&"ab"),
+            Ok(("", "ab"))
+        );
+    }
+
+    #[test]
+    fn expected() {
+        let Err(ParserError::Error(e)) =
+            take_while::<_, Expected<_>, _>(|c| c.is_ascii_digit()).parse(&"ab")
+        else {
+            panic!("Expected an error")
+        };
+        assert_eq!(*e.input(), "ab");
+        assert!(
+            e.to_string().starts_with("expected matching character"),
+            "{e}"
+        );
     }
 }
@@ -0,0 +1,156 @@
+/*!
+# Token primitives
+
+- Counterparts to [`tag`][fn@super::tag] for [`TokenInput`], matching a single
+  token. These are not re-exported from [`primitives`][super].
+- Sequences, choices & repetition of these use the usual combinators.
+
+*/
+
+use std::fmt;
+
+use crate::{
+    input::tokens::{Token, TokenInput},
+    parse::{ExpectError, Expectation, Parser, ParserResult},
+    util::conditional_transforms::OrExpected,
+};
+
+/// Any single token.
+pub fn any_token<'a, T: fmt::Debug + 'a, E: ExpectError<TokenInput<'a, T>>>(
+    input: &TokenInput<'a, T>,
+) -> ParserResult<TokenInput<'a, T>, &'a T, E> {
+    input
+        .split_first()
+        .ok_or_expected(input, Expectation::label("token"))
+}
+
+/// A token of the given kind.
+pub fn token<'a, T: Token + fmt::Debug + 'a, E: ExpectError<TokenInput<'a, T>>>(
+    kind: T::Kind,
+) -> impl Parser<TokenInput<'a, T>, &'a T, E> {
+    move |input: &TokenInput<'a, T>| {
+        input
+            .split_first()
+            .filter(|(token, _)| token.kind() == &kind)
+            .ok_or_expected(input, Expectation::token(&kind))
+    }
+}
+
+/// A token matching `predicate`, described by `label` in errors.
+pub fn token_if<'a, T: fmt::Debug + 'a, E: ExpectError<TokenInput<'a, T>>>(
+    label: &'static str,
+    predicate: impl Fn(&T) -> bool,
+) -> impl Parser<TokenInput<'a, T>, &'a T, E> {
+    move |input: &TokenInput<'a, T>| {
+        input
+            .split_first()
+            .filter(|(token, _)| predicate(token))
+            .ok_or_expected(input, Expectation::label(label))
+    }
+}
+
+/// A token for which `f` gives an output, such as the value of a literal.
+pub fn token_map<'a, T: fmt::Debug + 'a, O, E: ExpectError<TokenInput<'a, T>>>(
+    label: &'static str,
+    f: impl Fn(&'a T) -> Option<O>,
+) -> impl Parser<TokenInput<'a, T>, O, E> {
+    move |input: &TokenInput<'a, T>| {
+        input
+            .split_first()
+            .and_then(|(token, remaining)| Some((f(token)?, remaining)))
+            .ok_or_expected(input, Expectation::label(label))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        combinators::many::many,
+        input::{span::Span, tokens::Spanned, Input, Slice},
+        parse::{Choice, Expected, ParserError, Sequence},
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Kind {
+        Let,
+        Ident,
+        Number,
+        Equals,
+        Semicolon,
+    }
+
+    fn lex(text: &Span) -> Vec<Spanned<Kind>> {
+        let mut tokens = Vec::new();
+        let mut offset = 0;
+        for word in text.as_str().split(' ') {
+            let kind = match word {
+                "let" => Kind::Let,
+                "=" => Kind::Equals,
+                ";" => Kind::Semicolon,
+                w if w.starts_with(|c: char| c.is_ascii_digit()) => Kind::Number,
+                _ => Kind::Ident,
+            };
+            tokens.push(Spanned::new(kind, text.slice(offset..offset + word.len())));
+            offset += word.len() + 1;
+        }
+        tokens
+    }
+
+    type Tokens<'a> = TokenInput<'a, Spanned<Kind>>;
+
+    fn value<'a>(input: &Tokens<'a>) -> ParserResult<Tokens<'a>, String, Expected<Tokens<'a>>> {
+        (
+            token_map("number", |t: &Spanned<Kind>| match t.kind {
+                Kind::Number => Some(t.span.as_str().to_string()),
+                _ => None,
+            }),
+            token(Kind::Ident).map(|t: &Spanned<Kind>| t.span.as_str().to_string()),
+        )
+            .or()
+            .parse(input)
+    }
+
+    #[test]
+    fn statements() {
+        let text = Span::anonymous("let a = 1 ; let b = a ;");
+        let tokens = lex(&text);
+        let statement = (
+            token(Kind::Let),
+            token(Kind::Ident),
+            token(Kind::Equals),
+            value,
+            token(Kind::Semicolon),
+        )
+            .and()
+            .map(|(_, name, _, value, _)| (name.span.as_str().to_string(), value));
+
+        let (statements, remaining) = many(statement).parse(&TokenInput::new(&tokens)).unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "a".to_string())
+            ]
+        );
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let text = Span::anonymous("let = 1");
+        let tokens = lex(&text);
+        let statement = (token(Kind::Let), token(Kind::Ident), any_token).and();
+
+        let Err(ParserError::Error(e)) = statement.parse(&TokenInput::new(&tokens)) else {
+            panic!("Expected an error")
+        };
+        let e: Expected<Tokens> = e;
+        assert_eq!(
+            e.to_string().split(", found").next(),
+            Some("expected Ident")
+        );
+        let e = e.map_input(|input| input.span().unwrap());
+        assert_eq!(e.input().position(), (1, 5));
+    }
+}
@@ -1,13 +1,33 @@
 use crate::{
     input::Input,
-    parse::{IntoStreamingResult, NotFound, Parser, ParserResult, StreamingResult},
-    util::conditional_transforms::{CompleteIf, EitherCompleteIf, OrNotFound},
+    parse::{
+        ExpectError, Expectation, IntoStreamingResult, Parser, ParserError, ParserResult,
+        StreamingResult,
+    },
+    util::conditional_transforms::{CompleteIf, EitherCompleteIf, OrExpected},
 };
 
-pub fn whitespace<I: Input>(s: &I) -> ParserResult<I, I> {
-    s.take_while(|c| c.is_whitespace()).ok_or_not_found()
+pub fn whitespace<I: Input, E: ExpectError<I>>(s: &I) -> ParserResult<I, I, E> {
+    s.take_while(|c| c.is_whitespace())
+        .ok_or_expected(s, Expectation::label("whitespace"))
 }
 
-pub fn whitespace_stream<I: Input>(s: &I) -> StreamingResult<I, I> {
+pub fn whitespace_stream<I: Input, E: ExpectError<I>>(s: &I) -> StreamingResult<I, I, E> {
     whitespace.parse(s).has_stopped()
 }
+
+fn is_blank(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Spaces & tabs, if any. This never fails, so it fits any error types.
+pub fn blank<I: Input, E, F>(s: &I) -> ParserResult<I, I, E, F> {
+    Ok(s.take_while(is_blank)
+        .unwrap_or_else(|| (s.empty(), s.clone())))
+}
+
+/// At least one space or tab.
+pub fn space<I: Input, E: ExpectError<I>, F>(s: &I) -> ParserResult<I, I, E, F> {
+    s.take_while(is_blank)
+        .ok_or_else(|| ParserError::Error(E::expected(s, Expectation::label("space"))))
+}
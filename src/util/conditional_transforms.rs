@@ -1,7 +1,10 @@
-use crate::{input::Input, parse::{
-    Incomplete, Never, NotFound, ParserError, ParserResult, StreamingError, StreamingOk,
-    StreamingResult,
-}};
+use crate::{
+    input::Input,
+    parse::{
+        ExpectError, Expectation, Incomplete, Never, NotFound, ParserError, ParserResult,
+        StreamingError, StreamingOk, StreamingResult,
+    },
+};
 
 pub trait OrNotFound<I, O> {
     fn ok_or_not_found(self) -> ParserResult<I, O>;
@@ -12,6 +15,23 @@ impl<I, O> OrNotFound<I, O> for Option<(O, I)> {
     }
 }
 
+pub trait OrExpected<I, O> {
+    fn ok_or_expected<E: ExpectError<I>>(
+        self,
+        input: &I,
+        expectation: Expectation,
+    ) -> ParserResult<I, O, E>;
+}
+impl<I, O> OrExpected<I, O> for Option<(O, I)> {
+    fn ok_or_expected<E: ExpectError<I>>(
+        self,
+        input: &I,
+        expectation: Expectation,
+    ) -> ParserResult<I, O, E> {
+        self.ok_or_else(|| ParserError::Error(E::expected(input, expectation)))
+    }
+}
+
 pub trait OrFail<I, O, F> {
     fn ok_or_fail(self) -> ParserResult<I, O, Never, F>;
 }
@@ -30,6 +50,23 @@ impl<I, O> StreamingOrNotFound<I, O> for Option<StreamingOk<I, O>> {
     }
 }
 
+pub trait StreamingOrExpected<I, O> {
+    fn ok_or_expected<E: ExpectError<I>>(
+        self,
+        input: &I,
+        expectation: Expectation,
+    ) -> StreamingResult<I, O, E>;
+}
+impl<I, O> StreamingOrExpected<I, O> for Option<StreamingOk<I, O>> {
+    fn ok_or_expected<E: ExpectError<I>>(
+        self,
+        input: &I,
+        expectation: Expectation,
+    ) -> StreamingResult<I, O, E> {
+        self.ok_or_else(|| StreamingError::Error(E::expected(input, expectation)))
+    }
+}
+
 pub trait OrIncomplete<I, O> {
     fn ok_or_incomplete(self) -> StreamingResult<I, O, Never, Incomplete>;
 }
@@ -39,6 +76,7 @@ impl<I, O> OrIncomplete<I, O> for Option<StreamingOk<I, O>> {
     }
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait CompleteIf<I, O> {
     fn as_complete_if<Func: Fn(&O, &I) -> bool>(self, f: Func) -> StreamingOk<I, O>;
     fn as_complete(self) -> StreamingOk<I, O>
@@ -79,6 +117,7 @@ impl<I, O> CompleteIf<I, O> for StreamingOk<I, O> {
     }
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait MaybeCompleteIf<I, O> {
     fn as_complete_if<Func: Fn(&O, &I) -> bool>(self, f: Func) -> Option<StreamingOk<I, O>>;
     fn as_complete(self) -> Option<StreamingOk<I, O>>
@@ -107,6 +146,7 @@ impl<I, O> MaybeCompleteIf<I, O> for Option<(O, I)> {
     }
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait EitherCompleteIf<I, O, E, F> {
     fn as_complete_if<Func: Fn(&O, &I) -> bool>(self, f: Func) -> StreamingResult<I, O, E, F>;
     fn as_complete(self) -> StreamingResult<I, O, E, F>